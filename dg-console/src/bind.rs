@@ -0,0 +1,303 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use dg_runtime::input::{InputEvent, Key, MouseButton};
+
+use crate::{
+    command::{self, Value, ValueKind},
+    registry::{Parameter, Signature},
+    Console, Error,
+};
+
+/// Anything a command can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Button {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+impl Button {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Key::from_name(name)
+            .map(Button::Key)
+            .or_else(|| MouseButton::from_name(name).map(Button::Mouse))
+    }
+}
+
+impl Display for Button {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Button::Key(key) => write!(f, "{}", key),
+            Button::Mouse(button) => write!(f, "{}", button),
+        }
+    }
+}
+
+/// Commands bound to buttons.
+///
+/// Following the usual convention a command starting with `+` is a held command, releasing its
+/// button runs the same command with a `-` instead.
+#[derive(Default)]
+pub struct Bindings {
+    bindings: BTreeMap<Button, String>,
+    /// Release commands of the buttons held down, decided when they were pressed.
+    held: BTreeMap<Button, String>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `button` to a `;` separated list of commands, replacing any previous binding.
+    pub fn bind(&mut self, button: Button, line: &str) -> Result<(), Error> {
+        command::parse_commands(line)?;
+        self.bindings.insert(button, line.to_owned());
+        Ok(())
+    }
+
+    pub fn unbind(&mut self, button: Button) -> Option<String> {
+        self.bindings.remove(&button)
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    pub fn get(&self, button: Button) -> Option<&str> {
+        self.bindings.get(&button).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Button, &str)> {
+        self.bindings.iter().map(|(b, l)| (*b, l.as_str()))
+    }
+
+    /// Returns the line to run for a press, repeated presses of a held button are ignored.
+    pub fn press(&mut self, button: Button) -> Option<String> {
+        if self.held.contains_key(&button) {
+            return None;
+        }
+
+        let line = self.bindings.get(&button)?.clone();
+        self.held.insert(button, release_line(&line));
+        Some(line)
+    }
+
+    /// Returns the line to run for a release, if the press started any held commands.
+    pub fn release(&mut self, button: Button) -> Option<String> {
+        self.held.remove(&button).filter(|line| !line.is_empty())
+    }
+
+    /// Writes every binding as a `bind` command which can be run back through the console.
+    pub fn save(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|(button, line)| {
+                let button = Value::String(button.to_string());
+                format!("bind {} {}\n", button, Value::String(line.clone()))
+            })
+            .collect()
+    }
+}
+
+/// `+forward; +jump 2; say hi` is released with `-forward; -jump`.
+fn release_line(line: &str) -> String {
+    let Ok(commands) = command::parse_commands(line) else {
+        return String::new();
+    };
+
+    commands
+        .iter()
+        .filter_map(|c| c.name().strip_prefix('+'))
+        .map(|name| format!("-{}", name))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl Console {
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    pub fn bindings_mut(&mut self) -> &mut Bindings {
+        &mut self.bindings
+    }
+
+    /// Queues the commands bound to the button of `event`, they run on the next [`Console::update`].
+    pub fn handle_input(&mut self, event: &InputEvent) {
+        let line = match *event {
+            InputEvent::KeyPressed(key) => self.bindings.press(Button::Key(key)),
+            InputEvent::KeyReleased(key) => self.bindings.release(Button::Key(key)),
+            InputEvent::MouseButtonPressed(button) => self.bindings.press(Button::Mouse(button)),
+            InputEvent::MouseButtonReleased(button) => self.bindings.release(Button::Mouse(button)),
+        };
+        self.pending.extend(line);
+    }
+}
+
+fn button(values: &[Value]) -> Result<Button, Error> {
+    let name = values[0].as_str().unwrap();
+    Button::from_name(name).ok_or_else(|| Error::CommandFailed(format!("unknown button `{}`", name)))
+}
+
+pub(crate) fn register_builtins(console: &mut Console) {
+    console.register(
+        "bind",
+        Signature::from_parameters(vec![
+            Parameter::named("button", ValueKind::String),
+            Parameter::named("commands", ValueKind::String).with_default(Value::String(String::new())),
+        ])
+        .with_description("Binds a button to a `;` separated list of commands, or prints its binding"),
+        |console, values| {
+            let button = button(values)?;
+            match values[1].as_str().unwrap() {
+                "" => {
+                    let line = match console.bindings.get(button) {
+                        Some(line) => format!("{} = {}", button, Value::String(line.to_owned())),
+                        None => format!("{} is not bound", button),
+                    };
+                    console.print(line);
+                    Ok(())
+                }
+                line => console.bindings.bind(button, line),
+            }
+        },
+    );
+
+    console.register(
+        "unbind",
+        Signature::from_parameters(vec![Parameter::named("button", ValueKind::String)])
+            .with_description("Removes the binding of a button"),
+        |console, values| {
+            let button = button(values)?;
+            console
+                .bindings
+                .unbind(button)
+                .map(|_| ())
+                .ok_or_else(|| Error::CommandFailed(format!("`{}` is not bound", button)))
+        },
+    );
+
+    console.register(
+        "unbindall",
+        Signature::new(&[]).with_description("Removes every binding"),
+        |console, _| {
+            console.bindings.clear();
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use dg_runtime::input::{InputEvent, Key, MouseButton};
+
+    use super::{Bindings, Button};
+    use crate::{registry::Signature, Console};
+
+    fn recording_console() -> (Console, Rc<RefCell<Vec<String>>>) {
+        let mut console = Console::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        for name in ["+forward", "-forward", "+attack", "-attack", "jump"] {
+            let l = log.clone();
+            console.register(name, Signature::new(&[]), move |_, _| {
+                l.borrow_mut().push(name.to_owned());
+                Ok(())
+            });
+        }
+        (console, log)
+    }
+
+    #[test]
+    fn test_bind() {
+        let (mut console, log) = recording_console();
+        console.run("bind w +forward; bind space \"jump; jump\"").unwrap();
+        console.run("bind MOUSE1 \"+attack; +forward\"").unwrap();
+
+        console.handle_input(&InputEvent::KeyPressed(Key::W));
+        console.handle_input(&InputEvent::KeyPressed(Key::W));
+        console.handle_input(&InputEvent::KeyPressed(Key::Space));
+        console.handle_input(&InputEvent::KeyReleased(Key::Space));
+        assert!(log.borrow().is_empty());
+        console.update();
+        assert_eq!(*log.borrow(), vec!["+forward", "jump", "jump"]);
+
+        log.borrow_mut().clear();
+        console.handle_input(&InputEvent::KeyReleased(Key::W));
+        console.handle_input(&InputEvent::MouseButtonPressed(MouseButton::Left));
+        console.handle_input(&InputEvent::MouseButtonReleased(MouseButton::Left));
+        console.update();
+        assert_eq!(
+            *log.borrow(),
+            vec!["-forward", "+attack", "+forward", "-attack", "-forward"]
+        );
+
+        console.run("bind w").unwrap();
+        assert_eq!(console.drain_output(), vec!["w = \"+forward\""]);
+        console.run("unbind w").unwrap();
+        assert!(console.run("unbind w").is_err());
+        assert!(console.run("bind nope jump").is_err());
+        assert!(console.run("bind q \"jump [\"").is_err());
+    }
+
+    #[test]
+    fn test_bind_wait() {
+        let (mut console, log) = recording_console();
+        console.run("bind space \"jump; wait; jump\"").unwrap();
+        console.run("bind MOUSE1 \"jump; wait 3; jump\"").unwrap();
+
+        console.handle_input(&InputEvent::KeyPressed(Key::Space));
+        console.update();
+        assert_eq!(log.borrow().len(), 1);
+        console.update();
+        assert_eq!(log.borrow().len(), 2);
+
+        log.borrow_mut().clear();
+        console.handle_input(&InputEvent::MouseButtonPressed(MouseButton::Left));
+        console.update();
+        for _ in 0..2 {
+            console.update();
+            assert_eq!(log.borrow().len(), 1);
+        }
+        console.update();
+        assert_eq!(log.borrow().len(), 2);
+        assert!(!console.is_waiting());
+    }
+
+    #[test]
+    fn test_bind_errors_logged() {
+        let (mut console, _) = recording_console();
+        console.run("bind f1 missing").unwrap();
+        console.handle_input(&InputEvent::KeyPressed(Key::F1));
+        console.update();
+        assert_eq!(console.drain_output(), vec!["error: unknown command `missing`"]);
+    }
+
+    #[test]
+    fn test_save() {
+        let mut bindings = Bindings::new();
+        bindings.bind(Button::Key(Key::Grave), "toggle_console").unwrap();
+        bindings
+            .bind(Button::Mouse(MouseButton::Right), "+zoom; say \"zoom\"")
+            .unwrap();
+        let saved = bindings.save();
+        assert_eq!(
+            saved,
+            "bind \"grave\" \"toggle_console\"\nbind \"mouse2\" \"+zoom; say \\\"zoom\\\"\"\n"
+        );
+
+        let mut console = Console::new();
+        console.exec_str("bindings.cfg", &saved).unwrap();
+        assert_eq!(console.bindings().save(), saved);
+
+        // Buttons named like numbers come back as the same button.
+        let mut bindings = Bindings::new();
+        bindings.bind(Button::Key(Key::Num1), "jump").unwrap();
+        let saved = bindings.save();
+        assert_eq!(saved, "bind \"1\" \"jump\"\n");
+        let mut console = Console::new();
+        console.exec_str("bindings.cfg", &saved).unwrap();
+        assert_eq!(console.bindings().get(Button::Key(Key::Num1)), Some("jump"));
+    }
+}
@@ -1,40 +1,238 @@
-// use core::range::Range;
+use core::range::Range;
 use std::str::Chars;
 
-#[derive(Debug, PartialEq, PartialOrd, Clone)]
+use dg_math::vector::{Vec2, Vec3, Vec4};
+
+use crate::{
+    expr::{BinaryOp, Expr},
+    Error, ParseErrorKind,
+};
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Command {
     name: String,
-    values: Vec<Value>,
+    arguments: Vec<Expr>,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Clone)]
+impl Command {
+    pub fn new(name: &str, arguments: Vec<Expr>) -> Self {
+        Self {
+            name: name.to_owned(),
+            arguments,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arguments(&self) -> &[Expr] {
+        &self.arguments
+    }
+
+    /// Evaluates the arguments, looking up `$name` references with `variable`.
+    pub fn evaluate<F>(&self, variable: F) -> Result<Vec<Value>, Error>
+    where
+        F: Fn(&str) -> Option<Value>,
+    {
+        self.arguments.iter().map(|a| a.evaluate(&variable)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Value {
     Integer(i64),
     Float(f64),
     Boolean(bool),
     String(String),
     Array(Vec<Value>),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
 }
 
-impl Value {
-    fn same_kind(&self, o: &Value) -> bool {
-        match (self, o) {
-            (Value::Integer(_), Value::Integer(_)) => true,
-            (Value::Float(_), Value::Float(_)) => true,
-            (Value::Boolean(_), Value::Boolean(_)) => true,
-            (Value::String(_), Value::String(_)) => true,
-            (Value::Array(_), Value::Array(_)) => true,
+// dg-math vectors only offer approximate equality, values compare their components exactly.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Vec2(a), Value::Vec2(b)) => a.x == b.x && a.y == b.y,
+            (Value::Vec3(a), Value::Vec3(b)) => a.x == b.x && a.y == b.y && a.z == b.z,
+            (Value::Vec4(a), Value::Vec4(b)) => a.x == b.x && a.y == b.y && a.z == b.z && a.w == b.w,
             _ => false,
         }
     }
 }
 
-pub fn parse_command(s: &str) -> Option<Command> {
-    Parser::new(s).parse_command()
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ValueKind {
+    Integer,
+    Float,
+    Boolean,
+    String,
+    Array,
+    Vec2,
+    Vec3,
+    Vec4,
+}
+
+impl std::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ValueKind::Integer => "integer",
+            ValueKind::Float => "float",
+            ValueKind::Boolean => "boolean",
+            ValueKind::String => "string",
+            ValueKind::Array => "array",
+            ValueKind::Vec2 => "vec2",
+            ValueKind::Vec3 => "vec3",
+            ValueKind::Vec4 => "vec4",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Value {
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Integer(_) => ValueKind::Integer,
+            Value::Float(_) => ValueKind::Float,
+            Value::Boolean(_) => ValueKind::Boolean,
+            Value::String(_) => ValueKind::String,
+            Value::Array(_) => ValueKind::Array,
+            Value::Vec2(_) => ValueKind::Vec2,
+            Value::Vec3(_) => ValueKind::Vec3,
+            Value::Vec4(_) => ValueKind::Vec4,
+        }
+    }
+
+    /// Converts the value to `kind`, only integers are widened to floats.
+    pub fn coerce(&self, kind: ValueKind) -> Option<Value> {
+        match (self, kind) {
+            (Value::Integer(i), ValueKind::Float) => Some(Value::Float(*i as f64)),
+            (v, kind) if v.kind() == kind => Some(v.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            Value::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec2(&self) -> Option<Vec2> {
+        match self {
+            Value::Vec2(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec3(&self) -> Option<Vec3> {
+        match self {
+            Value::Vec3(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec4(&self) -> Option<Vec4> {
+        match self {
+            Value::Vec4(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(v) => write!(f, "{:?}", v),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "\"{}\"", escape(s)),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Value::Vec2(v) => write!(f, "({:?}, {:?})", v.x, v.y),
+            Value::Vec3(v) => write!(f, "({:?}, {:?}, {:?})", v.x, v.y, v.z),
+            Value::Vec4(v) => write!(f, "({:?}, {:?}, {:?}, {:?})", v.x, v.y, v.z, v.w),
+        }
+    }
+}
+
+pub fn parse_command(s: &str) -> Result<Command, Error> {
+    let mut parser = Parser::new(s);
+    let command = parser.parse_command()?;
+    match &parser.token {
+        Some(token) => Err(Parser::error(token.range, ParseErrorKind::UnexpectedToken)),
+        None => Ok(command),
+    }
+}
+
+/// Parses a line of `;` separated commands, empty commands are skipped.
+pub fn parse_commands(s: &str) -> Result<Vec<Command>, Error> {
+    Parser::new(s).parse_commands()
 }
 
 #[derive(Debug)]
-enum Base {
+pub(crate) enum Base {
     Decimal,
     Hexadecimal,
     Binary,
@@ -58,93 +256,55 @@ impl Base {
 }
 
 #[derive(Debug)]
-enum LiteralKind {
+pub(crate) enum LiteralKind {
     Int(Base),
     Float,
-    String,
+    String { terminated: bool },
 }
 
 #[derive(Debug)]
-enum TokenKind {
+pub(crate) enum TokenKind {
     Ident,
+    /// `$name`, a reference to a cvar.
+    Variable,
+    Operator(BinaryOp),
     Whitespace,
     Literal(LiteralKind),
     OpeningBracket,
     ClosingBracket,
+    OpeningParen,
+    ClosingParen,
     Comma,
+    Semicolon,
+    Comment,
     Unknown,
 }
 
 #[derive(Debug)]
-struct Token<'a> {
-    kind: TokenKind,
-    // range: Range<usize>,
-    s: &'a str,
+pub(crate) struct Token<'a> {
+    pub(crate) kind: TokenKind,
+    pub(crate) range: Range<usize>,
+    pub(crate) s: &'a str,
 }
 
 impl<'a> Token<'a> {
-    fn is_ident(&self) -> bool {
-        match self.kind {
-            TokenKind::Ident => true,
-            _ => false,
-        }
-    }
-
-    fn is_whitespace(&self) -> bool {
-        match self.kind {
-            TokenKind::Whitespace => true,
-            _ => false,
-        }
-    }
-
-    fn is_integer(&self) -> bool {
-        match self.kind {
-            TokenKind::Literal(LiteralKind::Int(_)) => true,
-            _ => false,
-        }
+    /// Whitespace and comments, neither of which carry meaning.
+    pub(crate) fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
     }
 
-    fn is_float(&self) -> bool {
-        match self.kind {
-            TokenKind::Literal(LiteralKind::Float) => true,
-            _ => false,
-        }
+    fn is_semicolon(&self) -> bool {
+        matches!(self.kind, TokenKind::Semicolon)
     }
-
-    fn is_string(&self) -> bool {
-        match self.kind {
-            TokenKind::Literal(LiteralKind::String) => true,
-            _ => false,
-        }
-    }
-
-    fn is_opening_bracket(&self) -> bool {
-        match self.kind {
-            TokenKind::OpeningBracket => true,
-            _ => false,
-        }
-    }
-
-    fn is_closing_bracket(&self) -> bool {
-        match self.kind {
-            TokenKind::ClosingBracket => true,
-            _ => false,
-        }
-    }
-
-    // fn is_comma(&self) -> bool {
-    //     match self.kind {
-    //         TokenKind::Comma => true,
-    //         _ => false,
-    //     }
-    // }
 }
 
 #[derive(Clone)]
-struct Tokenizer<'a> {
+pub(crate) struct Tokenizer<'a> {
     s: &'a str,
     chars: Chars<'a>,
     current: usize,
+    /// The last token that wasn't trivia can be divided, a `/` after anything else starts a path.
+    after_operand: bool,
 }
 
 impl<'a> Tokenizer<'a> {
@@ -153,6 +313,7 @@ impl<'a> Tokenizer<'a> {
             s,
             chars: s.chars(),
             current: 0,
+            after_operand: false,
         }
     }
 
@@ -163,27 +324,64 @@ impl<'a> Tokenizer<'a> {
         let kind = match next {
             '[' => TokenKind::OpeningBracket,
             ']' => TokenKind::ClosingBracket,
+            '(' => TokenKind::OpeningParen,
+            ')' => TokenKind::ClosingParen,
             ',' => TokenKind::Comma,
+            '-' if self.look_ahead().is_some_and(|c| c.is_ascii_digit()) => {
+                self.consume_number(next)
+            }
+            // Held commands such as `+forward` and `-forward`.
+            '+' | '-' if self.look_ahead().is_some_and(Self::is_ident_start) => self.consume_ident(),
+            '$' if self.look_ahead().is_some_and(Self::is_ident_start) => {
+                self.consume_while(Self::is_word);
+                TokenKind::Variable
+            }
+            ';' => TokenKind::Semicolon,
+            '/' if self.look_ahead() == Some('/') => self.consume_comment(),
+            // Paths like `/etc/game.cfg` and `../game.cfg`.
+            '.' if matches!(self.look_ahead(), Some('.' | '/')) => self.consume_ident(),
+            '/' if !self.after_operand && self.look_ahead().is_some_and(Self::is_word) => {
+                self.consume_ident()
+            }
+            '+' => TokenKind::Operator(BinaryOp::Add),
+            '-' => TokenKind::Operator(BinaryOp::Sub),
+            '*' => TokenKind::Operator(BinaryOp::Mul),
+            '/' => TokenKind::Operator(BinaryOp::Div),
+            '%' => TokenKind::Operator(BinaryOp::Rem),
+            '=' if self.look_ahead() == Some('=') => self.consume_operator(BinaryOp::Eq),
+            '!' if self.look_ahead() == Some('=') => self.consume_operator(BinaryOp::Ne),
+            '<' if self.look_ahead() == Some('=') => self.consume_operator(BinaryOp::Le),
+            '>' if self.look_ahead() == Some('=') => self.consume_operator(BinaryOp::Ge),
+            '<' => TokenKind::Operator(BinaryOp::Lt),
+            '>' => TokenKind::Operator(BinaryOp::Gt),
             c if Self::is_whitespace(c) => self.consume_whitespace(),
             c if Self::is_ident_start(c) => self.consume_ident(),
-            c if c.is_numeric() => self.consume_number(c),
+            c if c.is_ascii_digit() => self.consume_number(c),
             '"' => self.consume_string(),
             _ => TokenKind::Unknown,
         };
 
         let end = self.current;
+        if !matches!(kind, TokenKind::Whitespace | TokenKind::Comment) {
+            self.after_operand = matches!(
+                kind,
+                TokenKind::Variable
+                    | TokenKind::Literal(LiteralKind::Int(_) | LiteralKind::Float)
+                    | TokenKind::ClosingParen
+            );
+        }
 
         Some(Token {
             kind,
-            // range: Range { start, end },
+            range: Range { start, end },
             s: &self.s[start..end],
         })
     }
 
     fn next_char(&mut self) -> Option<char> {
         let c = self.chars.next();
-        if c.is_some() {
-            self.current += 1;
+        if let Some(c) = c {
+            self.current += c.len_utf8();
         }
         c
     }
@@ -192,36 +390,29 @@ impl<'a> Tokenizer<'a> {
         self.chars.clone().next()
     }
 
+    fn look_ahead_nth(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n)
+    }
+
     fn consume_whitespace(&mut self) -> TokenKind {
         self.consume_while(Self::is_whitespace);
         TokenKind::Whitespace
     }
 
     fn is_whitespace(c: char) -> bool {
-        match c {
-            ' ' => true,
-            '\n' => true,
-            _ => false,
-        }
+        matches!(c, ' ' | '\t' | '\r' | '\n')
     }
 
     fn is_ident_start(c: char) -> bool {
-        match c {
-            'a'..='z' => true,
-            'A'..='Z' => true,
-            '_' => true,
-            _ => false,
-        }
+        matches!(c, 'a'..='z' | 'A'..='Z' | '_')
     }
 
     fn is_ident(c: char) -> bool {
-        match c {
-            'a'..='z' => true,
-            'A'..='Z' => true,
-            '0'..='9' => true,
-            '_' => true,
-            _ => false,
-        }
+        matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_')
+    }
+
+    fn is_word(c: char) -> bool {
+        Self::is_ident(c) || c == '.'
     }
 
     fn consume_while<P>(&mut self, p: P)
@@ -237,12 +428,37 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Identifiers double as bare words, so dots and slashes are allowed for paths like
+    /// `configs/autoexec.cfg`.
     fn consume_ident(&mut self) -> TokenKind {
-        self.consume_while(Self::is_ident);
+        loop {
+            match self.look_ahead() {
+                Some(c) if Self::is_word(c) => {}
+                Some('/') if self.look_ahead_nth(1).is_some_and(Self::is_word) => {}
+                _ => break,
+            }
+            self.next_char();
+        }
         TokenKind::Ident
     }
 
+    /// Consumes the second character of a two character operator.
+    fn consume_operator(&mut self, op: BinaryOp) -> TokenKind {
+        self.next_char();
+        TokenKind::Operator(op)
+    }
+
+    fn consume_comment(&mut self) -> TokenKind {
+        self.consume_while(|c| c != '\n');
+        TokenKind::Comment
+    }
+
     fn consume_number(&mut self, start: char) -> TokenKind {
+        let start = match start {
+            '-' => self.next_char().expect("sign is followed by a digit"),
+            c => c,
+        };
+
         let base = if start == '0' {
             match self.look_ahead() {
                 Some('x') => {
@@ -259,34 +475,36 @@ impl<'a> Tokenizer<'a> {
             Base::Decimal
         };
 
-        match base {
-            Base::Decimal => self.consume_while(|c| match c {
-                '0'..='9' => true,
-                _ => false,
-            }),
-            Base::Hexadecimal => self.consume_while(|c| match c {
-                '0'..='9' => true,
-                'a'..='f' => true,
-                'A'..='F' => true,
-                _ => false,
-            }),
-            Base::Binary => self.consume_while(|c| match c {
-                '0'..='1' => true,
-                _ => false,
-            }),
-        }
+        let mut float = false;
+        if let Base::Decimal = base {
+            self.consume_while(|c| c.is_ascii_digit());
 
-        if self.look_ahead() == Some('.')
-            && match base {
-                Base::Decimal => true,
-                _ => false,
+            if self.look_ahead() == Some('.') {
+                self.next_char();
+                self.consume_while(|c| c.is_ascii_digit());
+                float = true;
             }
-        {
-            self.next_token();
-            self.consume_while(|c| match c {
-                '0'..='9' => true,
+
+            let exponent = match (self.look_ahead(), self.look_ahead_nth(1), self.look_ahead_nth(2)) {
+                (Some('e' | 'E'), Some(c), _) if c.is_ascii_digit() => true,
+                (Some('e' | 'E'), Some('-' | '+'), Some(c)) if c.is_ascii_digit() => true,
                 _ => false,
-            });
+            };
+            if exponent {
+                self.next_char();
+                if matches!(self.look_ahead(), Some('-' | '+')) {
+                    self.next_char();
+                }
+                self.consume_while(|c| c.is_ascii_digit());
+                float = true;
+            }
+        }
+
+        // Trailing identifier characters are swallowed into the literal so `12abc` or `0b102`
+        // is reported as a single invalid number instead of two unrelated tokens.
+        self.consume_while(|c| Self::is_ident(c) || c == '.');
+
+        if float {
             TokenKind::Literal(LiteralKind::Float)
         } else {
             TokenKind::Literal(LiteralKind::Int(base))
@@ -294,23 +512,16 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn consume_string(&mut self) -> TokenKind {
-        while let Some(c) = self.look_ahead() {
-            if match c {
-                '\\' if self.look_ahead() == Some('"') || self.look_ahead() == Some('\\') => {
+        while let Some(c) = self.next_char() {
+            match c {
+                '\\' => {
                     self.next_char();
-                    true
                 }
-                '"' => false,
-                _ => true,
-            } {
-                self.next_char();
-            } else {
-                // To include the last doublequote
-                self.next_char();
-                break;
+                '"' => return TokenKind::Literal(LiteralKind::String { terminated: true }),
+                _ => {}
             }
         }
-        TokenKind::Literal(LiteralKind::String)
+        TokenKind::Literal(LiteralKind::String { terminated: false })
     }
 }
 
@@ -322,169 +533,386 @@ impl<'a> Iterator for Tokenizer<'a> {
     }
 }
 
+/// Deepest arrays, groups and negations are nested in an argument, the parser recurses into
+/// each level.
+pub const MAX_NESTING: usize = 64;
+
 pub struct Parser<'a> {
+    src: &'a str,
     tokenizer: Tokenizer<'a>,
-    token: Token<'a>,
+    token: Option<Token<'a>>,
+    /// How many brackets or parens enclose the current token, whitespace only separates
+    /// arguments outside of them.
+    depth: usize,
+    /// How many operands enclose the current one, up to [`MAX_NESTING`].
+    nesting: usize,
 }
 
 impl<'a> Parser<'a> {
     fn new(src: &'a str) -> Self {
         let mut tokenizer = Tokenizer::new(src);
-        let token = tokenizer.next().expect("tokenizer has no tokens to parse");
-        Self { tokenizer, token }
+        let token = tokenizer.next();
+        Self {
+            src,
+            tokenizer,
+            token,
+            depth: 0,
+            nesting: 0,
+        }
+    }
+
+    fn parse_commands(&mut self) -> Result<Vec<Command>, Error> {
+        let mut commands = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match &self.token {
+                None => break,
+                Some(token) if token.is_semicolon() => self.next(),
+                Some(_) => {
+                    commands.push(self.parse_command()?);
+                    match &self.token {
+                        None => break,
+                        Some(token) if token.is_semicolon() => self.next(),
+                        Some(token) => {
+                            return Err(Self::error(token.range, ParseErrorKind::UnexpectedToken))
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(commands)
     }
 
-    fn parse_command(&mut self) -> Option<Command> {
+    /// Parses a single command, stopping at the end of input or a `;`.
+    fn parse_command(&mut self) -> Result<Command, Error> {
+        self.skip_whitespace();
         let name = self.parse_ident()?;
         self.next();
-        let mut values = Vec::new();
 
-        loop {
-            if !self.next() {
+        let mut arguments = Vec::new();
+        while let Some(token) = &self.token {
+            if token.is_semicolon() {
                 break;
             }
-
-            if self.token.is_whitespace() {
-                continue;
+            if !token.is_trivia() {
+                return Err(Self::error(token.range, ParseErrorKind::UnexpectedToken));
             }
 
-            if let Some(value) = self.parse_value() {
-                values.push(value);
-            } else {
-                break;
+            self.skip_whitespace();
+            if self.token.as_ref().is_some_and(|t| !t.is_semicolon()) {
+                let argument = self.parse_expression()?;
+                // Arguments without `$name` references are evaluated once, here.
+                let argument = match argument {
+                    Expr::Value(_) => argument,
+                    e if e.is_constant() => Expr::Value(e.evaluate(&|_| None)?),
+                    e => e,
+                };
+                arguments.push(argument);
             }
         }
 
-        Some(Command { name, values })
+        Ok(Command { name, arguments })
     }
 
-    fn parse_ident(&mut self) -> Option<String> {
-        if !self.token.is_ident() {
-            return None;
+    fn parse_ident(&mut self) -> Result<String, Error> {
+        let token = self.current()?;
+        match token.kind {
+            TokenKind::Ident => Ok(token.s.to_owned()),
+            _ => Err(Self::error(token.range, ParseErrorKind::ExpectedCommandName)),
         }
+    }
 
-        Some(self.token.s.to_owned())
+    fn parse_expression(&mut self) -> Result<Expr, Error> {
+        self.parse_binary(0)
     }
 
-    fn parse_integer(&mut self) -> Option<Value> {
-        if !self.token.is_integer() {
-            return None;
-        }
+    /// Parses operators binding at least as tightly as `min_precedence`, left associative.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, Error> {
+        let mut left = self.parse_unary()?;
 
-        let v = match &self.token.kind {
-            TokenKind::Literal(LiteralKind::Int(base)) => {
-                i64::from_str_radix(&self.token.s[base.offset()..], base.radix()).unwrap()
+        loop {
+            if self.depth > 0 {
+                self.skip_whitespace();
             }
-            _ => unreachable!(),
-        };
+            let Some(token) = &self.token else {
+                break;
+            };
+
+            let (op, signed) = match token.kind {
+                TokenKind::Operator(op) => (op, false),
+                // `$height-5` lexes as `$height` and `-5`, adding the negative literal is the same.
+                TokenKind::Literal(LiteralKind::Int(_) | LiteralKind::Float)
+                    if token.s.starts_with('-') =>
+                {
+                    (BinaryOp::Add, true)
+                }
+                _ => break,
+            };
 
-        Some(Value::Integer(v))
-    }
+            let precedence = Self::precedence(op);
+            if precedence < min_precedence {
+                break;
+            }
 
-    fn parse_float(&mut self) -> Option<Value> {
-        if !self.token.is_float() {
-            return None;
+            if !signed {
+                self.next();
+                if self.depth > 0 {
+                    self.skip_whitespace();
+                }
+            }
+            let right = self.parse_binary(precedence + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
         }
 
-        Some(Value::Float(self.token.s.parse().unwrap()))
+        Ok(left)
     }
 
-    fn parse_bool(&mut self) -> Option<Value> {
-        if !self.token.is_ident() {
-            return None;
+    fn precedence(op: BinaryOp) -> u8 {
+        match op {
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 1,
+            BinaryOp::Add | BinaryOp::Sub => 2,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 3,
         }
+    }
 
-        match self.token.s.to_lowercase().as_str() {
-            "true" => Some(Value::Boolean(true)),
-            "false" => Some(Value::Boolean(false)),
-            _ => None,
+    fn parse_unary(&mut self) -> Result<Expr, Error> {
+        let token = self.current()?;
+        if self.nesting == MAX_NESTING {
+            return Err(Self::error(token.range, ParseErrorKind::TooDeep));
         }
+        self.nesting += 1;
+        let expr = self.parse_operand();
+        self.nesting -= 1;
+        expr
     }
 
-    fn parse_string(&mut self) -> Option<Value> {
-        if !self.token.is_string() {
-            return None;
+    fn parse_operand(&mut self) -> Result<Expr, Error> {
+        let token = self.current()?;
+        match token.kind {
+            TokenKind::Operator(BinaryOp::Sub) => {
+                self.next();
+                Ok(Expr::Negate(Box::new(self.parse_unary()?)))
+            }
+            TokenKind::Variable => {
+                let name = token.s[1..].to_owned();
+                self.next();
+                Ok(Expr::Variable(name))
+            }
+            TokenKind::OpeningBracket => self.parse_array(),
+            TokenKind::OpeningParen => self.parse_paren(),
+            _ => self.parse_value().map(Expr::Value),
         }
+    }
 
-        let end = self.token.s.len() - 1;
-        Some(Value::String(self.token.s[1..end].to_owned()))
+    /// Parses the literal at the current token and moves past it.
+    fn parse_value(&mut self) -> Result<Value, Error> {
+        let token = self.current()?;
+        let value = match &token.kind {
+            TokenKind::Literal(LiteralKind::Int(base)) => {
+                let (sign, digits) = match token.s.strip_prefix('-') {
+                    Some(digits) => ("-", digits),
+                    None => ("", token.s),
+                };
+                let digits = format!("{}{}", sign, &digits[base.offset()..]);
+                i64::from_str_radix(&digits, base.radix())
+                    .map(Value::Integer)
+                    .map_err(|_| Self::error(token.range, ParseErrorKind::InvalidNumber))?
+            }
+            TokenKind::Literal(LiteralKind::Float) => token
+                .s
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| Self::error(token.range, ParseErrorKind::InvalidNumber))?,
+            TokenKind::Literal(LiteralKind::String { terminated: true }) => {
+                Value::String(Self::unescape(token)?)
+            }
+            TokenKind::Literal(LiteralKind::String { terminated: false }) => {
+                return Err(Self::error(token.range, ParseErrorKind::UnterminatedString));
+            }
+            TokenKind::Ident => match token.s.to_lowercase().as_str() {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                // Any other bare word is a string, as in `exec autoexec.cfg`.
+                _ => Value::String(token.s.to_owned()),
+            },
+            TokenKind::Unknown => {
+                return Err(Self::error(token.range, ParseErrorKind::UnknownToken));
+            }
+            _ => return Err(Self::error(token.range, ParseErrorKind::UnexpectedToken)),
+        };
+        self.next();
+
+        Ok(value)
     }
 
-    fn parse_value(&mut self) -> Option<Value> {
-        self.parse_float()
-            .or(self.parse_integer())
-            .or(self.parse_bool())
-            .or(self.parse_string())
-            .or(self.parse_array())
+    /// Decodes the escapes of a terminated string literal, excluding its quotes.
+    fn unescape(token: &Token) -> Result<String, Error> {
+        let inner = &token.s[1..token.s.len() - 1];
+        let mut s = String::with_capacity(inner.len());
+        let mut chars = inner.char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            if c != '\\' {
+                s.push(c);
+                continue;
+            }
+
+            // Offset by one for the opening quote.
+            let start = token.range.start + 1 + i;
+            let invalid = |end: usize| {
+                Self::error(
+                    Range {
+                        start,
+                        end: token.range.start + 1 + end,
+                    },
+                    ParseErrorKind::InvalidEscape,
+                )
+            };
+
+            let decoded = match chars.next() {
+                Some((_, 'n')) => '\n',
+                Some((_, 't')) => '\t',
+                Some((_, 'r')) => '\r',
+                Some((_, '0')) => '\0',
+                Some((_, '\\')) => '\\',
+                Some((_, '"')) => '"',
+                Some((_, '\'')) => '\'',
+                Some((j, 'u')) => {
+                    let rest = &inner[j + 1..];
+                    let close = rest.find('}').filter(|_| rest.starts_with('{'));
+                    let close = close.ok_or_else(|| invalid(j + 1))?;
+                    let end = j + 1 + close + 1;
+                    let c = u32::from_str_radix(&rest[1..close], 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| invalid(end))?;
+                    // Skip past the closing brace.
+                    for _ in 0..close + 1 {
+                        chars.next();
+                    }
+                    c
+                }
+                Some((j, c)) => return Err(invalid(j + c.len_utf8())),
+                None => return Err(invalid(inner.len())),
+            };
+            s.push(decoded);
+        }
+
+        Ok(s)
+    }
+
+    fn parse_array(&mut self) -> Result<Expr, Error> {
+        let (items, _) = self.parse_sequence(|kind| matches!(kind, TokenKind::ClosingBracket))?;
+        Ok(Expr::Array(items))
     }
 
-    fn parse_array(&mut self) -> Option<Value> {
-        if !self.token.is_opening_bracket() {
-            return None;
+    /// Parses a group `(a)` or a vector `(x, y)`, `(x, y, z)` or `(x, y, z, w)` where every
+    /// component is a number.
+    fn parse_paren(&mut self) -> Result<Expr, Error> {
+        let (mut items, range) = self.parse_sequence(|kind| matches!(kind, TokenKind::ClosingParen))?;
+        let invalid = || Self::error(range, ParseErrorKind::InvalidVector);
+
+        if items.len() == 1 {
+            return Ok(items.remove(0));
+        }
+        if !(2..=4).contains(&items.len()) {
+            return Err(invalid());
+        }
+
+        // Constant components are checked now, the rest when evaluated.
+        let vector = Expr::Vector(items);
+        if !vector.is_constant() {
+            return Ok(vector);
         }
-        self.next().then_some(())?;
-        while self.token.is_whitespace() {
-            self.next().then_some(())?;
+        match vector.evaluate(&|_| None) {
+            Ok(value) => Ok(Expr::Value(value)),
+            Err(Error::InvalidComponent(_)) => Err(invalid()),
+            Err(e) => Err(e),
         }
+    }
 
-        let mut first: Option<Value> = None;
-        let mut values = Vec::new();
-        while !self.token.is_closing_bracket() {
-            let value = self.parse_value()?;
-            match &first {
-                Some(v) => if !v.same_kind(&value) {
-                    return None;
-                },
-                None => first = Some(value.clone()),
+    /// Parses comma separated expressions from an opening token up to the closing token,
+    /// allowing a trailing comma. Returns the expressions with the span of the whole sequence.
+    fn parse_sequence<F>(&mut self, is_close: F) -> Result<(Vec<Expr>, Range<usize>), Error>
+    where
+        F: Fn(&TokenKind) -> bool,
+    {
+        let start = self.current()?.range.start;
+        self.next();
+        self.depth += 1;
+        self.skip_whitespace();
+
+        let mut items = Vec::new();
+        loop {
+            let token = self.current()?;
+            if is_close(&token.kind) {
+                break;
             }
 
-            values.push(value);
+            items.push(self.parse_expression()?);
 
-            self.next().then_some(())?;
             self.skip_whitespace();
-
-            match self.token.kind {
+            let token = self.current()?;
+            match token.kind {
                 TokenKind::Comma => {
-                    self.next().then_some(())?;
+                    self.next();
                     self.skip_whitespace();
-                    if self.token.is_closing_bracket() {
-                        break;
-                    }
-                },
-                TokenKind::ClosingBracket => break,
-                _ => return None,
+                }
+                ref kind if is_close(kind) => break,
+                _ => return Err(Self::error(token.range, ParseErrorKind::UnexpectedToken)),
             }
         }
 
-        Some(Value::Array(values))
+        let end = self.current()?.range.end;
+        self.next();
+        self.depth -= 1;
+
+        Ok((items, Range { start, end }))
     }
 
-    fn skip_whitespace(&mut self) -> bool {
-        let whitespace = self.token.is_whitespace();
-        while self.token.is_whitespace() {
-            if !self.next() {
-                return true;
-            }
-        }
-        whitespace
+    fn current(&self) -> Result<&Token<'a>, Error> {
+        self.token.as_ref().ok_or(Self::error(
+            Range {
+                start: self.src.len(),
+                end: self.src.len(),
+            },
+            ParseErrorKind::UnexpectedEoi,
+        ))
     }
 
-    fn next(&mut self) -> bool {
-        match self.tokenizer.next() {
-            Some(t) => {
-                self.token = t;
-                true
-            }
-            None => false,
+    fn error(span: Range<usize>, kind: ParseErrorKind) -> Error {
+        Error::ParseError { span, kind }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.token.as_ref().is_some_and(Token::is_trivia) {
+            self.next();
         }
     }
+
+    fn next(&mut self) {
+        self.token = self.tokenizer.next();
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::command::{Parser, TokenKind};
+    use core::range::Range;
+
+    use dg_math::vector::{Vec2, Vec3};
+
+    use crate::{
+        command::{parse_command, parse_commands, Parser, TokenKind, MAX_NESTING},
+        Error, ParseErrorKind,
+    };
 
     use super::{Command, Tokenizer, Value};
+    use crate::expr::Expr;
+
+    fn constant(name: &str, values: Vec<Value>) -> Command {
+        Command::new(name, values.into_iter().map(Expr::Value).collect())
+    }
 
     #[test]
     fn test_tokenizer() {
@@ -493,9 +921,8 @@ mod tests {
         let tokenizer = Tokenizer::new(s);
 
         for token in tokenizer {
-            match token.kind {
-                TokenKind::Unknown => panic!("unknown token found: {:?}", token),
-                _ => {}
+            if let TokenKind::Unknown = token.kind {
+                panic!("unknown token found: {:?}", token);
             }
         }
     }
@@ -505,9 +932,9 @@ mod tests {
         let s = "function true false 16.666 5 0x01 0b01 \"frametime\" [true,false,true,true,true,true]";
         let mut parser = Parser::new(s);
         let r = parser.parse_command();
-        let e = Command {
-            name: "function".into(),
-            values: vec![
+        let e = constant(
+            "function",
+            vec![
                 Value::Boolean(true),
                 Value::Boolean(false),
                 Value::Float(16.666),
@@ -524,17 +951,144 @@ mod tests {
                     Value::Boolean(true),
                 ]),
             ],
-        };
-        assert_eq!(r, Some(e));
+        );
+        assert_eq!(r.unwrap(), e);
         
         let s = "function";
         let mut parser = Parser::new(s);
         let r = parser.parse_command();
-        let e = Command {
-            name: "function".into(),
-            values: vec![],
-        };
-        assert_eq!(r, Some(e));
+        let e = constant("function", vec![]);
+        assert_eq!(r.unwrap(), e);
         
     }
+
+    #[test]
+    fn test_parser_paths() {
+        for path in ["configs/autoexec.cfg", "/etc/game.cfg", "../a/b.cfg"] {
+            let r = Parser::new(&format!("exec {}", path)).parse_command();
+            assert_eq!(r.unwrap(), constant("exec", vec![Value::String(path.into())]));
+        }
+
+        // A comment right after a word isn't part of the path.
+        let r = Parser::new("exec a.cfg// comment").parse_command();
+        assert_eq!(r.unwrap(), constant("exec", vec![Value::String("a.cfg".into())]));
+    }
+
+    fn parse_error(s: &str) -> (Range<usize>, ParseErrorKind) {
+        match Parser::new(s).parse_command() {
+            Err(Error::ParseError { span, kind }) => (span, kind),
+            r => panic!("expected parse error for `{}`, got {:?}", s, r),
+        }
+    }
+
+    #[test]
+    fn test_parser_errors() {
+        let (span, kind) = parse_error("say \"hello");
+        assert_eq!(span, Range { start: 4, end: 10 });
+        assert!(matches!(kind, ParseErrorKind::UnterminatedString));
+
+        let (span, kind) = parse_error("say \"a\\qb\"");
+        assert_eq!(span, Range { start: 6, end: 8 });
+        assert!(matches!(kind, ParseErrorKind::InvalidEscape));
+
+        let (span, kind) = parse_error("say \"\\u{zz}\"");
+        assert_eq!(span, Range { start: 5, end: 11 });
+        assert!(matches!(kind, ParseErrorKind::InvalidEscape));
+
+        let (span, kind) = parse_error("teleport (1, 2, 3, 4, 5)");
+        assert_eq!(span, Range { start: 9, end: 24 });
+        assert!(matches!(kind, ParseErrorKind::InvalidVector));
+
+        let (span, kind) = parse_error("teleport (1, true)");
+        assert_eq!(span, Range { start: 9, end: 18 });
+        assert!(matches!(kind, ParseErrorKind::InvalidVector));
+
+        let (span, kind) = parse_error("set 1e");
+        assert_eq!(span, Range { start: 4, end: 6 });
+        assert!(matches!(kind, ParseErrorKind::InvalidNumber));
+
+        let (span, kind) = parse_error("set 12abc");
+        assert_eq!(span, Range { start: 4, end: 9 });
+        assert!(matches!(kind, ParseErrorKind::InvalidNumber));
+
+        let (span, kind) = parse_error("set 0b102");
+        assert_eq!(span, Range { start: 4, end: 9 });
+        assert!(matches!(kind, ParseErrorKind::InvalidNumber));
+
+        let (span, kind) = parse_error("set ?");
+        assert_eq!(span, Range { start: 4, end: 5 });
+        assert!(matches!(kind, ParseErrorKind::UnknownToken));
+
+        let (span, kind) = parse_error("set [1, 2");
+        assert_eq!(span, Range { start: 9, end: 9 });
+        assert!(matches!(kind, ParseErrorKind::UnexpectedEoi));
+
+        let (span, kind) = parse_error("5 set");
+        assert_eq!(span, Range { start: 0, end: 1 });
+        assert!(matches!(kind, ParseErrorKind::ExpectedCommandName));
+
+        let (span, kind) = parse_error("set \"a\"\"b\"");
+        assert_eq!(span, Range { start: 7, end: 10 });
+        assert!(matches!(kind, ParseErrorKind::UnexpectedToken));
+
+        let (span, kind) = parse_error(&format!("help {}", "[".repeat(100_000)));
+        assert_eq!(span, Range { start: 69, end: 70 });
+        assert!(matches!(kind, ParseErrorKind::TooDeep));
+        let (_, kind) = parse_error(&format!("set {}1", "-".repeat(100_000)));
+        assert!(matches!(kind, ParseErrorKind::TooDeep));
+        let nested = format!("set {}1{}", "[".repeat(MAX_NESTING - 1), "]".repeat(MAX_NESTING - 1));
+        assert!(Parser::new(&nested).parse_command().is_ok());
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let commands = parse_commands("say \"a; b\"; ; exec autoexec.cfg // run the config").unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                constant("say", vec![Value::String("a; b".into())]),
+                constant("exec", vec![Value::String("autoexec.cfg".into())]),
+            ]
+        );
+
+        assert_eq!(parse_commands("// nothing to see").unwrap(), vec![]);
+
+        match parse_commands("say 1; say [1, ?]") {
+            Err(Error::ParseError { span, .. }) => assert_eq!(span, Range { start: 15, end: 16 }),
+            r => panic!("expected parse error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_literals() {
+        let command = parse_command(
+            "spawn -5 -0x10 1e-3 -2.5E2 3. \"a\\n\\t\\\"b\\u{e9}\" [1, [true, \"x\"], []] (10, 0, 5) (1.5, -2)",
+        )
+        .unwrap();
+        let values = command.evaluate(|_| None).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Value::Integer(-5),
+                Value::Integer(-16),
+                Value::Float(1e-3),
+                Value::Float(-250.0),
+                Value::Float(3.0),
+                Value::String("a\n\t\"b\u{e9}".into()),
+                Value::Array(vec![
+                    Value::Integer(1),
+                    Value::Array(vec![Value::Boolean(true), Value::String("x".into())]),
+                    Value::Array(vec![]),
+                ]),
+                Value::Vec3(Vec3::new(10.0, 0.0, 5.0)),
+                Value::Vec2(Vec2::new(1.5, -2.0)),
+            ]
+        );
+
+        // Display output parses back to the same value.
+        for value in values {
+            let round_trip = parse_command(&format!("set {}", value)).unwrap();
+            assert_eq!(round_trip.evaluate(|_| None).unwrap(), vec![value]);
+        }
+    }
 }
@@ -0,0 +1,354 @@
+use core::range::Range;
+
+use crate::{
+    command::{Token, TokenKind, Tokenizer, Value, ValueKind},
+    Console,
+};
+
+/// Hint for the command being typed, `argument` is the index of the argument under the cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint {
+    pub signature: String,
+    pub argument: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// Span of the line the chosen candidate should replace.
+    pub replace: Range<usize>,
+    pub candidates: Vec<String>,
+    pub hint: Option<Hint>,
+}
+
+/// Where the cursor sits in the partially typed line.
+struct Position<'a> {
+    name: Option<&'a str>,
+    /// `None` while the command name itself is being typed.
+    argument: Option<usize>,
+    prefix: &'a str,
+    replace: Range<usize>,
+}
+
+fn locate<'a>(line: &'a str, cursor: usize) -> Position<'a> {
+    // A cursor inside a multibyte character sits before it.
+    let mut cursor = cursor.min(line.len());
+    while !line.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    let tokens: Vec<Token> = Tokenizer::new(&line[..cursor]).collect();
+
+    let mut name = None;
+    let mut argument: Option<usize> = None;
+    let mut depth = 0usize;
+    let mut separated = true;
+
+    for token in &tokens {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => {
+                separated = true;
+                continue;
+            }
+            TokenKind::Semicolon => {
+                name = None;
+                argument = None;
+                depth = 0;
+                separated = true;
+                continue;
+            }
+            TokenKind::ClosingBracket | TokenKind::ClosingParen => depth = depth.saturating_sub(1),
+            _ if depth == 0 && separated => match name {
+                None => name = Some(token.s),
+                Some(_) => argument = Some(argument.map_or(0, |a| a + 1)),
+            },
+            _ => {}
+        }
+
+        if let TokenKind::OpeningBracket | TokenKind::OpeningParen = token.kind {
+            depth += 1;
+        }
+        separated = false;
+    }
+
+    // The token touching the cursor is the partial word, anything else means a fresh word.
+    let partial = tokens.last().filter(|t| {
+        matches!(
+            t.kind,
+            TokenKind::Ident | TokenKind::Literal(_) | TokenKind::Unknown
+        )
+    });
+
+    match partial {
+        Some(token) => Position {
+            // A partial name is still being typed.
+            name: name.filter(|_| argument.is_some()),
+            argument,
+            prefix: token.s,
+            replace: token.range,
+        },
+        None => {
+            let argument = match (name, depth) {
+                (None, _) => None,
+                // Inside an array or vector the cursor stays on that argument.
+                (Some(_), d) if d > 0 => argument,
+                (Some(_), _) if separated => Some(argument.map_or(0, |a| a + 1)),
+                (Some(_), _) => argument,
+            };
+            Position {
+                name,
+                argument,
+                prefix: "",
+                replace: Range {
+                    start: cursor,
+                    end: cursor,
+                },
+            }
+        }
+    }
+}
+
+fn kind_choices(kind: ValueKind) -> Vec<Value> {
+    match kind {
+        ValueKind::Boolean => vec![Value::Boolean(true), Value::Boolean(false)],
+        _ => Vec::new(),
+    }
+}
+
+/// The text to insert for `value` if it completes `prefix`.
+///
+/// Strings match by their contents, quoted or not. They're inserted quoted after an opening `"`
+/// or if they wouldn't read back as the same bare word, and bare otherwise.
+fn value_candidate(value: &Value, prefix: &str) -> Option<String> {
+    let Value::String(s) = value else {
+        let text = value.to_string();
+        return text.starts_with(prefix).then_some(text);
+    };
+
+    let (quoted, typed) = match prefix.strip_prefix('"') {
+        Some(rest) => (true, rest.strip_suffix('"').unwrap_or(rest)),
+        None => (false, prefix),
+    };
+    if !s.starts_with(typed) {
+        return None;
+    }
+    Some(if quoted || !is_bare_word(s) {
+        value.to_string()
+    } else {
+        s.clone()
+    })
+}
+
+/// Whether `s` reads back as the string `s` without quotes.
+fn is_bare_word(s: &str) -> bool {
+    let tokens: Vec<Token> = Tokenizer::new(s).collect();
+    let word = tokens.len() == 1 && matches!(tokens[0].kind, TokenKind::Ident);
+    word && !matches!(s.to_lowercase().as_str(), "true" | "false")
+}
+
+impl Console {
+    /// Completes the word ending at `cursor` (a byte offset into `line`).
+    ///
+    /// Command and cvar names are offered for the first word, and enumerated values for
+    /// arguments whose parameter declares choices or is a boolean. Incomplete input such as an
+    /// unterminated string or an unclosed `[` is tolerated.
+    pub fn complete(&self, line: &str, cursor: usize) -> Completion {
+        let position = locate(line, cursor);
+
+        let (mut candidates, hint): (Vec<_>, _) = match (position.name, position.argument) {
+            (None, _) => {
+                let names = self
+                    .registry()
+                    .iter()
+                    .map(|c| c.name.clone())
+                    .chain(self.cvars().iter().map(|c| c.name().to_owned()))
+                    .chain(self.aliases().map(|(name, _)| name.to_owned()))
+                    .filter(|name| name.starts_with(position.prefix))
+                    .collect();
+                (names, None)
+            }
+            (Some(name), argument) => {
+                if let Some(entry) = self.registry().get(name) {
+                    let parameter = argument.and_then(|a| entry.signature.parameters().get(a));
+                    let choices = match parameter {
+                        Some(p) if !p.choices.is_empty() => p.choices.clone(),
+                        Some(p) => kind_choices(p.kind),
+                        None => Vec::new(),
+                    };
+                    let hint = Hint {
+                        signature: entry.signature.hint(name),
+                        argument: argument.filter(|&a| a < entry.signature.parameters().len()),
+                    };
+                    let candidates = choices
+                        .iter()
+                        .filter_map(|c| value_candidate(c, position.prefix))
+                        .collect();
+                    (candidates, Some(hint))
+                } else if let Some(cvar) = self.cvars().get(name) {
+                    let kind = cvar.default().kind();
+                    let choices = match argument {
+                        Some(0) => kind_choices(kind),
+                        _ => Vec::new(),
+                    };
+                    let hint = Hint {
+                        signature: format!("{} <{}>", name, kind),
+                        argument: argument.filter(|&a| a == 0),
+                    };
+                    let candidates = choices
+                        .iter()
+                        .filter_map(|c| value_candidate(c, position.prefix))
+                        .collect();
+                    (candidates, Some(hint))
+                } else {
+                    (Vec::new(), None)
+                }
+            }
+        };
+
+        candidates.sort();
+        candidates.dedup();
+
+        Completion {
+            replace: position.replace,
+            candidates,
+            hint,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::range::Range;
+
+    use crate::{
+        command::{Value, ValueKind},
+        cvar::Cvar,
+        registry::{Parameter, Signature},
+        Console,
+    };
+
+    fn console() -> Console {
+        let mut console = Console::new();
+        console.register("physics_step", Signature::new(&[ValueKind::Integer]), |_, _| Ok(()));
+        console.register(
+            "give",
+            Signature::from_parameters(vec![
+                Parameter::new(ValueKind::String).with_choices(&[
+                    Value::String("rifle".into()),
+                    Value::String("rocket".into()),
+                    Value::String("shotgun".into()),
+                ]),
+                Parameter::new(ValueKind::Array).with_choices(&[
+                    Value::String("ammo".into()),
+                    Value::String("armor".into()),
+                    Value::String("health".into()),
+                ]),
+            ]),
+            |_, _| Ok(()),
+        );
+        console.register_cvar(Cvar::new("physics_debug", Value::Boolean(false)));
+        console
+    }
+
+    #[test]
+    fn test_complete_name() {
+        let mut console = console();
+
+        let c = console.complete("phys", 4);
+        assert_eq!(c.candidates, vec!["physics_debug", "physics_step"]);
+        assert_eq!(c.replace, Range { start: 0, end: 4 });
+        assert_eq!(c.hint, None);
+
+        // Mid-token cursor only considers the text before it.
+        let c = console.complete("physics_step 5", 3);
+        assert_eq!(c.candidates, vec!["physics_debug", "physics_step"]);
+        assert_eq!(c.replace, Range { start: 0, end: 3 });
+
+        let c = console.complete("", 0);
+        assert_eq!(
+            c.candidates,
+            vec![
+                "alias",
+                "bind",
+                "exec",
+                "find",
+                "give",
+                "help",
+                "physics_debug",
+                "physics_step",
+                "unalias",
+                "unbind",
+                "unbindall",
+                "wait",
+                "writeconfig"
+            ]
+        );
+
+        console.run("alias phys_reset \"physics_step 1\"").unwrap();
+        let c = console.complete("jump; phys", 10);
+        assert_eq!(c.candidates, vec!["phys_reset", "physics_debug", "physics_step"]);
+    }
+
+    #[test]
+    fn test_complete_arguments() {
+        let console = console();
+
+        let c = console.complete("physics_debug t", 15);
+        assert_eq!(c.candidates, vec!["true"]);
+        assert_eq!(c.replace, Range { start: 14, end: 15 });
+        assert_eq!(c.hint.unwrap().signature, "physics_debug <boolean>");
+
+        let c = console.complete("give \"r", 7);
+        assert_eq!(c.candidates, vec!["\"rifle\"", "\"rocket\""]);
+        let hint = c.hint.unwrap();
+        assert_eq!(hint.signature, "give <string> <array>");
+        assert_eq!(hint.argument, Some(0));
+
+        // Bare words match the contents of strings and are completed bare.
+        let c = console.complete("give ri", 7);
+        assert_eq!(c.candidates, vec!["rifle"]);
+        assert_eq!(c.replace, Range { start: 5, end: 7 });
+        let c = console.complete("give \"rifle\"", 12);
+        assert_eq!(c.candidates, vec!["\"rifle\""]);
+
+        let c = console.complete("give \"rifle\" ", 13);
+        assert_eq!(c.candidates, vec!["ammo", "armor", "health"]);
+        assert_eq!(c.hint.unwrap().argument, Some(1));
+
+        let c = console.complete("physics_step 1 2", 16);
+        assert_eq!(c.hint.unwrap().argument, None);
+    }
+
+    #[test]
+    fn test_complete_multibyte() {
+        let console = console();
+
+        let c = console.complete("é", 1);
+        assert_eq!(c.replace, Range { start: 0, end: 0 });
+
+        let line = "give \"é\" ";
+        let c = console.complete(line, 7);
+        assert_eq!(c.replace, Range { start: 5, end: 6 });
+        assert_eq!(c.hint.unwrap().argument, Some(0));
+    }
+
+    #[test]
+    fn test_complete_array() {
+        let console = console();
+
+        let line = "give \"rifle\" [\"ammo\", \"a";
+        let c = console.complete(line, line.len());
+        assert_eq!(c.candidates, vec!["\"ammo\"", "\"armor\""]);
+        assert_eq!(c.replace, Range { start: 22, end: 24 });
+        assert_eq!(c.hint.unwrap().argument, Some(1));
+
+        let line = "give \"rifle\" [\"ammo\", ";
+        let c = console.complete(line, line.len());
+        assert_eq!(c.candidates.len(), 3);
+        assert_eq!(c.hint.unwrap().argument, Some(1));
+
+        // Cursor in the middle of an array element.
+        let line = "give \"rifle\" [\"health\"] ";
+        let c = console.complete(line, 17);
+        assert_eq!(c.candidates, vec!["\"health\""]);
+        assert_eq!(c.replace, Range { start: 14, end: 17 });
+    }
+}
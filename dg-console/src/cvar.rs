@@ -0,0 +1,290 @@
+use std::{collections::BTreeMap, ops::BitOr, rc::Rc};
+
+use crate::{command::Value, Error};
+
+pub type ChangeCallback = Rc<dyn Fn(&str, &Value, &Value)>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CvarFlags(u8);
+
+impl CvarFlags {
+    pub const NONE: CvarFlags = CvarFlags(0);
+    /// Can only be changed from Rust code.
+    pub const READ_ONLY: CvarFlags = CvarFlags(1 << 0);
+    /// Can only be changed from the console while cheats are enabled.
+    pub const CHEAT: CvarFlags = CvarFlags(1 << 1);
+    /// Written out by [`Cvars::save`].
+    pub const PERSIST: CvarFlags = CvarFlags(1 << 2);
+
+    pub fn contains(&self, other: CvarFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for CvarFlags {
+    type Output = CvarFlags;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        CvarFlags(self.0 | rhs.0)
+    }
+}
+
+/// A named console variable, the kind of its default decides the kind of every later value.
+#[derive(Clone)]
+pub struct Cvar {
+    name: String,
+    value: Value,
+    default: Value,
+    min: Option<f64>,
+    max: Option<f64>,
+    flags: CvarFlags,
+    description: String,
+    callbacks: Vec<ChangeCallback>,
+}
+
+impl Cvar {
+    pub fn new(name: &str, default: Value) -> Self {
+        Self {
+            name: name.to_owned(),
+            value: default.clone(),
+            default,
+            min: None,
+            max: None,
+            flags: CvarFlags::NONE,
+            description: String::new(),
+            callbacks: Vec::new(),
+        }
+    }
+
+    pub fn with_min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn with_max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn with_range(self, min: f64, max: f64) -> Self {
+        self.with_min(min).with_max(max)
+    }
+
+    pub fn with_flags(mut self, flags: CvarFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// One-line description shown by `help` and `find`.
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    pub fn default(&self) -> &Value {
+        &self.default
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    pub fn flags(&self) -> CvarFlags {
+        self.flags
+    }
+
+    fn validate(&self, value: &Value) -> Result<Value, Error> {
+        let value = value
+            .coerce(self.default.kind())
+            .ok_or_else(|| Error::TypeMismatch {
+                name: self.name.clone(),
+                index: 0,
+                expected: self.default.kind(),
+                found: value.kind(),
+            })?;
+
+        if let Some(v) = value.as_float() {
+            let below = self.min.is_some_and(|min| v < min);
+            let above = self.max.is_some_and(|max| v > max);
+            if below || above {
+                return Err(Error::OutOfRange {
+                    name: self.name.clone(),
+                    value: value.to_string(),
+                    min: self.min,
+                    max: self.max,
+                });
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+#[derive(Default)]
+pub struct Cvars {
+    cvars: BTreeMap<String, Cvar>,
+}
+
+impl Cvars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a cvar, replacing any previous cvar with the same name.
+    pub fn register(&mut self, cvar: Cvar) {
+        self.cvars.insert(cvar.name.clone(), cvar);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cvar> {
+        self.cvars.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.cvars.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.cvars.get(name).map(Cvar::value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cvar> {
+        self.cvars.values()
+    }
+
+    /// Subscribes `callback` to changes of the cvar, it is called with the name, old and new value.
+    pub fn on_change<F>(&mut self, name: &str, callback: F) -> Result<(), Error>
+    where
+        F: Fn(&str, &Value, &Value) + 'static,
+    {
+        let cvar = self
+            .cvars
+            .get_mut(name)
+            .ok_or_else(|| Error::UnknownCvar(name.to_owned()))?;
+        cvar.callbacks.push(Rc::new(callback));
+        Ok(())
+    }
+
+    /// Sets the cvar from Rust code, ignoring the read-only and cheat flags.
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), Error> {
+        let cvar = self
+            .cvars
+            .get_mut(name)
+            .ok_or_else(|| Error::UnknownCvar(name.to_owned()))?;
+        let value = cvar.validate(&value)?;
+        let old = std::mem::replace(&mut cvar.value, value);
+
+        if old != cvar.value {
+            for callback in &cvar.callbacks {
+                callback(&cvar.name, &old, &cvar.value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the cvar on behalf of the console, respecting its flags.
+    pub fn set_from_console(&mut self, name: &str, value: Value, cheats: bool) -> Result<(), Error> {
+        let cvar = self
+            .cvars
+            .get(name)
+            .ok_or_else(|| Error::UnknownCvar(name.to_owned()))?;
+
+        if cvar.flags.contains(CvarFlags::READ_ONLY) {
+            return Err(Error::ReadOnly(name.to_owned()));
+        }
+        if cvar.flags.contains(CvarFlags::CHEAT) && !cheats {
+            return Err(Error::CheatProtected(name.to_owned()));
+        }
+
+        self.set(name, value)
+    }
+
+    pub fn reset(&mut self, name: &str) -> Result<(), Error> {
+        let default = self
+            .cvars
+            .get(name)
+            .ok_or_else(|| Error::UnknownCvar(name.to_owned()))?
+            .default
+            .clone();
+        self.set(name, default)
+    }
+
+    /// Writes every persistent cvar as a `name value` line which can be run back through the console.
+    pub fn save(&self) -> String {
+        self.cvars
+            .values()
+            .filter(|c| c.flags.contains(CvarFlags::PERSIST))
+            .map(|c| format!("{} {}\n", c.name, c.value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Cvar, CvarFlags, Cvars};
+    use crate::{command::Value, Error};
+
+    #[test]
+    fn test_cvars() {
+        let mut cvars = Cvars::new();
+        cvars.register(Cvar::new("physics_steps", Value::Integer(10)).with_range(1.0, 100.0));
+        cvars.register(
+            Cvar::new("r_gamma", Value::Float(2.2)).with_flags(CvarFlags::PERSIST | CvarFlags::CHEAT),
+        );
+        cvars.register(Cvar::new("version", Value::String("0.1".into())).with_flags(CvarFlags::READ_ONLY));
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let c = changes.clone();
+        cvars
+            .on_change("physics_steps", move |_, old, new| {
+                c.borrow_mut().push((old.clone(), new.clone()))
+            })
+            .unwrap();
+
+        cvars.set("physics_steps", Value::Integer(20)).unwrap();
+        cvars.set("physics_steps", Value::Integer(20)).unwrap();
+        assert_eq!(*changes.borrow(), vec![(Value::Integer(10), Value::Integer(20))]);
+
+        assert!(matches!(
+            cvars.set("physics_steps", Value::Integer(0)),
+            Err(Error::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            cvars.set("physics_steps", Value::Boolean(true)),
+            Err(Error::TypeMismatch { .. })
+        ));
+
+        cvars.set("r_gamma", Value::Integer(1)).unwrap();
+        assert_eq!(cvars.value("r_gamma"), Some(&Value::Float(1.0)));
+        assert!(matches!(
+            cvars.set_from_console("r_gamma", Value::Float(2.0), false),
+            Err(Error::CheatProtected(_))
+        ));
+        assert!(matches!(
+            cvars.set_from_console("version", Value::String("1.0".into()), true),
+            Err(Error::ReadOnly(_))
+        ));
+
+        cvars.reset("physics_steps").unwrap();
+        assert_eq!(cvars.value("physics_steps"), Some(&Value::Integer(10)));
+        assert_eq!(cvars.save(), "r_gamma 1.0\n");
+    }
+}
@@ -0,0 +1,292 @@
+use std::fmt::Display;
+
+use dg_math::{
+    vector::{Vec2, Vec3, Vec4},
+    Scalar,
+};
+
+use crate::{command::Value, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinaryOp {
+    fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+        )
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// An argument as written, evaluated when its command runs so `$name` sees the current cvar value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Value(Value),
+    /// `$name`, the value of a cvar.
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Array(Vec<Expr>),
+    /// 2 to 4 components which must evaluate to numbers.
+    Vector(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn is_constant(&self) -> bool {
+        match self {
+            Expr::Value(_) => true,
+            Expr::Variable(_) => false,
+            Expr::Negate(expr) => expr.is_constant(),
+            Expr::Binary(_, left, right) => left.is_constant() && right.is_constant(),
+            Expr::Array(items) | Expr::Vector(items) => items.iter().all(Expr::is_constant),
+        }
+    }
+
+    /// Evaluates the expression, looking up `$name` references with `variable`.
+    pub fn evaluate<F>(&self, variable: &F) -> Result<Value, Error>
+    where
+        F: Fn(&str) -> Option<Value>,
+    {
+        match self {
+            Expr::Value(value) => Ok(value.clone()),
+            Expr::Variable(name) => variable(name).ok_or_else(|| Error::UnknownCvar(name.clone())),
+            Expr::Negate(expr) => negate(expr.evaluate(variable)?),
+            Expr::Binary(op, left, right) => {
+                binary(*op, left.evaluate(variable)?, right.evaluate(variable)?)
+            }
+            Expr::Array(items) => items
+                .iter()
+                .map(|item| item.evaluate(variable))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            Expr::Vector(items) => {
+                let components = items
+                    .iter()
+                    .map(|item| {
+                        let value = item.evaluate(variable)?;
+                        value
+                            .as_float()
+                            .ok_or_else(|| Error::InvalidComponent(value.kind()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(vector(&components).expect("component count checked by the parser"))
+            }
+        }
+    }
+}
+
+fn components(value: &Value) -> Option<Vec<f64>> {
+    match value {
+        Value::Vec2(v) => Some(vec![v.x as f64, v.y as f64]),
+        Value::Vec3(v) => Some(vec![v.x as f64, v.y as f64, v.z as f64]),
+        Value::Vec4(v) => Some(vec![v.x as f64, v.y as f64, v.z as f64, v.w as f64]),
+        _ => None,
+    }
+}
+
+fn vector(components: &[f64]) -> Option<Value> {
+    let c: Vec<Scalar> = components.iter().map(|&c| c as Scalar).collect();
+    match c[..] {
+        [x, y] => Some(Value::Vec2(Vec2::new(x, y))),
+        [x, y, z] => Some(Value::Vec3(Vec3::new(x, y, z))),
+        [x, y, z, w] => Some(Value::Vec4(Vec4::new(x, y, z, w))),
+        _ => None,
+    }
+}
+
+fn negate(value: Value) -> Result<Value, Error> {
+    match value {
+        Value::Integer(i) => i.checked_neg().map(Value::Integer).ok_or(Error::IntegerOverflow),
+        Value::Float(f) => Ok(Value::Float(-f)),
+        ref v => match components(v) {
+            Some(c) => Ok(vector(&c.iter().map(|c| -c).collect::<Vec<_>>()).unwrap()),
+            None => Err(Error::InvalidOperand {
+                op: "-".to_owned(),
+                found: v.kind(),
+            }),
+        },
+    }
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, Error> {
+    let invalid = || Error::InvalidOperands {
+        op: op.to_string(),
+        left: left.kind(),
+        right: right.kind(),
+    };
+
+    if op.is_comparison() {
+        return compare(op, &left, &right).map(Value::Boolean).ok_or_else(invalid);
+    }
+
+    match (&left, &right) {
+        (Value::Integer(a), Value::Integer(b)) => integer(op, *a, *b).map(Value::Integer),
+        (Value::String(a), Value::String(b)) if op == BinaryOp::Add => {
+            Ok(Value::String(format!("{}{}", a, b)))
+        }
+        _ => {
+            if let (Some(a), Some(b)) = (left.as_float(), right.as_float()) {
+                return Ok(Value::Float(float(op, a, b)));
+            }
+
+            // Vectors combine componentwise with vectors of the same size, or scale by a number.
+            let c = match (components(&left), components(&right)) {
+                (Some(a), Some(b)) if a.len() == b.len() && matches!(op, BinaryOp::Add | BinaryOp::Sub) => {
+                    a.iter().zip(&b).map(|(a, b)| float(op, *a, *b)).collect()
+                }
+                (Some(a), None) if matches!(op, BinaryOp::Mul | BinaryOp::Div) => {
+                    let b = right.as_float().ok_or_else(invalid)?;
+                    a.iter().map(|a| float(op, *a, b)).collect()
+                }
+                (None, Some(b)) if op == BinaryOp::Mul => {
+                    let a = left.as_float().ok_or_else(invalid)?;
+                    b.iter().map(|b| a * b).collect::<Vec<_>>()
+                }
+                _ => return Err(invalid()),
+            };
+            Ok(vector(&c).unwrap())
+        }
+    }
+}
+
+fn integer(op: BinaryOp, a: i64, b: i64) -> Result<i64, Error> {
+    let result = match op {
+        BinaryOp::Add => a.checked_add(b),
+        BinaryOp::Sub => a.checked_sub(b),
+        BinaryOp::Mul => a.checked_mul(b),
+        BinaryOp::Div | BinaryOp::Rem if b == 0 => return Err(Error::DivisionByZero),
+        BinaryOp::Div => a.checked_div(b),
+        BinaryOp::Rem => a.checked_rem(b),
+        _ => unreachable!("comparisons are handled separately"),
+    };
+    result.ok_or(Error::IntegerOverflow)
+}
+
+fn float(op: BinaryOp, a: f64, b: f64) -> f64 {
+    match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        BinaryOp::Rem => a % b,
+        _ => unreachable!("comparisons are handled separately"),
+    }
+}
+
+/// Numbers compare by value regardless of kind, ordering is only defined for numbers and strings.
+fn compare(op: BinaryOp, left: &Value, right: &Value) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Value::Integer(a), Value::Integer(b)) => a.partial_cmp(b),
+        (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+        _ => match (left.as_float(), right.as_float()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => {
+                return match op {
+                    BinaryOp::Eq => Some(left == right),
+                    BinaryOp::Ne => Some(left != right),
+                    _ => None,
+                }
+            }
+        },
+    };
+
+    Some(match op {
+        BinaryOp::Eq => ordering.is_some_and(|o| o.is_eq()),
+        BinaryOp::Ne => !ordering.is_some_and(|o| o.is_eq()),
+        BinaryOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        BinaryOp::Le => ordering.is_some_and(|o| o.is_le()),
+        BinaryOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        BinaryOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+        _ => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dg_math::vector::Vec3;
+
+    use crate::{command::parse_command, command::Value, Error};
+
+    fn evaluate(s: &str) -> Result<Value, Error> {
+        let command = parse_command(&format!("set {}", s))?;
+        let values = command.evaluate(|name| match name {
+            "height" => Some(Value::Float(1.5)),
+            "steps" => Some(Value::Integer(10)),
+            "name" => Some(Value::String("tank".into())),
+            _ => None,
+        })?;
+        Ok(values[0].clone())
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(evaluate("1+2*3").unwrap(), Value::Integer(7));
+        assert_eq!(evaluate("(1+2)*3").unwrap(), Value::Integer(9));
+        assert_eq!(evaluate("$steps-5").unwrap(), Value::Integer(5));
+        assert_eq!(evaluate("$steps/4").unwrap(), Value::Integer(2));
+        assert_eq!(evaluate("$steps%4").unwrap(), Value::Integer(2));
+        assert_eq!(evaluate("$steps*$height").unwrap(), Value::Float(15.0));
+        assert_eq!(evaluate("-$steps").unwrap(), Value::Integer(-10));
+        assert_eq!(evaluate("$name+\"_2\"").unwrap(), Value::String("tank_2".into()));
+        assert_eq!(
+            evaluate("(0, $height+5, 0)").unwrap(),
+            Value::Vec3(Vec3::new(0.0, 6.5, 0.0))
+        );
+        // Outside of brackets whitespace separates arguments, leaving a dangling `-`.
+        assert!(matches!(
+            evaluate("(1, 2, 3)*2 - (1, 1, 1)"),
+            Err(Error::ParseError { .. })
+        ));
+        assert_eq!(
+            evaluate("[(1, 2, 3) * 2 - (1, 1, 1)]").unwrap(),
+            Value::Array(vec![Value::Vec3(Vec3::new(1.0, 3.0, 5.0))])
+        );
+
+        assert!(matches!(evaluate("$steps/0"), Err(Error::DivisionByZero)));
+        assert!(matches!(evaluate("$missing"), Err(Error::UnknownCvar(_))));
+        assert!(matches!(evaluate("$name*2"), Err(Error::InvalidOperands { .. })));
+        assert!(matches!(evaluate("(1, $name)"), Err(Error::InvalidComponent(_))));
+    }
+
+    #[test]
+    fn test_comparison() {
+        assert_eq!(evaluate("$steps>5").unwrap(), Value::Boolean(true));
+        assert_eq!(evaluate("$steps==10.0").unwrap(), Value::Boolean(true));
+        assert_eq!(evaluate("$height<=1").unwrap(), Value::Boolean(false));
+        assert_eq!(evaluate("$name!=tank").unwrap(), Value::Boolean(false));
+        assert_eq!(evaluate("1+1==2").unwrap(), Value::Boolean(true));
+        assert!(matches!(evaluate("$name<1"), Err(Error::InvalidOperands { .. })));
+    }
+}
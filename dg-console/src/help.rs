@@ -0,0 +1,413 @@
+use crate::{
+    command::{Value, ValueKind},
+    cvar::{Cvar, CvarFlags},
+    registry::{CommandEntry, Parameter, Signature},
+    Console, Error,
+};
+
+fn flag_names(flags: CvarFlags) -> Vec<&'static str> {
+    [
+        (CvarFlags::READ_ONLY, "read_only"),
+        (CvarFlags::CHEAT, "cheat"),
+        (CvarFlags::PERSIST, "persist"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| flags.contains(*flag))
+    .map(|(_, name)| name)
+    .collect()
+}
+
+fn cvar_range(cvar: &Cvar) -> Option<String> {
+    match (cvar.min(), cvar.max()) {
+        (None, None) => None,
+        (min, max) => Some(format!(
+            "{}..{}",
+            min.map(|m| m.to_string()).unwrap_or_default(),
+            max.map(|m| m.to_string()).unwrap_or_default()
+        )),
+    }
+}
+
+fn cvar_hint(cvar: &Cvar) -> String {
+    format!("{} <{}>", cvar.name(), cvar.default().kind())
+}
+
+/// Appends ` - description` when there is one.
+fn with_description(line: String, description: &str) -> String {
+    if description.is_empty() {
+        line
+    } else {
+        format!("{} - {}", line, description)
+    }
+}
+
+fn command_summary(entry: &CommandEntry) -> String {
+    with_description(
+        entry.signature.hint(&entry.name),
+        entry.signature.description(),
+    )
+}
+
+fn cvar_summary(cvar: &Cvar) -> String {
+    with_description(
+        format!("{} = {}", cvar_hint(cvar), cvar.value()),
+        cvar.description(),
+    )
+}
+
+fn parameter_name(index: usize, parameter: &Parameter) -> String {
+    parameter
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("arg{}", index))
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_option<T: ToString>(value: Option<T>) -> String {
+    match value {
+        Some(v) => json_string(&v.to_string()),
+        None => "null".to_owned(),
+    }
+}
+
+/// Escapes the characters that would break a Markdown table cell.
+fn markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+impl Console {
+    /// Lines printed by `help <name>` for a command, cvar or alias.
+    pub fn describe(&self, name: &str) -> Option<Vec<String>> {
+        if let Some(entry) = self.registry().get(name) {
+            let mut lines = vec![entry.signature.hint(name)];
+            if !entry.signature.description().is_empty() {
+                lines.push(format!("  {}", entry.signature.description()));
+            }
+            for (i, parameter) in entry.signature.parameters().iter().enumerate() {
+                let mut line = format!("    {}: {}", parameter_name(i, parameter), parameter.kind);
+                if let Some(default) = &parameter.default {
+                    line.push_str(&format!(" = {}", default));
+                }
+                lines.push(with_description(line, &parameter.description));
+            }
+            return Some(lines);
+        }
+
+        if let Some(cvar) = self.cvars().get(name) {
+            let mut lines = vec![cvar_hint(cvar)];
+            if !cvar.description().is_empty() {
+                lines.push(format!("  {}", cvar.description()));
+            }
+            lines.push(format!("    value: {}", cvar.value()));
+            lines.push(format!("    default: {}", cvar.default()));
+            if let Some(range) = cvar_range(cvar) {
+                lines.push(format!("    range: {}", range));
+            }
+            let flags = flag_names(cvar.flags());
+            if !flags.is_empty() {
+                lines.push(format!("    flags: {}", flags.join(", ")));
+            }
+            return Some(lines);
+        }
+
+        self.alias(name)
+            .map(|body| vec![format!("{} = {}", name, Value::String(body.to_owned()))])
+    }
+
+    /// One summary line for every command, cvar and alias whose name or description contains
+    /// `query`, ignoring case. An empty query matches everything.
+    pub fn find(&self, query: &str) -> Vec<String> {
+        let query = query.to_lowercase();
+        let matches = |name: &str, description: &str| {
+            name.to_lowercase().contains(&query) || description.to_lowercase().contains(&query)
+        };
+
+        let commands = self
+            .registry()
+            .iter()
+            .filter(|e| matches(&e.name, e.signature.description()))
+            .map(command_summary);
+        let cvars = self
+            .cvars()
+            .iter()
+            .filter(|c| matches(c.name(), c.description()))
+            .map(cvar_summary);
+        let aliases = self
+            .aliases()
+            .filter(|(name, body)| matches(name, body))
+            .map(|(name, body)| format!("{} = {}", name, Value::String(body.to_owned())));
+
+        commands.chain(cvars).chain(aliases).collect()
+    }
+
+    /// Documents every command and cvar as Markdown.
+    pub fn export_markdown(&self) -> String {
+        let mut md = String::from("# Console reference\n\n## Commands\n");
+
+        for entry in self.registry().iter() {
+            md.push_str(&format!("\n### `{}`\n\n", entry.name));
+            if !entry.signature.description().is_empty() {
+                md.push_str(&format!("{}\n\n", entry.signature.description()));
+            }
+            md.push_str(&format!("```\n{}\n```\n", entry.signature.hint(&entry.name)));
+
+            if !entry.signature.parameters().is_empty() {
+                md.push_str("\n| Argument | Type | Default | Description |\n");
+                md.push_str("| --- | --- | --- | --- |\n");
+                for (i, parameter) in entry.signature.parameters().iter().enumerate() {
+                    md.push_str(&format!(
+                        "| `{}` | {} | {} | {} |\n",
+                        parameter_name(i, parameter),
+                        parameter.kind,
+                        parameter
+                            .default
+                            .as_ref()
+                            .map(|d| format!("`{}`", markdown_cell(&d.to_string())))
+                            .unwrap_or_default(),
+                        markdown_cell(&parameter.description),
+                    ));
+                }
+            }
+        }
+
+        md.push_str("\n## Cvars\n\n");
+        md.push_str("| Name | Type | Default | Range | Flags | Description |\n");
+        md.push_str("| --- | --- | --- | --- | --- | --- |\n");
+        for cvar in self.cvars().iter() {
+            md.push_str(&format!(
+                "| `{}` | {} | `{}` | {} | {} | {} |\n",
+                cvar.name(),
+                cvar.default().kind(),
+                markdown_cell(&cvar.default().to_string()),
+                cvar_range(cvar).unwrap_or_default(),
+                flag_names(cvar.flags()).join(", "),
+                markdown_cell(cvar.description()),
+            ));
+        }
+
+        md
+    }
+
+    /// Documents every command and cvar as JSON, defaults are written as console literals.
+    pub fn export_json(&self) -> String {
+        let commands: Vec<String> = self
+            .registry()
+            .iter()
+            .map(|entry| {
+                let parameters: Vec<String> = entry
+                    .signature
+                    .parameters()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        format!(
+                            "{{\"name\":{},\"type\":{},\"default\":{},\"description\":{}}}",
+                            json_string(&parameter_name(i, p)),
+                            json_string(&p.kind.to_string()),
+                            json_option(p.default.as_ref()),
+                            json_string(&p.description),
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"name\":{},\"description\":{},\"parameters\":[{}]}}",
+                    json_string(&entry.name),
+                    json_string(entry.signature.description()),
+                    parameters.join(","),
+                )
+            })
+            .collect();
+
+        let cvars: Vec<String> = self
+            .cvars()
+            .iter()
+            .map(|cvar| {
+                let flags: Vec<String> = flag_names(cvar.flags())
+                    .into_iter()
+                    .map(json_string)
+                    .collect();
+                format!(
+                    "{{\"name\":{},\"type\":{},\"default\":{},\"min\":{},\"max\":{},\"flags\":[{}],\"description\":{}}}",
+                    json_string(cvar.name()),
+                    json_string(&cvar.default().kind().to_string()),
+                    json_string(&cvar.default().to_string()),
+                    cvar.min().map_or("null".to_owned(), |m| m.to_string()),
+                    cvar.max().map_or("null".to_owned(), |m| m.to_string()),
+                    flags.join(","),
+                    json_string(cvar.description()),
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"commands\":[{}],\"cvars\":[{}]}}",
+            commands.join(","),
+            cvars.join(",")
+        )
+    }
+}
+
+pub(crate) fn register_builtins(console: &mut Console) {
+    console.register(
+        "help",
+        Signature::from_parameters(vec![Parameter::named("name", ValueKind::String)
+            .with_default(Value::String(String::new()))
+            .with_description("Command, cvar or alias to describe")])
+        .with_description("Lists everything available or describes one command, cvar or alias"),
+        |console, values| {
+            let name = values[0].as_str().unwrap();
+            let lines = if name.is_empty() {
+                console.find("")
+            } else {
+                console.describe(name).ok_or_else(|| {
+                    Error::CommandFailed(format!("no command, cvar or alias named `{}`", name))
+                })?
+            };
+
+            for line in lines {
+                console.print(line);
+            }
+            Ok(())
+        },
+    );
+
+    console.register(
+        "find",
+        Signature::from_parameters(vec![Parameter::named("text", ValueKind::String)])
+            .with_description("Lists commands, cvars and aliases mentioning the text"),
+        |console, values| {
+            let lines = console.find(values[0].as_str().unwrap());
+            if lines.is_empty() {
+                console.print("no matches");
+            }
+            for line in lines {
+                console.print(line);
+            }
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        command::{Value, ValueKind},
+        cvar::{Cvar, CvarFlags},
+        registry::{Parameter, Signature},
+        Console,
+    };
+
+    fn console() -> Console {
+        let mut console = Console::new();
+        console.register(
+            "teleport",
+            Signature::from_parameters(vec![
+                Parameter::named("position", ValueKind::Vec3).with_description("Where to go"),
+                Parameter::named("relative", ValueKind::Boolean).with_default(Value::Boolean(false)),
+            ])
+            .with_description("Moves the player"),
+            |_, _| Ok(()),
+        );
+        console.register_cvar(
+            Cvar::new("physics_steps", Value::Integer(10))
+                .with_range(1.0, 100.0)
+                .with_flags(CvarFlags::PERSIST)
+                .with_description("Physics sub-steps per frame"),
+        );
+        console
+    }
+
+    #[test]
+    fn test_help() {
+        let mut console = console();
+
+        console.run("help teleport").unwrap();
+        assert_eq!(
+            console.drain_output(),
+            vec![
+                "teleport <position: vec3> [relative: boolean = false]",
+                "  Moves the player",
+                "    position: vec3 - Where to go",
+                "    relative: boolean = false",
+            ]
+        );
+
+        console.run("help physics_steps").unwrap();
+        assert_eq!(
+            console.drain_output(),
+            vec![
+                "physics_steps <integer>",
+                "  Physics sub-steps per frame",
+                "    value: 10",
+                "    default: 10",
+                "    range: 1..100",
+                "    flags: persist",
+            ]
+        );
+
+        console.run("help").unwrap();
+        let all = console.drain_output();
+        assert!(all.contains(&"teleport <position: vec3> [relative: boolean = false] - Moves the player".to_owned()));
+        assert!(all.contains(&"physics_steps <integer> = 10 - Physics sub-steps per frame".to_owned()));
+
+        assert!(console.run("help nothing").is_err());
+    }
+
+    #[test]
+    fn test_find() {
+        let mut console = console();
+        console.run("alias warp \"teleport (0, 0, 0)\"").unwrap();
+
+        console.run("find PHYSICS").unwrap();
+        assert_eq!(
+            console.drain_output(),
+            vec!["physics_steps <integer> = 10 - Physics sub-steps per frame"]
+        );
+
+        console.run("find teleport").unwrap();
+        assert_eq!(console.drain_output().len(), 2);
+
+        console.run("find nothing_matches_this").unwrap();
+        assert_eq!(console.drain_output(), vec!["no matches"]);
+    }
+
+    #[test]
+    fn test_export() {
+        let console = console();
+
+        let md = console.export_markdown();
+        assert!(md.contains("### `teleport`\n\nMoves the player\n\n```\nteleport <position: vec3> [relative: boolean = false]\n```\n"));
+        assert!(md.contains("| `relative` | boolean | `false` |  |\n"));
+        assert!(md.contains("| `physics_steps` | integer | `10` | 1..100 | persist | Physics sub-steps per frame |\n"));
+
+        let json = console.export_json();
+        assert!(json.contains(
+            "{\"name\":\"teleport\",\"description\":\"Moves the player\",\"parameters\":[\
+             {\"name\":\"position\",\"type\":\"vec3\",\"default\":null,\"description\":\"Where to go\"},\
+             {\"name\":\"relative\",\"type\":\"boolean\",\"default\":\"false\",\"description\":\"\"}]}"
+        ));
+        assert!(json.contains(
+            "{\"name\":\"physics_steps\",\"type\":\"integer\",\"default\":\"10\",\"min\":1,\"max\":100,\
+             \"flags\":[\"persist\"],\"description\":\"Physics sub-steps per frame\"}"
+        ));
+        // Defaults holding quotes must stay valid JSON.
+        assert!(json.contains("{\"name\":\"name\",\"type\":\"string\",\"default\":\"\\\"\\\"\""));
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::VecDeque, path::Path};
+
+use crate::Error;
+
+pub const DEFAULT_HISTORY_SIZE: usize = 512;
+
+/// Bounded history of executed lines with up/down navigation and reverse incremental search.
+///
+/// Nothing here knows about rendering or key handling, front ends map their own input onto
+/// [`History::older`], [`History::newer`] and [`History::search`].
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: VecDeque<String>,
+    limit: usize,
+    /// Index into `entries` while navigating, `None` when editing a fresh line.
+    cursor: Option<usize>,
+    /// Line that was being edited before navigation started.
+    draft: String,
+    search: Option<Search>,
+}
+
+#[derive(Debug, Clone)]
+struct Search {
+    query: String,
+    /// Index of the current match, earlier matches are found below it.
+    index: Option<usize>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_SIZE)
+    }
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit,
+            cursor: None,
+            draft: String::new(),
+            search: None,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.truncate();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Records an executed line, blank lines and repeats of the newest entry are dropped.
+    pub fn push(&mut self, line: &str) {
+        self.reset();
+
+        let line = line.trim_end();
+        if line.trim().is_empty() || self.entries.back().is_some_and(|l| l == line) {
+            return;
+        }
+
+        self.entries.push_back(line.to_owned());
+        self.truncate();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.reset();
+    }
+
+    /// Stops navigating and searching, the next [`History::older`] starts from the newest entry.
+    pub fn reset(&mut self) {
+        self.cursor = None;
+        self.draft.clear();
+        self.search = None;
+    }
+
+    /// Moves to the older entry, `current` is the line being edited and is restored by
+    /// navigating back past the newest entry.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let index = match self.cursor {
+            None => {
+                self.draft = current.to_owned();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(0) => 0,
+            Some(i) => i - 1,
+        };
+        self.cursor = Some(index);
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Moves to the newer entry, returning the draft once past the newest one.
+    pub fn newer(&mut self) -> Option<&str> {
+        let index = self.cursor?;
+        if index + 1 < self.entries.len() {
+            self.cursor = Some(index + 1);
+            self.entries.get(index + 1).map(String::as_str)
+        } else {
+            self.cursor = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Updates the reverse search query and returns the newest matching entry.
+    pub fn search(&mut self, query: &str) -> Option<&str> {
+        let index = self.rfind(query, self.entries.len());
+        self.search = Some(Search {
+            query: query.to_owned(),
+            index,
+        });
+        index.map(|i| self.entries[i].as_str())
+    }
+
+    /// Steps to the next older match of the current query, like pressing Ctrl-R again.
+    /// Stays on the current match when there are no older ones.
+    pub fn search_next(&mut self) -> Option<&str> {
+        let search = self.search.as_ref()?;
+        let from = search.index.unwrap_or(self.entries.len());
+        let index = self.rfind(&search.query, from).or(search.index);
+
+        if let Some(search) = &mut self.search {
+            search.index = index;
+        }
+        index.map(|i| self.entries[i].as_str())
+    }
+
+    fn rfind(&self, query: &str, before: usize) -> Option<usize> {
+        self.entries
+            .iter()
+            .take(before)
+            .rposition(|l| l.contains(query))
+    }
+
+    fn truncate(&mut self) {
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Writes one entry per line, oldest first.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(entry);
+            contents.push('\n');
+        }
+        std::fs::write(path, contents).map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Appends the entries of a file written by [`History::save`], a missing file is not an error.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(Error::Io(format!("{}: {}", path.display(), e))),
+        };
+
+        for line in contents.lines() {
+            self.push(line);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+
+    #[test]
+    fn test_navigation() {
+        let mut history = History::new(3);
+        for line in ["a", "b", "b", "", "c", "d"] {
+            history.push(line);
+        }
+        assert_eq!(history.iter().collect::<Vec<_>>(), vec!["b", "c", "d"]);
+
+        assert_eq!(history.older("draft"), Some("d"));
+        assert_eq!(history.older("d"), Some("c"));
+        assert_eq!(history.older("c"), Some("b"));
+        assert_eq!(history.older("b"), Some("b"));
+        assert_eq!(history.newer(), Some("c"));
+        assert_eq!(history.newer(), Some("d"));
+        assert_eq!(history.newer(), Some("draft"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut history = History::default();
+        for line in ["r_vsync true", "physics_steps 10", "r_gamma 2.2", "say hi"] {
+            history.push(line);
+        }
+
+        assert_eq!(history.search("r_"), Some("r_gamma 2.2"));
+        assert_eq!(history.search_next(), Some("r_vsync true"));
+        assert_eq!(history.search_next(), Some("r_vsync true"));
+        assert_eq!(history.search("phys"), Some("physics_steps 10"));
+        assert_eq!(history.search("nothing"), None);
+        assert_eq!(history.search_next(), None);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join(format!("dg_console_history_{}.txt", std::process::id()));
+
+        let mut history = History::default();
+        history.push("jump");
+        history.push("r_vsync false");
+        history.save(&path).unwrap();
+
+        let mut loaded = History::new(1);
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded.iter().collect::<Vec<_>>(), vec!["r_vsync false"]);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.load(&path).is_ok());
+    }
+}
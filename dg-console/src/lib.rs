@@ -1,34 +1,482 @@
 #![feature(new_range_api)]
 
 // pub mod command_parser_combinator;
+pub mod bind;
 pub mod command;
+pub mod complete;
+pub mod cvar;
+pub mod expr;
+pub mod help;
+pub mod history;
+pub mod output;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod registry;
+pub mod script;
 use core::range::Range;
+use std::collections::BTreeMap;
 
+use bind::Bindings;
+use command::{Command, Value, ValueKind};
+use cvar::{Cvar, Cvars};
+use history::History;
+use output::{ConsoleLogger, Level, Line, Output};
+use registry::{Registry, Signature};
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone)]
 pub enum ParseErrorKind {
     #[error("unexpected end of input")]
     UnexpectedEoi,
+    #[error("unexpected token")]
+    UnexpectedToken,
+    #[error("unknown token")]
+    UnknownToken,
+    #[error("expected a command name")]
+    ExpectedCommandName,
+    #[error("unterminated string")]
+    UnterminatedString,
+    #[error("invalid number literal")]
+    InvalidNumber,
+    #[error("invalid escape sequence")]
+    InvalidEscape,
+    #[error("vectors need 2 to 4 numeric components")]
+    InvalidVector,
+    #[error("arguments are nested more than {} levels deep", command::MAX_NESTING)]
+    TooDeep,
 }
 
 #[derive(Error, Debug, Clone)]
 pub enum Error {
-    #[error("parse error")]
+    #[error("parse error: {kind}")]
     ParseError {
         span: Range<usize>,
         kind: ParseErrorKind,
+    },
+    #[error("unknown command `{0}`")]
+    UnknownCommand(String),
+    #[error("`{name}` expects {} argument(s) but {found} were given", arity(*min, *max))]
+    WrongArity {
+        name: String,
+        min: usize,
+        max: usize,
+        found: usize,
+    },
+    #[error("`{name}` expects argument {index} to be {expected} but found {found}")]
+    TypeMismatch {
+        name: String,
+        index: usize,
+        expected: ValueKind,
+        found: ValueKind,
+    },
+    #[error("unknown cvar `{0}`")]
+    UnknownCvar(String),
+    #[error("`{0}` is read-only")]
+    ReadOnly(String),
+    #[error("`{0}` can only be changed with cheats enabled")]
+    CheatProtected(String),
+    #[error("{value} is out of range for `{name}`")]
+    OutOfRange {
+        name: String,
+        value: String,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    #[error("cannot apply `{op}` to {left} and {right}")]
+    InvalidOperands {
+        op: String,
+        left: ValueKind,
+        right: ValueKind,
+    },
+    #[error("cannot apply `{op}` to {found}")]
+    InvalidOperand { op: String, found: ValueKind },
+    #[error("vector components must be numbers but found {0}")]
+    InvalidComponent(ValueKind),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("integer overflow")]
+    IntegerOverflow,
+    #[error("alias loop `{0}`")]
+    AliasLoop(String),
+    #[error("command nesting exceeds {MAX_DEPTH} levels")]
+    RecursionLimit,
+    #[error("{0}")]
+    Io(String),
+    #[error("{path}:{line}: {error}")]
+    Script {
+        path: String,
+        line: usize,
+        text: String,
+        error: Box<Error>,
+    },
+    #[error("{0}")]
+    CommandFailed(String),
+}
+
+fn arity(min: usize, max: usize) -> String {
+    if min == max {
+        min.to_string()
+    } else {
+        format!("{} to {}", min, max)
+    }
+}
+
+impl Error {
+    /// Renders the error for display under the line that caused it,
+    /// parse errors underline the offending span with carets.
+    pub fn render(&self, line: &str) -> String {
+        match self {
+            Error::ParseError { span, kind } => {
+                let start = span.start.min(line.len());
+                let end = span.end.clamp(start, line.len());
+                let column = line[..start].chars().count();
+                let width = line[start..end].chars().count().max(1);
+
+                format!(
+                    "error: {}\n  {}\n  {}{}",
+                    kind,
+                    line,
+                    " ".repeat(column),
+                    "^".repeat(width)
+                )
+            }
+            Error::Script {
+                path,
+                line,
+                text,
+                error,
+            } => format!("{}:{}: {}", path, line, error.render(text)),
+            e => format!("error: {}", e),
+        }
     }
 }
 
+/// How deeply aliases and `exec` may nest before execution is aborted.
+pub const MAX_DEPTH: usize = 64;
+
 pub struct Console {
+    registry: Registry,
+    cvars: Cvars,
+    aliases: BTreeMap<String, String>,
+    alias_stack: Vec<String>,
+    depth: usize,
+    cheats: bool,
+    output: Output,
+    history: History,
+    bindings: Bindings,
+    /// Lines queued by input, run on the next [`Console::update`].
+    pending: Vec<String>,
+    /// Set by `wait` while the commands it interrupted unwind.
+    wait: Option<script::Wait>,
+    waits: Vec<script::Wait>,
+}
 
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Console {
-    pub fn run(&self, command: &str) -> Result<(), Error> {
-        let command = command::parse_command(command);
-        
-        Ok(())
+    /// Creates a console with the built-in `alias`, `unalias`, `exec`, `help`, `find` and binding
+    /// commands.
+    pub fn new() -> Self {
+        let mut console = Self {
+            registry: Registry::new(),
+            cvars: Cvars::new(),
+            aliases: BTreeMap::new(),
+            alias_stack: Vec::new(),
+            depth: 0,
+            cheats: false,
+            output: Output::default(),
+            history: History::default(),
+            bindings: Bindings::new(),
+            pending: Vec::new(),
+            wait: None,
+            waits: Vec::new(),
+        };
+        script::register_builtins(&mut console);
+        help::register_builtins(&mut console);
+        bind::register_builtins(&mut console);
+        console
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn register<F>(&mut self, name: &str, signature: Signature, handler: F)
+    where
+        F: Fn(&mut Console, &[Value]) -> Result<(), Error> + 'static,
+    {
+        self.registry.register(name, signature, handler);
+    }
+
+    pub fn cvars(&self) -> &Cvars {
+        &self.cvars
+    }
+
+    pub fn cvars_mut(&mut self) -> &mut Cvars {
+        &mut self.cvars
+    }
+
+    pub fn register_cvar(&mut self, cvar: Cvar) {
+        self.cvars.register(cvar);
+    }
+
+    pub fn cheats(&self) -> bool {
+        self.cheats
+    }
+
+    pub fn set_cheats(&mut self, cheats: bool) {
+        self.cheats = cheats;
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.log(Level::Info, line);
+    }
+
+    pub fn log(&mut self, level: Level, line: impl Into<String>) {
+        self.output.push(Line::new(level, line));
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn output_mut(&mut self) -> &mut Output {
+        &mut self.output
+    }
+
+    /// Creates a `log` logger feeding this console, see [`ConsoleLogger::install`].
+    pub fn logger(&mut self, level: log::LevelFilter) -> ConsoleLogger {
+        self.output.logger(level)
+    }
+
+    /// Takes the text of everything printed or logged since the last call.
+    pub fn drain_output(&mut self) -> Vec<String> {
+        self.output.pump_log();
+        self.output.drain().into_iter().map(|l| l.to_string()).collect()
+    }
+
+    /// Runs `line` and returns what it printed separately from the rest of the output.
+    pub fn run_captured(&mut self, line: &str) -> (Result<(), Error>, Vec<Line>) {
+        self.output.begin_capture();
+        let result = self.run(line);
+        let captured = self.output.end_capture();
+        (result, captured)
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
     }
-}
\ No newline at end of file
+
+    /// Runs a line typed by the user, recording it in the history even if it fails.
+    pub fn submit(&mut self, line: &str) -> Result<(), Error> {
+        self.history.push(line);
+        self.run(line)
+    }
+
+    /// Runs a line of `;` separated commands, stopping at the first error.
+    pub fn run(&mut self, line: &str) -> Result<(), Error> {
+        if self.depth == 0 {
+            self.schedule_wait();
+        }
+        let commands = command::parse_commands(line)?;
+        self.run_commands(commands)
+    }
+
+    /// Advances the console by a frame, running commands queued by input and resuming waits.
+    /// Their errors are logged to the output, along with records from the `log` crate.
+    pub fn update(&mut self) {
+        self.output.pump_log();
+        // Earlier waits count this frame before queued lines run, so theirs start counting next.
+        self.update_waits();
+        let pending = std::mem::take(&mut self.pending);
+        for line in pending {
+            if let Err(e) = self.run(&line) {
+                self.log(Level::Error, e.render(&line));
+            }
+        }
+        self.schedule_wait();
+    }
+
+    pub fn execute(&mut self, command: &Command) -> Result<(), Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::RecursionLimit);
+        }
+
+        self.depth += 1;
+        let result = self.dispatch(command);
+        self.depth -= 1;
+        result
+    }
+
+    /// Commands take precedence over aliases, which take precedence over cvars.
+    fn dispatch(&mut self, command: &Command) -> Result<(), Error> {
+        let name = command.name();
+        let values = command.evaluate(|name| self.cvars.value(name).cloned())?;
+
+        if let Some(entry) = self.registry.get(name) {
+            let values = entry.signature.check(name, &values)?;
+            // Cloned so the handler is free to mutate the console, including the registry.
+            let handler = entry.handler.clone();
+            return handler(self, &values);
+        }
+
+        if self.aliases.contains_key(name) {
+            return match values.len() {
+                0 => self.run_alias(name),
+                found => Err(Error::WrongArity {
+                    name: name.to_owned(),
+                    min: 0,
+                    max: 0,
+                    found,
+                }),
+            };
+        }
+
+        if self.cvars.contains(name) {
+            return self.run_cvar(name, &values);
+        }
+
+        Err(Error::UnknownCommand(name.to_owned()))
+    }
+
+    /// Prints the cvar with no arguments and sets it with one.
+    fn run_cvar(&mut self, name: &str, values: &[Value]) -> Result<(), Error> {
+        match values {
+            [] => {
+                let cvar = self.cvars.get(name).expect("cvar checked by caller");
+                let line = format!("{} = {} (default {})", name, cvar.value(), cvar.default());
+                self.print(line);
+                Ok(())
+            }
+            [value] => self.cvars.set_from_console(name, value.clone(), self.cheats),
+            _ => Err(Error::WrongArity {
+                name: name.to_owned(),
+                min: 0,
+                max: 1,
+                found: values.len(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use crate::{
+        command::{Value, ValueKind},
+        cvar::{Cvar, CvarFlags},
+        registry::Signature,
+        Console, Error,
+    };
+
+    #[test]
+    fn test_run() {
+        let mut console = Console::new();
+        let steps = Rc::new(Cell::new(0));
+
+        let s = steps.clone();
+        console.register(
+            "physics_steps",
+            Signature::new(&[ValueKind::Integer]),
+            move |_, values| {
+                match values[0] {
+                    Value::Integer(v) => s.set(v),
+                    _ => unreachable!(),
+                }
+                Ok(())
+            },
+        );
+
+        console.run("physics_steps 10").unwrap();
+        assert_eq!(steps.get(), 10);
+
+        assert!(matches!(
+            console.run("physic_steps 10"),
+            Err(Error::UnknownCommand(_))
+        ));
+        assert!(matches!(
+            console.run("physics_steps"),
+            Err(Error::WrongArity { .. })
+        ));
+        assert!(matches!(
+            console.run("physics_steps \"ten\""),
+            Err(Error::TypeMismatch { .. })
+        ));
+        assert!(console.run("").is_ok());
+
+        assert!(console.submit("physics_steps 20").is_ok());
+        assert!(console.submit("physics_steps").is_err());
+        assert_eq!(
+            console.history().iter().collect::<Vec<_>>(),
+            vec!["physics_steps 20", "physics_steps"]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let line = "say \"hello";
+        let e = Console::new().run(line).unwrap_err();
+        assert_eq!(
+            e.render(line),
+            "error: unterminated string\n  say \"hello\n      ^^^^^^"
+        );
+
+        let line = "say [1, 2";
+        let e = Console::new().run(line).unwrap_err();
+        assert_eq!(
+            e.render(line),
+            "error: unexpected end of input\n  say [1, 2\n           ^"
+        );
+
+        let e = Console::new().run("say").unwrap_err();
+        assert_eq!(e.render("say"), "error: unknown command `say`");
+    }
+
+    #[test]
+    fn test_run_cvar() {
+        let mut console = Console::new();
+        console.register_cvar(Cvar::new("r_vsync", Value::Boolean(true)));
+        console.register_cvar(Cvar::new("sv_gravity", Value::Float(9.8)).with_flags(CvarFlags::CHEAT));
+
+        console.run("r_vsync false").unwrap();
+        assert_eq!(console.cvars().value("r_vsync"), Some(&Value::Boolean(false)));
+
+        console.run("r_vsync").unwrap();
+        assert_eq!(console.drain_output(), vec!["r_vsync = false (default true)"]);
+
+        assert!(matches!(console.run("sv_gravity 0"), Err(Error::CheatProtected(_))));
+        console.set_cheats(true);
+        console.run("sv_gravity 0").unwrap();
+        assert_eq!(console.cvars().value("sv_gravity"), Some(&Value::Float(0.0)));
+
+        assert!(matches!(console.run("r_vsync true false"), Err(Error::WrongArity { .. })));
+    }
+
+    #[test]
+    fn test_update_log() {
+        let mut console = Console::new();
+        console.logger(log::LevelFilter::Warn).install().unwrap();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let listener = seen.clone();
+        console
+            .output_mut()
+            .subscribe(move |line| listener.borrow_mut().push(line.to_string()));
+
+        log::warn!(target: "dg_packer", "texture is not power of two");
+        assert!(seen.borrow().is_empty());
+        console.update();
+        assert_eq!(*seen.borrow(), vec!["[dg_packer] texture is not power of two"]);
+    }
+}
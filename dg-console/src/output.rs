@@ -0,0 +1,281 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    rc::Rc,
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+pub const DEFAULT_OUTPUT_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub level: Level,
+    /// Module that logged the line, empty for lines printed by commands.
+    pub target: String,
+    pub text: String,
+}
+
+impl Line {
+    pub fn new(level: Level, text: impl Into<String>) -> Self {
+        Self {
+            level,
+            target: String::new(),
+            text: text.into(),
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.target.is_empty() {
+            write!(f, "{}", self.text)
+        } else {
+            write!(f, "[{}] {}", self.target, self.text)
+        }
+    }
+}
+
+pub type Listener = Rc<dyn Fn(&Line)>;
+
+/// Ring buffer of console output.
+///
+/// While a capture is active lines go to the innermost capture instead of the buffer and
+/// listeners, so output can be routed back to whoever ran the command.
+pub struct Output {
+    lines: VecDeque<Line>,
+    capacity: usize,
+    listeners: Vec<Listener>,
+    captures: Vec<Vec<Line>>,
+    log: Option<(Sender<Line>, Receiver<Line>)>,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::new(DEFAULT_OUTPUT_CAPACITY)
+    }
+}
+
+impl Output {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity,
+            listeners: Vec::new(),
+            captures: Vec::new(),
+            log: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.lines.len() > self.capacity {
+            self.lines.pop_front();
+        }
+    }
+
+    pub fn push(&mut self, line: Line) {
+        if let Some(capture) = self.captures.last_mut() {
+            capture.push(line);
+            return;
+        }
+
+        for listener in &self.listeners {
+            listener(&line);
+        }
+
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Lines from oldest to newest.
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.lines.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn drain(&mut self) -> Vec<Line> {
+        self.lines.drain(..).collect()
+    }
+
+    /// Calls `listener` for every line added to the buffer from now on.
+    pub fn subscribe<F>(&mut self, listener: F)
+    where
+        F: Fn(&Line) + 'static,
+    {
+        self.listeners.push(Rc::new(listener));
+    }
+
+    pub fn begin_capture(&mut self) {
+        self.captures.push(Vec::new());
+    }
+
+    pub fn end_capture(&mut self) -> Vec<Line> {
+        self.captures.pop().unwrap_or_default()
+    }
+
+    /// Creates a [`ConsoleLogger`] whose records show up here after [`Output::pump_log`].
+    pub fn logger(&mut self, level: log::LevelFilter) -> ConsoleLogger {
+        let (sender, _) = self.log.get_or_insert_with(channel);
+        ConsoleLogger {
+            sender: sender.clone(),
+            level,
+        }
+    }
+
+    /// Moves records logged from any thread into the buffer.
+    pub fn pump_log(&mut self) {
+        let Some((_, receiver)) = &self.log else {
+            return;
+        };
+
+        let lines: Vec<Line> = receiver.try_iter().collect();
+        for line in lines {
+            self.push(line);
+        }
+    }
+}
+
+/// Forwards `log` records into a console's output.
+///
+/// Records are queued because a logger may be called from any thread while the console lives
+/// on one, they appear once the console pumps its log.
+pub struct ConsoleLogger {
+    sender: Sender<Line>,
+    level: log::LevelFilter,
+}
+
+impl ConsoleLogger {
+    /// Installs the logger as the global `log` logger.
+    pub fn install(self) -> Result<(), log::SetLoggerError> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let _ = self.sender.send(Line {
+            level: record.level().into(),
+            target: record.target().to_owned(),
+            text: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use log::Log;
+
+    use super::{Level, Line, Output};
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut output = Output::new(2);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let s = seen.clone();
+        output.subscribe(move |line| s.borrow_mut().push(line.text.clone()));
+
+        output.push(Line::new(Level::Info, "a"));
+        output.push(Line::new(Level::Warn, "b"));
+        output.push(Line::new(Level::Error, "c"));
+        let lines: Vec<_> = output.lines().map(|l| l.text.as_str()).collect();
+        assert_eq!(lines, vec!["b", "c"]);
+        assert_eq!(*seen.borrow(), vec!["a", "b", "c"]);
+
+        output.begin_capture();
+        output.push(Line::new(Level::Info, "captured"));
+        let captured = output.end_capture();
+        assert_eq!(captured, vec![Line::new(Level::Info, "captured")]);
+        assert_eq!(output.lines().count(), 2);
+        assert_eq!(seen.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_logger() {
+        let mut output = Output::default();
+        let logger = output.logger(log::LevelFilter::Warn);
+
+        let thread = std::thread::spawn(move || {
+            logger.log(
+                &log::Record::builder()
+                    .level(log::Level::Warn)
+                    .target("dg_packer::texture")
+                    .args(format_args!("texture is not power of two"))
+                    .build(),
+            );
+            logger.log(
+                &log::Record::builder()
+                    .level(log::Level::Info)
+                    .args(format_args!("filtered out"))
+                    .build(),
+            );
+        });
+        thread.join().unwrap();
+
+        assert_eq!(output.lines().count(), 0);
+        output.pump_log();
+        let lines: Vec<_> = output.lines().map(Line::to_string).collect();
+        assert_eq!(lines, vec!["[dg_packer::texture] texture is not power of two"]);
+        assert_eq!(output.lines().next().unwrap().level, Level::Warn);
+    }
+}
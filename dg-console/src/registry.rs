@@ -0,0 +1,256 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::{
+    command::{Value, ValueKind},
+    Console, Error,
+};
+
+pub type Handler = Rc<dyn Fn(&mut Console, &[Value]) -> Result<(), Error>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: Option<String>,
+    pub kind: ValueKind,
+    /// Makes the parameter optional, only trailing parameters may have one.
+    pub default: Option<Value>,
+    pub description: String,
+    /// Values offered by completion, for arrays these are the element values.
+    pub choices: Vec<Value>,
+}
+
+impl Parameter {
+    pub fn new(kind: ValueKind) -> Self {
+        Self {
+            name: None,
+            kind,
+            default: None,
+            description: String::new(),
+            choices: Vec::new(),
+        }
+    }
+
+    pub fn named(name: &str, kind: ValueKind) -> Self {
+        Self::new(kind).with_name(name)
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    pub fn with_default(mut self, default: Value) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    pub fn with_choices(mut self, choices: &[Value]) -> Self {
+        self.choices = choices.to_vec();
+        self
+    }
+
+    /// Formats the parameter as `<name: kind>`, or `[name: kind = default]` when optional.
+    pub fn hint(&self) -> String {
+        let inner = match &self.name {
+            Some(name) => format!("{}: {}", name, self.kind),
+            None => self.kind.to_string(),
+        };
+        match &self.default {
+            Some(default) => format!("[{} = {}]", inner, default),
+            None => format!("<{}>", inner),
+        }
+    }
+}
+
+/// The arguments a command expects, in order, along with its documentation.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Signature {
+    parameters: Vec<Parameter>,
+    description: String,
+}
+
+impl Signature {
+    pub fn new(kinds: &[ValueKind]) -> Self {
+        Self::from_parameters(kinds.iter().copied().map(Parameter::new).collect())
+    }
+
+    /// # Panics
+    ///
+    /// If a parameter without a default follows one with a default.
+    pub fn from_parameters(parameters: Vec<Parameter>) -> Self {
+        let required = parameters.iter().take_while(|p| p.default.is_none()).count();
+        assert!(
+            parameters[required..].iter().all(|p| p.default.is_some()),
+            "parameters with defaults must come last"
+        );
+
+        Self {
+            parameters,
+            description: String::new(),
+        }
+    }
+
+    /// One-line description shown by `help` and `find`.
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Number of parameters without a default.
+    pub fn required(&self) -> usize {
+        self.parameters.iter().filter(|p| p.default.is_none()).count()
+    }
+
+    /// Formats the signature as `name <kind> <name: kind> [name: kind = default]`.
+    pub fn hint(&self, name: &str) -> String {
+        let mut hint = name.to_owned();
+        for parameter in &self.parameters {
+            hint.push(' ');
+            hint.push_str(&parameter.hint());
+        }
+        hint
+    }
+
+    /// Checks `values` against the signature, widening integers to floats where a float is
+    /// expected and filling in defaults for missing trailing arguments.
+    pub fn check(&self, name: &str, values: &[Value]) -> Result<Vec<Value>, Error> {
+        if values.len() < self.required() || values.len() > self.parameters.len() {
+            return Err(Error::WrongArity {
+                name: name.to_owned(),
+                min: self.required(),
+                max: self.parameters.len(),
+                found: values.len(),
+            });
+        }
+
+        self.parameters
+            .iter()
+            .enumerate()
+            .map(|(index, parameter)| match values.get(index) {
+                Some(value) => value.coerce(parameter.kind).ok_or_else(|| Error::TypeMismatch {
+                    name: name.to_owned(),
+                    index,
+                    expected: parameter.kind,
+                    found: value.kind(),
+                }),
+                None => Ok(parameter.default.clone().expect("arity checked above")),
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct CommandEntry {
+    pub name: String,
+    pub signature: Signature,
+    pub handler: Handler,
+}
+
+#[derive(Default)]
+pub struct Registry {
+    commands: BTreeMap<String, CommandEntry>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a command, replacing any previous command with the same name.
+    pub fn register<F>(&mut self, name: &str, signature: Signature, handler: F)
+    where
+        F: Fn(&mut Console, &[Value]) -> Result<(), Error> + 'static,
+    {
+        self.commands.insert(
+            name.to_owned(),
+            CommandEntry {
+                name: name.to_owned(),
+                signature,
+                handler: Rc::new(handler),
+            },
+        );
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Option<CommandEntry> {
+        self.commands.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CommandEntry> {
+        self.commands.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CommandEntry> {
+        self.commands.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parameter, Signature};
+    use crate::{
+        command::{Value, ValueKind},
+        Error,
+    };
+
+    #[test]
+    fn test_signature() {
+        let signature = Signature::new(&[ValueKind::Integer, ValueKind::Float]);
+
+        let r = signature.check("test", &[Value::Integer(1), Value::Integer(2)]);
+        assert_eq!(r.unwrap(), vec![Value::Integer(1), Value::Float(2.0)]);
+
+        let r = signature.check("test", &[Value::Integer(1)]);
+        assert!(matches!(r, Err(Error::WrongArity { min: 2, max: 2, found: 1, .. })));
+
+        let r = signature.check("test", &[Value::Float(1.0), Value::Float(2.0)]);
+        assert!(matches!(
+            r,
+            Err(Error::TypeMismatch {
+                index: 0,
+                expected: ValueKind::Integer,
+                found: ValueKind::Float,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_signature_defaults() {
+        let signature = Signature::from_parameters(vec![
+            Parameter::named("target", ValueKind::String),
+            Parameter::named("amount", ValueKind::Integer).with_default(Value::Integer(100)),
+        ]);
+        assert_eq!(signature.hint("heal"), "heal <target: string> [amount: integer = 100]");
+
+        let r = signature.check("heal", &[Value::String("player".into())]);
+        assert_eq!(r.unwrap(), vec![Value::String("player".into()), Value::Integer(100)]);
+
+        let r = signature.check("heal", &[]);
+        assert!(matches!(r, Err(Error::WrongArity { min: 1, max: 2, found: 0, .. })));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_signature_default_order() {
+        Signature::from_parameters(vec![
+            Parameter::new(ValueKind::Integer).with_default(Value::Integer(1)),
+            Parameter::new(ValueKind::Integer),
+        ]);
+    }
+}
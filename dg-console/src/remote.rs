@@ -0,0 +1,304 @@
+//! Line based remote console for dedicated servers and automated testing.
+//!
+//! The server never blocks and is polled from the thread that owns the [`Console`], typically
+//! once per frame. Every line a client sends is run through [`Console::run`] and answered with
+//! the output it printed, each line tagged so scripts can tell the parts apart:
+//!
+//! ```text
+//! out <printed line>
+//! err <rendered error line>
+//! done ok | done error
+//! ```
+//!
+//! When a password is set the first line of a connection must be that password, it is answered
+//! with `auth ok` or `auth failed` after which the connection is closed.
+//!
+//! [`RemoteServer::bind_tcp`] only listens on loopback addresses, anything reachable from other
+//! machines has to be asked for with [`RemoteServer::bind_tcp_public`].
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+};
+
+use crate::{Console, Error};
+
+/// Longest line a client may send before being disconnected.
+pub const MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// Most bytes read from one client per poll, the rest waits for the next poll.
+const MAX_READ_PER_POLL: usize = 4 * MAX_LINE_LENGTH;
+
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+trait Stream: Read + Write {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Stream for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    fn accept(&self) -> io::Result<Box<dyn Stream>> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+struct Connection {
+    stream: Box<dyn Stream>,
+    buffer: Vec<u8>,
+    authenticated: bool,
+    /// The client finished sending, its remaining lines are still answered.
+    eof: bool,
+    closed: bool,
+}
+
+impl Connection {
+    fn send(&mut self, tag: &str, line: &str) {
+        if self.closed {
+            return;
+        }
+        let message = format!("{} {}\n", tag, line);
+        if self.stream.write_all(message.as_bytes()).is_err() {
+            self.closed = true;
+        }
+    }
+
+    /// Reads whatever is available without blocking and returns the complete lines.
+    ///
+    /// The connection is closed without returning anything as soon as a line gets longer than
+    /// [`MAX_LINE_LENGTH`].
+    fn read_lines(&mut self) -> Vec<String> {
+        let mut chunk = [0u8; 4096];
+        let mut read = 0;
+        // Complete lines are taken out every poll, so the buffer starts with the partial one.
+        let mut partial = self.buffer.len();
+        while read < MAX_READ_PER_POLL {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(n) => {
+                    let chunk = &chunk[..n];
+                    read += n;
+                    let first = chunk.iter().position(|&b| b == b'\n').unwrap_or(n);
+                    if partial + first > MAX_LINE_LENGTH {
+                        self.closed = true;
+                        return Vec::new();
+                    }
+                    partial = match chunk.iter().rposition(|&b| b == b'\n') {
+                        Some(last) => n - last - 1,
+                        None => partial + n,
+                    };
+                    self.buffer.extend_from_slice(chunk);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            lines.push(line.trim_end_matches(['\r', '\n']).to_owned());
+        }
+        lines
+    }
+}
+
+/// Compares without stopping at the first difference, so the time taken doesn't tell a client
+/// how much of its guess was right.
+fn constant_time_eq(guess: &[u8], password: &[u8]) -> bool {
+    let mut diff = guess.len() ^ password.len();
+    for (i, byte) in guess.iter().enumerate() {
+        diff |= usize::from(byte ^ password.get(i).copied().unwrap_or(0));
+    }
+    diff == 0
+}
+
+pub struct RemoteServer {
+    listener: Listener,
+    password: Option<String>,
+    connections: Vec<Connection>,
+}
+
+impl RemoteServer {
+    /// Listens on a loopback TCP address, clients must send `password` first when one is given.
+    ///
+    /// Fails if `addr` resolves to an address other machines could connect to.
+    pub fn bind_tcp(addr: impl ToSocketAddrs, password: Option<&str>) -> Result<Self, Error> {
+        let addrs: Vec<SocketAddr> = addr
+            .to_socket_addrs()
+            .map_err(|e| Error::Io(e.to_string()))?
+            .collect();
+        if let Some(addr) = addrs.iter().find(|a| !a.ip().is_loopback()) {
+            return Err(Error::Io(format!(
+                "{} isn't a loopback address, use `bind_tcp_public` to listen on it",
+                addr
+            )));
+        }
+        Self::bind_tcp_public(&addrs[..], password)
+    }
+
+    /// Listens on any TCP address, including ones other machines can connect to.
+    pub fn bind_tcp_public(
+        addr: impl ToSocketAddrs,
+        password: Option<&str>,
+    ) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).map_err(|e| Error::Io(e.to_string()))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        Ok(Self::new(Listener::Tcp(listener), password))
+    }
+
+    /// Listens on a Unix socket, the socket file is removed when the server is dropped.
+    #[cfg(unix)]
+    pub fn bind_unix(path: impl AsRef<Path>, password: Option<&str>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let listener = UnixListener::bind(path)
+            .map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        Ok(Self::new(Listener::Unix(listener, path.to_owned()), password))
+    }
+
+    fn new(listener: Listener, password: Option<&str>) -> Self {
+        Self {
+            listener,
+            password: password.map(str::to_owned),
+            connections: Vec::new(),
+        }
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Listener::Unix(..) => None,
+        }
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Accepts new clients and runs every complete line they have sent.
+    pub fn poll(&mut self, console: &mut Console) {
+        while let Ok(stream) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_err() {
+                continue;
+            }
+            self.connections.push(Connection {
+                stream,
+                buffer: Vec::new(),
+                authenticated: self.password.is_none(),
+                eof: false,
+                closed: false,
+            });
+        }
+
+        for connection in &mut self.connections {
+            let lines = connection.read_lines();
+            // Responses are written blocking, bounded by the write timeout.
+            if lines.is_empty() || connection.stream.set_nonblocking(false).is_err() {
+                continue;
+            }
+
+            for line in lines {
+                if connection.closed {
+                    break;
+                }
+
+                if !connection.authenticated {
+                    let password = self.password.as_deref().unwrap_or_default();
+                    if constant_time_eq(line.as_bytes(), password.as_bytes()) {
+                        connection.authenticated = true;
+                        connection.send("auth", "ok");
+                    } else {
+                        connection.send("auth", "failed");
+                        connection.closed = true;
+                    }
+                    continue;
+                }
+
+                Self::run(connection, console, &line);
+            }
+
+            if connection.stream.set_nonblocking(true).is_err() {
+                connection.closed = true;
+            }
+        }
+
+        self.connections.retain(|c| !c.closed && !c.eof);
+    }
+
+    fn run(connection: &mut Connection, console: &mut Console, line: &str) {
+        let (result, output) = console.run_captured(line);
+
+        for printed in output {
+            connection.send("out", &printed.to_string());
+        }
+
+        match result {
+            Ok(()) => connection.send("done", "ok"),
+            Err(e) => {
+                for rendered in e.render(line).lines() {
+                    connection.send("err", rendered);
+                }
+                connection.send("done", "error");
+            }
+        }
+    }
+}
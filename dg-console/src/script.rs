@@ -0,0 +1,361 @@
+use std::path::Path;
+
+use crate::{
+    command::{self, Command, Value, ValueKind},
+    output::Level,
+    registry::{Parameter, Signature},
+    Console, Error,
+};
+
+/// Work left over when `wait` paused a line, alias or script.
+pub(crate) enum Deferred {
+    Command(Command),
+    /// A line of a script, parsed when it is reached.
+    Line(String),
+}
+
+pub(crate) struct Wait {
+    /// Updates left before `rest` runs.
+    frames: u64,
+    rest: Vec<Deferred>,
+}
+
+impl Console {
+    /// Defines an alias expanding to `body`, a `;` separated list of commands.
+    pub fn set_alias(&mut self, name: &str, body: &str) -> Result<(), Error> {
+        // Validate now so a broken alias is reported where it's defined rather than where it's used.
+        command::parse_commands(body)?;
+        self.aliases.insert(name.to_owned(), body.to_owned());
+        Ok(())
+    }
+
+    pub fn remove_alias(&mut self, name: &str) -> Option<String> {
+        self.aliases.remove(name)
+    }
+
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub(crate) fn run_alias(&mut self, name: &str) -> Result<(), Error> {
+        if self.alias_stack.iter().any(|a| a == name) {
+            let mut chain = self.alias_stack.clone();
+            chain.push(name.to_owned());
+            return Err(Error::AliasLoop(chain.join(" -> ")));
+        }
+
+        let body = self.aliases[name].clone();
+        self.alias_stack.push(name.to_owned());
+        let result = self.run(&body);
+        self.alias_stack.pop();
+        result
+    }
+
+    /// Runs `source` line by line, stopping at the first failing line.
+    pub fn exec_str(&mut self, path: &str, source: &str) -> Result<(), Error> {
+        let mut lines = source.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            self.run(line).map_err(|error| Error::Script {
+                path: path.to_owned(),
+                line: i + 1,
+                text: line.to_owned(),
+                error: Box::new(error),
+            })?;
+
+            if let Some(wait) = &mut self.wait {
+                wait.rest.extend(lines.map(|(_, line)| Deferred::Line(line.to_owned())));
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes `commands` in order until one fails or waits, a wait defers the remaining ones.
+    pub(crate) fn run_commands(&mut self, commands: Vec<Command>) -> Result<(), Error> {
+        let mut commands = commands.into_iter();
+        while let Some(command) = commands.next() {
+            self.execute(&command)?;
+
+            // Every level a wait unwinds through appends what it had left, innermost first.
+            if let Some(wait) = &mut self.wait {
+                wait.rest.extend(commands.map(Deferred::Command));
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves a wait that finished unwinding to the scheduled waits.
+    pub(crate) fn schedule_wait(&mut self) {
+        if let Some(wait) = self.wait.take() {
+            self.waits.push(wait);
+        }
+    }
+
+    /// Counts down the scheduled waits by one frame and resumes the ones that are due.
+    pub(crate) fn update_waits(&mut self) {
+        self.schedule_wait();
+
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.waits)
+            .into_iter()
+            .partition(|w| w.frames <= 1);
+        self.waits = waiting;
+        for wait in &mut self.waits {
+            wait.frames -= 1;
+        }
+
+        for wait in due {
+            self.resume(wait.rest);
+        }
+    }
+
+    fn resume(&mut self, rest: Vec<Deferred>) {
+        let mut rest = rest.into_iter();
+        while let Some(deferred) = rest.next() {
+            let (result, line) = match deferred {
+                Deferred::Command(command) => (self.execute(&command), String::new()),
+                Deferred::Line(line) => (self.run(&line), line),
+            };
+            if let Err(e) = result {
+                self.log(Level::Error, e.render(&line));
+                break;
+            }
+
+            if let Some(wait) = &mut self.wait {
+                wait.rest.extend(rest);
+                break;
+            }
+        }
+        self.schedule_wait();
+    }
+
+    /// Whether commands are waiting on `wait` to resume.
+    pub fn is_waiting(&self) -> bool {
+        self.wait.is_some() || !self.waits.is_empty()
+    }
+
+    pub fn exec_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+        self.exec_str(&path.to_string_lossy(), &source)
+    }
+
+    /// Writes persistent cvars and bindings to a config file that can be loaded with `exec`.
+    pub fn save_config(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let config = self.cvars.save() + &self.bindings.save();
+        std::fs::write(path, config).map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))
+    }
+}
+
+pub(crate) fn register_builtins(console: &mut Console) {
+    console.register(
+        "alias",
+        Signature::from_parameters(vec![
+            Parameter::named("name", ValueKind::String),
+            Parameter::named("commands", ValueKind::String),
+        ])
+        .with_description("Defines a command that runs a `;` separated list of commands"),
+        |console, values| {
+            let name = values[0].as_str().unwrap();
+            let body = values[1].as_str().unwrap();
+            console.set_alias(name, body)
+        },
+    );
+
+    console.register(
+        "unalias",
+        Signature::from_parameters(vec![Parameter::named("name", ValueKind::String)])
+            .with_description("Removes an alias"),
+        |console, values| {
+            let name = values[0].as_str().unwrap();
+            console
+                .remove_alias(name)
+                .map(|_| ())
+                .ok_or_else(|| Error::CommandFailed(format!("no alias named `{}`", name)))
+        },
+    );
+
+    console.register(
+        "exec",
+        Signature::from_parameters(vec![Parameter::named("path", ValueKind::String)])
+            .with_description("Runs a config file line by line"),
+        |console, values| console.exec_file(values[0].as_str().unwrap()),
+    );
+
+    console.register(
+        "wait",
+        Signature::from_parameters(vec![
+            Parameter::named("frames", ValueKind::Integer).with_default(Value::Integer(1))
+        ])
+        .with_description("Delays the rest of the line, alias or script by a number of frames"),
+        |console, values| {
+            let frames = values[0].as_integer().unwrap();
+            let frames = u64::try_from(frames)
+                .map_err(|_| Error::CommandFailed("frames must not be negative".to_owned()))?;
+            console.wait = Some(Wait {
+                frames,
+                rest: Vec::new(),
+            });
+            Ok(())
+        },
+    );
+
+    console.register(
+        "writeconfig",
+        Signature::from_parameters(vec![Parameter::named("path", ValueKind::String)])
+            .with_description("Saves persistent cvars and bindings to a config file"),
+        |console, values| console.save_config(values[0].as_str().unwrap()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::{
+        command::{Value, ValueKind},
+        cvar::Cvar,
+        registry::Signature,
+        Console, Error,
+    };
+
+    fn counting_console() -> (Console, Rc<Cell<usize>>) {
+        let mut console = Console::new();
+        let count = Rc::new(Cell::new(0));
+        let c = count.clone();
+        console.register("jump", Signature::new(&[]), move |_, _| {
+            c.set(c.get() + 1);
+            Ok(())
+        });
+        (console, count)
+    }
+
+    #[test]
+    fn test_chaining() {
+        let (mut console, count) = counting_console();
+        console.run("jump; jump;jump // and a comment").unwrap();
+        assert_eq!(count.get(), 3);
+    }
+
+    #[test]
+    fn test_alias() {
+        let (mut console, count) = counting_console();
+        console.run("alias double_jump \"jump; jump\"").unwrap();
+        console.run("alias quad_jump \"double_jump; double_jump\"").unwrap();
+        console.run("quad_jump").unwrap();
+        assert_eq!(count.get(), 4);
+
+        console.run("alias a b; alias b c; alias c a").unwrap();
+        match console.run("a") {
+            Err(Error::AliasLoop(chain)) => assert_eq!(chain, "a -> b -> c -> a"),
+            r => panic!("expected alias loop, got {:?}", r),
+        }
+
+        assert!(matches!(
+            console.run("alias broken \"jump [1,\""),
+            Err(Error::ParseError { .. })
+        ));
+
+        console.run("unalias quad_jump").unwrap();
+        assert!(matches!(console.run("quad_jump"), Err(Error::UnknownCommand(_))));
+    }
+
+    #[test]
+    fn test_exec() {
+        let (mut console, count) = counting_console();
+
+        let path = std::env::temp_dir().join(format!("dg_console_exec_{}.cfg", std::process::id()));
+        std::fs::write(&path, "// autoexec\njump\n\njump; jump\n").unwrap();
+        console
+            .run(&format!("exec \"{}\"", path.display()))
+            .unwrap();
+        assert_eq!(count.get(), 3);
+
+        std::fs::write(&path, "jump\nunknown_command\n").unwrap();
+        match console.exec_file(&path) {
+            Err(Error::Script { line, error, .. }) => {
+                assert_eq!(line, 2);
+                assert!(matches!(*error, Error::UnknownCommand(_)));
+            }
+            r => panic!("expected script error, got {:?}", r),
+        }
+
+        // Relative paths don't need quotes.
+        let dir = format!("target/dg_console_exec_{}", std::process::id());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(format!("{}/file.cfg", dir), "jump\n").unwrap();
+        console.run(&format!("exec {}/file.cfg", dir)).unwrap();
+        assert_eq!(count.get(), 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // A config that executes itself hits the recursion limit instead of overflowing.
+        std::fs::write(&path, format!("exec \"{}\"\n", path.display())).unwrap();
+        assert!(console.exec_file(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_wait() {
+        let (mut console, count) = counting_console();
+        console.run("alias double_jump \"jump; wait 2; jump\"").unwrap();
+        console.run("double_jump; jump").unwrap();
+        assert_eq!(count.get(), 1);
+        assert!(console.is_waiting());
+
+        console.update();
+        assert_eq!(count.get(), 1);
+        console.update();
+        assert_eq!(count.get(), 3);
+        assert!(!console.is_waiting());
+
+        let path = std::env::temp_dir().join(format!("dg_console_wait_{}.cfg", std::process::id()));
+        std::fs::write(&path, "jump; wait\njump\nwait 0; unknown_command\njump\n").unwrap();
+        console.exec_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(count.get(), 4);
+        console.update();
+        assert_eq!(count.get(), 5);
+        console.update();
+        assert_eq!(count.get(), 5);
+        assert_eq!(console.drain_output(), vec!["error: unknown command `unknown_command`"]);
+
+        assert!(console.run("wait -1").is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut console = Console::new();
+        console.register_cvar(Cvar::new("base_speed", Value::Float(2.5)));
+        console.register_cvar(Cvar::new("speed", Value::Float(0.0)));
+
+        let speeds = Rc::new(std::cell::RefCell::new(Vec::new()));
+        let s = speeds.clone();
+        console.register(
+            "set_speed",
+            Signature::new(&[ValueKind::Float]),
+            move |_, values| {
+                s.borrow_mut().push(values[0].as_float().unwrap());
+                Ok(())
+            },
+        );
+
+        console.run("set_speed $base_speed*2").unwrap();
+        // Arguments are evaluated when their command runs, after earlier commands on the line.
+        console.run("base_speed 4; set_speed $base_speed; speed $base_speed-1.5").unwrap();
+        assert_eq!(*speeds.borrow(), vec![5.0, 4.0]);
+        assert_eq!(console.cvars().value("speed"), Some(&Value::Float(2.5)));
+
+        assert!(matches!(console.run("set_speed $missing"), Err(Error::UnknownCvar(_))));
+        assert!(matches!(
+            console.run("set_speed $base_speed>1"),
+            Err(Error::TypeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    thread,
+};
+
+use dg_console::{
+    command::{ValueKind, MAX_NESTING},
+    registry::Signature,
+    remote::{RemoteServer, MAX_LINE_LENGTH},
+    Console,
+};
+
+fn console() -> Console {
+    let mut console = Console::new();
+    console.register("echo", Signature::new(&[ValueKind::String]), |console, values| {
+        let line = values[0].as_str().unwrap().to_owned();
+        console.print(line);
+        Ok(())
+    });
+    console
+}
+
+/// Polls the server on this thread, which owns the console, until the client thread is done.
+fn serve<T>(server: &mut RemoteServer, console: &mut Console, client: thread::JoinHandle<T>) -> T {
+    while !client.is_finished() {
+        server.poll(console);
+        thread::yield_now();
+    }
+    client.join().unwrap()
+}
+
+fn read_response(reader: &mut impl BufRead) -> Vec<String> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end().to_owned();
+        let done = line.starts_with("done ");
+        lines.push(line);
+        if done {
+            return lines;
+        }
+    }
+}
+
+#[test]
+fn test_tcp() {
+    let mut console = console();
+    let mut server = RemoteServer::bind_tcp("127.0.0.1:0", Some("hunter2")).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+
+        writer.write_all(b"hunter2\n").unwrap();
+        let mut auth = String::new();
+        reader.read_line(&mut auth).unwrap();
+
+        writer.write_all(b"echo hello; echo \"world\"\n").unwrap();
+        let ok = read_response(&mut reader);
+
+        writer.write_all(b"echo \"unterminated\n").unwrap();
+        let error = read_response(&mut reader);
+
+        (auth, ok, error)
+    });
+
+    let (auth, ok, error) = serve(&mut server, &mut console, client);
+    assert_eq!(auth, "auth ok\n");
+    assert_eq!(ok, vec!["out hello", "out world", "done ok"]);
+    assert_eq!(
+        error,
+        vec![
+            "err error: unterminated string",
+            "err   echo \"unterminated",
+            "err        ^^^^^^^^^^^^^",
+            "done error",
+        ]
+    );
+
+    // Output captured for the remote client isn't left in the console.
+    assert!(console.drain_output().is_empty());
+}
+
+#[test]
+fn test_tcp_wrong_password() {
+    let mut console = console();
+    let mut server = RemoteServer::bind_tcp("127.0.0.1:0", Some("hunter2")).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"guess\necho sneaky\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    });
+
+    let response = serve(&mut server, &mut console, client);
+    assert_eq!(response, "auth failed\n");
+    assert_eq!(server.connections(), 0);
+}
+
+#[test]
+fn test_tcp_password_prefix() {
+    let mut console = console();
+    let mut server = RemoteServer::bind_tcp("127.0.0.1:0", Some("hunter2")).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    for guess in ["hunter", "hunter22", ""] {
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(format!("{}\n", guess).as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        assert_eq!(serve(&mut server, &mut console, client), "auth failed\n");
+    }
+}
+
+#[test]
+fn test_tcp_long_line() {
+    let mut console = console();
+    let mut server = RemoteServer::bind_tcp("127.0.0.1:0", None).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // The server hangs up before the line ends, so later writes may fail.
+        let line = format!("echo {}\n", "a".repeat(MAX_LINE_LENGTH));
+        let _ = stream.write_all(line.as_bytes());
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    });
+
+    assert_eq!(serve(&mut server, &mut console, client), "");
+    assert_eq!(server.connections(), 0);
+    assert!(console.drain_output().is_empty());
+}
+
+#[test]
+fn test_tcp_disconnect() {
+    let mut console = console();
+    let mut server = RemoteServer::bind_tcp("127.0.0.1:0", None).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // A line cut short by the disconnect isn't run.
+        stream.write_all(b"echo half").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    });
+    assert_eq!(serve(&mut server, &mut console, client), "");
+    assert_eq!(server.connections(), 0);
+
+    // Clients that vanish without reading their responses don't affect the next one.
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"echo gone\n").unwrap();
+    });
+    serve(&mut server, &mut console, client);
+
+    let client = thread::spawn(move || {
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        writer.write_all(b"echo next\n").unwrap();
+        read_response(&mut reader)
+    });
+    let response = serve(&mut server, &mut console, client);
+    assert_eq!(response, vec!["out next", "done ok"]);
+}
+
+#[test]
+fn test_tcp_loopback_only() {
+    assert!(RemoteServer::bind_tcp("0.0.0.0:0", None).is_err());
+    assert!(RemoteServer::bind_tcp("localhost:0", None).is_ok());
+    assert!(RemoteServer::bind_tcp_public("0.0.0.0:0", None).is_ok());
+}
+
+#[test]
+fn test_tcp_deeply_nested() {
+    let mut console = console();
+    let mut server = RemoteServer::bind_tcp("127.0.0.1:0", None).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+
+        writer
+            .write_all(format!("echo {}\n", "[".repeat(60_000)).as_bytes())
+            .unwrap();
+        let nested = read_response(&mut reader);
+
+        // The server survives and the connection stays usable.
+        writer.write_all(b"echo \"still here\"\n").unwrap();
+        (nested, read_response(&mut reader))
+    });
+
+    let (nested, after) = serve(&mut server, &mut console, client);
+    assert_eq!(
+        nested[0],
+        format!(
+            "err error: arguments are nested more than {} levels deep",
+            MAX_NESTING
+        )
+    );
+    assert_eq!(nested.last().unwrap(), "done error");
+    assert_eq!(after, vec!["out still here", "done ok"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix() {
+    use std::os::unix::net::UnixStream;
+
+    let path = std::env::temp_dir().join(format!("dg_console_remote_{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut console = console();
+    let mut server = RemoteServer::bind_unix(&path, None).unwrap();
+
+    let client_path = path.clone();
+    let client = thread::spawn(move || {
+        let mut stream = UnixStream::connect(client_path).unwrap();
+        // Lines sent right before closing the write half are still answered.
+        stream.write_all(b"echo scripted\nnope\n").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    });
+
+    let response = serve(&mut server, &mut console, client);
+    assert_eq!(
+        response,
+        "out scripted\ndone ok\nerr error: unknown command `nope`\ndone error\n"
+    );
+
+    drop(server);
+    assert!(!path.exists());
+}
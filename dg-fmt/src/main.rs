@@ -0,0 +1,111 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use dg_script::{
+    format::format_module,
+    module::{FileSource, Loader},
+};
+
+/// Formats dg-script files in place.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Files to format, and directories to format the `.dg` files in.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Only report the files that aren't formatted, failing if there are any.
+    #[arg(long, default_value_t = false)]
+    check: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if let Err(e) = collect(path, &mut files) {
+            eprintln!("error: cannot read `{}`: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut failed = false;
+    for file in &files {
+        match format_file(file, args.check) {
+            Ok(true) => {}
+            Ok(false) => failed = true,
+            Err(e) => {
+                eprintln!("error: cannot format `{}`: {}", file.display(), e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Adds `path`, or the `.dg` files under it if it is a directory.
+fn collect(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|e| e == "dg") {
+            collect(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats `file`, or checks it is formatted, returning whether it was.
+fn format_file(file: &Path, check: bool) -> io::Result<bool> {
+    let (root, name) = match (file.parent(), file.file_name()) {
+        (Some(root), Some(name)) => (root, Path::new(name)),
+        _ => (Path::new(""), file),
+    };
+    // The modules imported are loaded too, to know what their exponents take.
+    let mut loader = Loader::new(FileSource::new(root));
+    let _ = loader.load(name);
+    let Some(module) = loader.module(name) else {
+        return fs::read_to_string(file).map(|_| false);
+    };
+
+    let formatted = match format_module(module) {
+        Ok(formatted) => formatted,
+        Err(diagnostics) => {
+            let path = file.display().to_string();
+            for diagnostic in diagnostics {
+                eprint!("{}", diagnostic.render(&path, &module.source));
+            }
+            return Ok(false);
+        }
+    };
+
+    if *formatted == *module.source {
+        return Ok(true);
+    }
+    if check {
+        let line = formatted
+            .lines()
+            .zip(module.source.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| formatted.lines().count().min(module.source.lines().count()));
+        eprintln!("{}:{}: not formatted", file.display(), line + 1);
+        return Ok(false);
+    }
+    fs::write(file, formatted)?;
+    Ok(true)
+}
@@ -0,0 +1,76 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn dg_fmt(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dg-fmt"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dg_fmt_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_check() {
+    let dir = scratch("check");
+    let messy = dir.join("messy.dg");
+    fs::write(&messy, "^ F a Int -> Int =>   a;").unwrap();
+    fs::write(dir.join("tidy.dg"), "^ G -> Int => 1;\n").unwrap();
+    let check = Path::new("--check");
+
+    // Checking reports the file without changing it.
+    let output = dg_fmt(&[check, &dir]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{}:1: not formatted\n", messy.display())
+    );
+    assert_eq!(
+        fs::read_to_string(&messy).unwrap(),
+        "^ F a Int -> Int =>   a;"
+    );
+
+    let output = dg_fmt(&[&dir]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(&messy).unwrap(),
+        "^ F a Int -> Int => a;\n"
+    );
+
+    let output = dg_fmt(&[check, &dir]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_errors() {
+    let dir = scratch("errors");
+    let broken = dir.join("broken.dg");
+    fs::write(&broken, "^ F =>").unwrap();
+
+    // Files that don't parse are left alone.
+    let output = dg_fmt(&[&broken]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("error: the body of `F` is never closed"));
+    assert_eq!(fs::read_to_string(&broken).unwrap(), "^ F =>");
+
+    let output = dg_fmt(&[&dir.join("missing.dg")]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: cannot format"));
+
+    // Paths are required.
+    assert_eq!(dg_fmt(&[]).status.code(), Some(2));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -12,7 +12,7 @@ pub fn run_headless(mut runner: impl Runner) {
         app = App {
             exit: false,
             time: time_step,
-            input: InputState {},
+            input: InputState::default(),
         };
 
         
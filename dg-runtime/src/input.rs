@@ -1,12 +1,129 @@
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+macro_rules! named_enum {
+    ($(#[$meta:meta])* pub enum $name:ident { $($variant:ident => $text:literal),* $(,)? }) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),*];
+
+            /// Lowercase name used in config files, e.g. by console key bindings.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $text),*
+                }
+            }
+
+            /// Looks up a value by its name, ignoring case.
+            pub fn from_name(name: &str) -> Option<Self> {
+                Self::ALL
+                    .iter()
+                    .copied()
+                    .find(|v| v.name().eq_ignore_ascii_case(name))
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.name())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::from_name(s).ok_or(())
+            }
+        }
+    };
+}
+
+named_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Key {
+        A => "a", B => "b", C => "c", D => "d", E => "e", F => "f", G => "g", H => "h", I => "i",
+        J => "j", K => "k", L => "l", M => "m", N => "n", O => "o", P => "p", Q => "q", R => "r",
+        S => "s", T => "t", U => "u", V => "v", W => "w", X => "x", Y => "y", Z => "z",
+        Num0 => "0", Num1 => "1", Num2 => "2", Num3 => "3", Num4 => "4",
+        Num5 => "5", Num6 => "6", Num7 => "7", Num8 => "8", Num9 => "9",
+        F1 => "f1", F2 => "f2", F3 => "f3", F4 => "f4", F5 => "f5", F6 => "f6",
+        F7 => "f7", F8 => "f8", F9 => "f9", F10 => "f10", F11 => "f11", F12 => "f12",
+        Up => "up", Down => "down", Left => "left", Right => "right",
+        Space => "space", Enter => "enter", Escape => "escape", Tab => "tab", Backspace => "backspace",
+        Insert => "insert", Delete => "delete", Home => "home", End => "end",
+        PageUp => "pageup", PageDown => "pagedown",
+        LeftShift => "lshift", RightShift => "rshift", LeftControl => "lctrl",
+        RightControl => "rctrl", LeftAlt => "lalt", RightAlt => "ralt",
+        Grave => "grave", Minus => "minus", Equals => "equals",
+    }
+}
+
+named_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum MouseButton {
+        Left => "mouse1",
+        Right => "mouse2",
+        Middle => "mouse3",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum InputEvent {
-    
+    KeyPressed(Key),
+    KeyReleased(Key),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
 }
 
+/// Buttons held this frame, fed by [`InputState::handle`] and advanced by [`InputState::end_frame`].
+#[derive(Debug, Default, Clone)]
 pub struct InputState {
-
+    keys: BTreeSet<Key>,
+    keys_pressed: BTreeSet<Key>,
+    keys_released: BTreeSet<Key>,
+    buttons: BTreeSet<MouseButton>,
+    buttons_pressed: BTreeSet<MouseButton>,
+    buttons_released: BTreeSet<MouseButton>,
 }
 
 impl InputState {
+    pub fn handle(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::KeyPressed(key) => {
+                if self.keys.insert(key) {
+                    self.keys_pressed.insert(key);
+                }
+            }
+            InputEvent::KeyReleased(key) => {
+                if self.keys.remove(&key) {
+                    self.keys_released.insert(key);
+                }
+            }
+            InputEvent::MouseButtonPressed(button) => {
+                if self.buttons.insert(button) {
+                    self.buttons_pressed.insert(button);
+                }
+            }
+            InputEvent::MouseButtonReleased(button) => {
+                if self.buttons.remove(&button) {
+                    self.buttons_released.insert(button);
+                }
+            }
+        }
+    }
+
+    /// Forgets which buttons were just pressed or released.
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+    }
+
     pub fn mouse_delta(&self) -> () {
         todo!()
     }
@@ -15,27 +132,79 @@ impl InputState {
         todo!()
     }
 
-    pub fn mouse_button_pressed(&self, _button: ()) -> bool {
-        todo!()
+    pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button)
     }
 
-    pub fn mouse_button_just_pressed(&self, _button: ()) -> bool {
-        todo!()
+    pub fn mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
     }
 
-    pub fn mouse_button_just_released(&self, _button: ()) -> bool {
-        todo!()
+    pub fn mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&button)
     }
 
-    pub fn key_button_pressed(&self, _key: ()) -> bool {
-        todo!()
+    pub fn key_button_pressed(&self, key: Key) -> bool {
+        self.keys.contains(&key)
     }
 
-    pub fn key_button_just_pressed(&self, _key: ()) -> bool {
-        todo!()
+    pub fn key_button_just_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
     }
 
-    pub fn key_button_just_released(&self, _key: ()) -> bool {
-        todo!()
+    pub fn key_button_just_released(&self, key: Key) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys.iter().copied()
+    }
+
+    pub fn mouse_buttons(&self) -> impl Iterator<Item = MouseButton> + '_ {
+        self.buttons.iter().copied()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InputEvent, InputState, Key, MouseButton};
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Key::Num1.to_string(), "1");
+        assert_eq!(Key::from_name("LShift"), Some(Key::LeftShift));
+        assert_eq!("mouse2".parse(), Ok(MouseButton::Right));
+        assert_eq!(Key::from_name("mouse1"), None);
+        for key in Key::ALL {
+            assert_eq!(Key::from_name(key.name()), Some(*key));
+        }
+    }
+
+    #[test]
+    fn test_input_state() {
+        let mut input = InputState::default();
+        input.handle(&InputEvent::KeyPressed(Key::W));
+        input.handle(&InputEvent::MouseButtonPressed(MouseButton::Left));
+        assert!(input.key_button_pressed(Key::W));
+        assert!(input.key_button_just_pressed(Key::W));
+        assert!(input.mouse_button_just_pressed(MouseButton::Left));
+
+        // Repeated presses of a held key aren't new presses.
+        input.end_frame();
+        input.handle(&InputEvent::KeyPressed(Key::W));
+        assert!(input.key_button_pressed(Key::W));
+        assert!(!input.key_button_just_pressed(Key::W));
+
+        input.handle(&InputEvent::KeyReleased(Key::W));
+        input.handle(&InputEvent::KeyReleased(Key::A));
+        assert!(!input.key_button_pressed(Key::W));
+        assert!(input.key_button_just_released(Key::W));
+        assert!(!input.key_button_just_released(Key::A));
+        assert_eq!(input.keys().count(), 0);
+        assert_eq!(input.mouse_buttons().collect::<Vec<_>>(), vec![MouseButton::Left]);
+
+        input.end_frame();
+        assert!(!input.key_button_just_released(Key::W));
+        assert!(input.mouse_button_pressed(MouseButton::Left));
+    }
+}
@@ -0,0 +1,501 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use dg_script::{
+    diagnostic::Diagnostic,
+    host::Host,
+    module::{Loader, Module, Source},
+    parser::{Item, ProductType, TypeName, Variable},
+    tokenizer::{Token, TokenKind, Tokenizer},
+};
+
+/// Built in types, offered by completion.
+const BUILTINS: &[&str] = &[
+    "Void", "Int", "Float", "Str", "Bool", "Vec2", "Vec3", "Vec4", "List", "Map", "Value",
+];
+
+/// Reads modules from the open documents, falling back to the files under `root`.
+struct Documents<'d> {
+    root: &'d Path,
+    open: &'d HashMap<PathBuf, String>,
+}
+
+impl Source for Documents<'_> {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        let path = self.root.join(path);
+        match self.open.get(&path) {
+            Some(text) => Ok(text.clone()),
+            None => fs::read_to_string(path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Builtin,
+    Module,
+    Alias,
+    Sum,
+    Variant,
+    Product,
+    Exponent,
+    Native,
+}
+
+impl Kind {
+    fn is_type(self) -> bool {
+        matches!(
+            self,
+            Kind::Builtin | Kind::Module | Kind::Alias | Kind::Sum | Kind::Product
+        )
+    }
+
+    fn is_callable(self) -> bool {
+        matches!(
+            self,
+            Kind::Variant | Kind::Product | Kind::Exponent | Kind::Native
+        )
+    }
+}
+
+/// Where a name is declared.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub path: PathBuf,
+    pub source: Rc<str>,
+    pub span: Range<usize>,
+}
+
+/// Something a document can refer to by name.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// The name as written in the document, `button.Label` for an item of an import.
+    pub name: String,
+    pub kind: Kind,
+    pub signature: String,
+    /// The `##` comments above the declaration.
+    pub docs: String,
+    /// `None` for builtins, natives and modules.
+    pub location: Option<Location>,
+    /// Variants of a sum type.
+    pub children: Vec<Symbol>,
+}
+
+/// A completion candidate, `label` is what replaces the word being completed after its last `.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: Kind,
+    pub detail: String,
+}
+
+/// What a document declares and imports, and the problems with it.
+pub struct Analysis {
+    text: String,
+    /// Items declared by the document, variants nested in their sum type.
+    items: Vec<Symbol>,
+    /// Every name the document can refer to, including nested variants.
+    names: HashMap<String, Symbol>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Analysis {
+    /// Analyses the document at `path`, reading the modules it imports from `open` or the disk.
+    pub fn new(path: &Path, open: &HashMap<PathBuf, String>, host: &Host) -> Self {
+        let (root, entry) = match (path.parent(), path.file_name()) {
+            (Some(root), Some(name)) => (root, Path::new(name)),
+            _ => (Path::new(""), path),
+        };
+        let mut loader = Loader::with_host(Documents { root, open }, host.clone());
+        let result = loader.load(entry);
+
+        let Some(module) = loader.module(entry).cloned() else {
+            let diagnostics = result.err().into_iter().flatten();
+            return Self {
+                text: open.get(path).cloned().unwrap_or_default(),
+                items: Vec::new(),
+                names: HashMap::new(),
+                diagnostics: diagnostics.map(|d| d.diagnostic).collect(),
+            };
+        };
+        // Problems with the modules imported are reported when they are opened.
+        let diagnostics = match result {
+            Ok(_) => module.diagnostics.clone(),
+            Err(diagnostics) => diagnostics
+                .into_iter()
+                .filter(|d| d.path == entry)
+                .map(|d| d.diagnostic)
+                .collect(),
+        };
+
+        let items = symbols(&module, "");
+        let mut names = HashMap::new();
+        for name in BUILTINS {
+            names.insert(name.to_string(), builtin(name, Kind::Builtin, name));
+        }
+        for ty in host.types() {
+            names.insert(ty.to_owned(), builtin(ty, Kind::Builtin, ty));
+        }
+        for (name, function) in host.functions() {
+            let mut signature = format!("@{}", name);
+            for parameter in &function.parameters {
+                signature += &format!(" {}", parameter);
+            }
+            signature += &format!(" -> {}", function.ret_ty);
+            names.insert(name.to_owned(), builtin(name, Kind::Native, &signature));
+        }
+        for (alias, path) in &module.imports {
+            let signature = format!("% {}", path.display());
+            names.insert(alias.clone(), builtin(alias, Kind::Module, &signature));
+            if let Some(import) = loader.module(path) {
+                insert(&mut names, symbols(import, &format!("{}.", alias)));
+            }
+        }
+        insert(&mut names, items.clone());
+
+        Self {
+            text: module.source.to_string(),
+            items,
+            names,
+            diagnostics,
+        }
+    }
+
+    /// Items the document declares, for its outline.
+    pub fn items(&self) -> &[Symbol] {
+        &self.items
+    }
+
+    /// The symbol named by the type or call at `offset`.
+    pub fn symbol_at(&self, offset: usize) -> Option<&Symbol> {
+        let token = token_at(&self.text, offset)?;
+        let name = match token.kind {
+            TokenKind::Type => token.s,
+            TokenKind::Call => &token.s[1..],
+            _ => return None,
+        };
+        self.names.get(name)
+    }
+
+    /// Where the type or exponent at `offset` is declared.
+    pub fn definition(&self, offset: usize) -> Option<&Location> {
+        self.symbol_at(offset)?.location.as_ref()
+    }
+
+    /// Markdown describing the type or call at `offset`.
+    pub fn hover(&self, offset: usize) -> Option<String> {
+        let symbol = self.symbol_at(offset)?;
+        let mut hover = format!("```dg\n{}\n```", symbol.signature);
+        if !symbol.docs.is_empty() {
+            hover += "\n\n";
+            hover += &symbol.docs;
+        }
+        Some(hover)
+    }
+
+    /// Completes the type name, or the call after `@`, ending at `offset`.
+    ///
+    /// `Vector2.` offers the members of `Vector2`, like the exponent `Vector2.Add`.
+    pub fn complete(&self, offset: usize) -> Vec<Completion> {
+        let before = self.text.get(..offset).unwrap_or_default();
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '@')))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &before[start..];
+        let (call, word) = match word.strip_prefix('@') {
+            Some(word) => (true, word),
+            None => (false, word),
+        };
+        let prefix = word.rfind('.').map_or("", |i| &word[..=i]);
+
+        // Names with more segments after the prefix complete to the next one, a module or type.
+        let mut completions: Vec<Completion> = Vec::new();
+        for symbol in self.names.values() {
+            let offered = match (call, prefix) {
+                (true, _) => symbol.kind.is_callable(),
+                (false, "") => symbol.kind.is_type(),
+                (false, _) => symbol.kind.is_type() || symbol.kind.is_callable(),
+            };
+            let Some(rest) = symbol.name.strip_prefix(prefix).filter(|_| offered) else {
+                continue;
+            };
+            let label = rest.split('.').next().unwrap_or_default();
+            if label.is_empty() || completions.iter().any(|c| c.label == label) {
+                continue;
+            }
+            let completion = match self.names.get(&format!("{}{}", prefix, label)) {
+                Some(s) => Completion {
+                    label: label.to_owned(),
+                    kind: s.kind,
+                    detail: s.signature.clone(),
+                },
+                None => Completion {
+                    label: label.to_owned(),
+                    kind: Kind::Module,
+                    detail: String::new(),
+                },
+            };
+            completions.push(completion);
+        }
+        completions.sort_by(|a, b| a.label.cmp(&b.label));
+        completions
+    }
+}
+
+fn builtin(name: &str, kind: Kind, signature: &str) -> Symbol {
+    Symbol {
+        name: name.to_owned(),
+        kind,
+        signature: signature.to_owned(),
+        docs: String::new(),
+        location: None,
+        children: Vec::new(),
+    }
+}
+
+/// Adds `symbols` and the variants nested in them.
+fn insert(names: &mut HashMap<String, Symbol>, symbols: Vec<Symbol>) {
+    for symbol in symbols {
+        insert(names, symbol.children.clone());
+        names.insert(symbol.name.clone(), symbol);
+    }
+}
+
+/// The items of `module`, named with `prefix`.
+fn symbols(module: &Module, prefix: &str) -> Vec<Symbol> {
+    let symbol = |name: &TypeName, kind, signature: String| {
+        let span = name.span.start - module.base..name.span.end - module.base;
+        Symbol {
+            name: format!("{}{}", prefix, name.name),
+            kind,
+            signature,
+            docs: docs(&module.source, span.start),
+            location: Some(Location {
+                path: module.path.clone(),
+                source: module.source.clone(),
+                span,
+            }),
+            children: Vec::new(),
+        }
+    };
+
+    let mut symbols = Vec::new();
+    for item in &module.script.items {
+        symbols.push(match item {
+            Item::Import(_) => continue,
+            Item::Alias(a) => symbol(
+                &a.name,
+                Kind::Alias,
+                format!("= {} {}", a.name.name, a.ty.name),
+            ),
+            Item::Product(p) => symbol(&p.name, Kind::Product, format!("* {}", fields(p))),
+            Item::Sum(s) => {
+                let variants: Vec<_> = s.variants.iter().map(fields).collect();
+                let signature = format!("+ {} {}", s.name.name, variants.join(" "));
+                let mut sum = symbol(&s.name, Kind::Sum, signature.trim_end().to_owned());
+                for variant in &s.variants {
+                    let mut variant_symbol = symbol(
+                        &variant.name,
+                        Kind::Variant,
+                        format!("+ {}.{}", s.name.name, fields(variant)),
+                    );
+                    variant_symbol.name =
+                        format!("{}{}.{}", prefix, s.name.name, variant.name.name);
+                    sum.children.push(variant_symbol);
+                }
+                sum
+            }
+            Item::Exponent(e) => {
+                let mut signature = format!("^ {}{}", e.name.name, variables(&e.parameters));
+                if let Some(ret_ty) = &e.ret_ty {
+                    signature += &format!(" -> {}", ret_ty.name);
+                }
+                symbol(&e.name, Kind::Exponent, signature)
+            }
+        });
+    }
+    symbols
+}
+
+fn fields(ty: &ProductType) -> String {
+    format!("{}{}", ty.name.name, variables(&ty.variables))
+}
+
+fn variables(variables: &[Variable]) -> String {
+    variables
+        .iter()
+        .map(|v| format!(" {} {}", v.name.ident, v.ty.name))
+        .collect()
+}
+
+/// The `##` comment lines right above the line `offset` is on.
+fn docs(source: &str, offset: usize) -> String {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let mut lines: Vec<_> = source[..line_start]
+        .lines()
+        .rev()
+        .map_while(|line| line.trim().strip_prefix("##"))
+        .map(str::trim)
+        .collect();
+    lines.reverse();
+    lines.join("\n")
+}
+
+/// The token `offset` is in or right after, so a cursor at the end of a name still finds it.
+fn token_at(text: &str, offset: usize) -> Option<Token<'_>> {
+    let mut tokenizer = Tokenizer::new(text);
+    let mut before = None;
+    loop {
+        let token = tokenizer.next_token();
+        if token.kind == TokenKind::Eoi || token.span.start > offset {
+            return before;
+        }
+        if token.kind == TokenKind::Whitespace {
+            continue;
+        }
+        if token.span.contains(&offset) {
+            return Some(token);
+        }
+        if token.span.end == offset {
+            before = Some(token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
+
+    use dg_script::host::Host;
+
+    use super::{Analysis, Kind};
+
+    const MAIN: &str = "% vec;
++ Shape Circle r Float Dot;
+## Where the player is.
+## Moves every frame.
+* Player pos vec.Vector2 shape Shape;
+^ Speed p Player -> Float => @vec.Vector2.Len p.pos;
+^ Broken -> Int => 1.5;";
+
+    const VEC: &str = "## A 2D vector.
+* Vector2 x Float y Float;
+^ Vector2.Len v Vector2 -> Float => @Float.Sqrt + * v.x v.x * v.y v.y;
+^ Vector2.Zero -> Vector2 => @Vector2 0.0 0.0;";
+
+    fn analysis() -> Analysis {
+        let open = HashMap::from([
+            (PathBuf::from("/game/main.dg"), MAIN.to_owned()),
+            (PathBuf::from("/game/vec.dg"), VEC.to_owned()),
+        ]);
+        Analysis::new(Path::new("/game/main.dg"), &open, &Host::std())
+    }
+
+    fn offset(of: &str) -> usize {
+        MAIN.find(of).unwrap() + 1
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let analysis = analysis();
+        assert_eq!(analysis.diagnostics.len(), 1);
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "expected Int but found Float"
+        );
+        assert_eq!(&MAIN[analysis.diagnostics[0].primary.span.clone()], "1.5");
+
+        let open = HashMap::from([(PathBuf::from("/a.dg"), "* A x Int\n* B;".to_owned())]);
+        let analysis = Analysis::new(Path::new("/a.dg"), &open, &Host::std());
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "expected `;` after the item, found `*`"
+        );
+    }
+
+    #[test]
+    fn test_navigation() {
+        let analysis = analysis();
+
+        let location = analysis.definition(offset("vec.Vector2 shape")).unwrap();
+        assert_eq!(location.path, Path::new("vec.dg"));
+        assert_eq!(&VEC[location.span.clone()], "Vector2");
+        let location = analysis.definition(offset("Shape;")).unwrap();
+        assert_eq!(location.path, Path::new("main.dg"));
+        assert_eq!(&MAIN[location.span.clone()], "Shape");
+        assert!(analysis.definition(offset("Float =>")).is_none());
+
+        assert_eq!(
+            analysis.hover(offset("Player ->")).unwrap(),
+            "```dg\n* Player pos vec.Vector2 shape Shape\n```\n\nWhere the player is.\nMoves every frame."
+        );
+        assert_eq!(
+            analysis.hover(offset("@vec.Vector2.Len")).unwrap(),
+            "```dg\n^ Vector2.Len v Vector2 -> Float\n```"
+        );
+        assert_eq!(
+            analysis.hover(MAIN.find(" p.pos").unwrap()).unwrap(),
+            "```dg\n^ Vector2.Len v Vector2 -> Float\n```"
+        );
+        assert_eq!(
+            analysis.hover(offset("Shape;")).unwrap(),
+            "```dg\n+ Shape Circle r Float Dot\n```"
+        );
+
+        let symbols = analysis.items();
+        let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Shape", "Player", "Speed", "Broken"]);
+        assert_eq!(symbols[0].children[1].name, "Shape.Dot");
+    }
+
+    #[test]
+    fn test_complete() {
+        let analysis = analysis();
+        let labels = |offset| -> Vec<String> {
+            analysis
+                .complete(offset)
+                .into_iter()
+                .map(|c| c.label)
+                .collect()
+        };
+
+        let types = labels(MAIN.find("pos ").unwrap() + 4);
+        assert!(types.contains(&"Player".to_owned()));
+        assert!(types.contains(&"Float".to_owned()));
+        assert!(types.contains(&"vec".to_owned()));
+        assert!(!types.contains(&"Speed".to_owned()));
+
+        // Members of a type qualified by its module.
+        let members = labels(MAIN.find("Len p.pos").unwrap());
+        assert_eq!(members, ["Len", "Zero"]);
+        let members = labels(MAIN.find("Vector2.Len").unwrap());
+        assert_eq!(members, ["Vector2"]);
+
+        let completion = &analysis.complete(MAIN.find("Len p.pos").unwrap())[0];
+        assert_eq!(completion.kind, Kind::Exponent);
+        assert_eq!(completion.detail, "^ Vector2.Len v Vector2 -> Float");
+
+        // Words after text that isn't ASCII.
+        let text = "# café\n* Ünit x é@Flo";
+        let open = HashMap::from([(PathBuf::from("/a.dg"), text.to_owned())]);
+        let analysis = Analysis::new(Path::new("/a.dg"), &open, &Host::std());
+        for offset in [text.find('\n').unwrap(), text.len()] {
+            let labels: Vec<_> = analysis
+                .complete(offset)
+                .into_iter()
+                .map(|c| c.label)
+                .collect();
+            assert!(labels.contains(&"Float".to_owned()), "{:?}", labels);
+        }
+    }
+}
@@ -0,0 +1,453 @@
+mod analysis;
+
+use std::{collections::HashMap, error::Error, ops::Range, path::PathBuf};
+
+use analysis::{Analysis, Kind, Symbol};
+use dg_script::{diagnostic::Severity, host::Host};
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest},
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse,
+    DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse,
+    GotoDefinitionResponse, Hover, HoverContents, HoverProviderCapability, Location, MarkupContent,
+    MarkupKind, OneOf, Position, PublishDiagnosticsParams, ServerCapabilities, SymbolKind,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_owned(), "@".to_owned()]),
+            ..Default::default()
+        }),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    Server {
+        connection: &connection,
+        host: Host::std(),
+        documents: HashMap::new(),
+        analyses: HashMap::new(),
+    }
+    .run()?;
+
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server<'c> {
+    connection: &'c Connection,
+    host: Host,
+    /// Text of the open documents.
+    documents: HashMap<PathBuf, String>,
+    analyses: HashMap<PathBuf, Analysis>,
+}
+
+impl Server<'_> {
+    fn run(&mut self) -> Result<()> {
+        for message in &self.connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.respond(request)?;
+                    self.connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => self.notify(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn notify(&mut self, notification: Notification) -> Result<()> {
+        let notification = match cast::<DidOpenTextDocument>(notification) {
+            Ok(None) => return Ok(()),
+            Ok(Some(params)) => {
+                let document = params.text_document;
+                let Some(path) = path(&document.uri) else {
+                    return Ok(());
+                };
+                self.documents.insert(path, document.text);
+                return self.analyse();
+            }
+            Err(notification) => notification,
+        };
+        let notification = match cast::<DidChangeTextDocument>(notification) {
+            Ok(None) => return Ok(()),
+            Ok(Some(params)) => {
+                let Some(path) = path(&params.text_document.uri) else {
+                    return Ok(());
+                };
+                // Changes are full documents, the last one is current.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(path, change.text);
+                }
+                return self.analyse();
+            }
+            Err(notification) => notification,
+        };
+        if let Ok(Some(params)) = cast::<DidCloseTextDocument>(notification) {
+            let uri = params.text_document.uri;
+            let Some(path) = path(&uri) else {
+                return Ok(());
+            };
+            self.documents.remove(&path);
+            self.publish(uri, Vec::new())?;
+            return self.analyse();
+        }
+        Ok(())
+    }
+
+    /// Analyses every open document again, as any of them may import the one that changed.
+    fn analyse(&mut self) -> Result<()> {
+        self.analyses.clear();
+        for path in self.documents.keys() {
+            let analysis = Analysis::new(path, &self.documents, &self.host);
+            self.analyses.insert(path.clone(), analysis);
+        }
+
+        for (path, analysis) in &self.analyses {
+            let Ok(uri) = Url::from_file_path(path) else {
+                continue;
+            };
+            let text = &self.documents[path];
+            let diagnostics = analysis
+                .diagnostics
+                .iter()
+                .map(|d| {
+                    let mut message = d.message.clone();
+                    if !d.primary.message.is_empty() {
+                        message += &format!("\n{}", d.primary.message);
+                    }
+                    for note in &d.notes {
+                        message += &format!("\nnote: {}", note);
+                    }
+                    let related = d
+                        .secondary
+                        .iter()
+                        .map(|label| DiagnosticRelatedInformation {
+                            location: Location::new(uri.clone(), range(text, &label.span)),
+                            message: label.message.clone(),
+                        })
+                        .collect();
+                    lsp_types::Diagnostic {
+                        range: range(text, &d.primary.span),
+                        severity: Some(match d.severity {
+                            Severity::Error => DiagnosticSeverity::ERROR,
+                            Severity::Warning => DiagnosticSeverity::WARNING,
+                        }),
+                        source: Some("dg-script".to_owned()),
+                        message,
+                        related_information: Some(related),
+                        ..Default::default()
+                    }
+                })
+                .collect();
+            self.publish(uri, diagnostics)?;
+        }
+        Ok(())
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        self.connection
+            .sender
+            .send(Message::Notification(notification))?;
+        Ok(())
+    }
+
+    fn respond(&self, request: Request) -> Result<Response> {
+        let request = match cast_request::<GotoDefinition>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position_params;
+                let result = self
+                    .at(&position.text_document.uri, position.position)
+                    .and_then(|(path, analysis, offset)| {
+                        let location = analysis.definition(offset)?;
+                        let target = path.parent()?.join(&location.path);
+                        Some(GotoDefinitionResponse::Scalar(Location::new(
+                            Url::from_file_path(target).ok()?,
+                            range(&location.source, &location.span),
+                        )))
+                    });
+                return Ok(Response::new_ok(id, result));
+            }
+            Err(Skipped::Other(request)) => request,
+            Err(Skipped::Invalid(response)) => return Ok(response),
+        };
+        let request = match cast_request::<HoverRequest>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position_params;
+                let result = self
+                    .at(&position.text_document.uri, position.position)
+                    .and_then(|(_, analysis, offset)| analysis.hover(offset))
+                    .map(|value| Hover {
+                        contents: HoverContents::Markup(MarkupContent {
+                            kind: MarkupKind::Markdown,
+                            value,
+                        }),
+                        range: None,
+                    });
+                return Ok(Response::new_ok(id, result));
+            }
+            Err(Skipped::Other(request)) => request,
+            Err(Skipped::Invalid(response)) => return Ok(response),
+        };
+        let request = match cast_request::<Completion>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position;
+                let items = self
+                    .at(&position.text_document.uri, position.position)
+                    .map(|(_, analysis, offset)| analysis.complete(offset))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| CompletionItem {
+                        label: c.label,
+                        kind: Some(completion_kind(c.kind)),
+                        detail: Some(c.detail).filter(|d| !d.is_empty()),
+                        ..Default::default()
+                    })
+                    .collect();
+                return Ok(Response::new_ok(id, CompletionResponse::Array(items)));
+            }
+            Err(Skipped::Other(request)) => request,
+            Err(Skipped::Invalid(response)) => return Ok(response),
+        };
+        let request = match cast_request::<DocumentSymbolRequest>(request) {
+            Ok((id, params)) => {
+                let symbols = path(&params.text_document.uri)
+                    .and_then(|path| self.analyses.get(&path))
+                    .map(|a| a.items().iter().filter_map(document_symbol).collect())
+                    .unwrap_or_default();
+                return Ok(Response::new_ok(
+                    id,
+                    DocumentSymbolResponse::Nested(symbols),
+                ));
+            }
+            Err(Skipped::Other(request)) => request,
+            Err(Skipped::Invalid(response)) => return Ok(response),
+        };
+
+        let message = format!("`{}` is not supported", request.method);
+        Ok(Response::new_err(
+            request.id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            message,
+        ))
+    }
+
+    /// The analysis of an open document and the offset of `position` in it.
+    fn at(&self, uri: &Url, position: Position) -> Option<(PathBuf, &Analysis, usize)> {
+        let path = path(uri)?;
+        let analysis = self.analyses.get(&path)?;
+        let offset = offset(&self.documents[&path], position);
+        Some((path, analysis, offset))
+    }
+}
+
+/// The parameters of `notification` if it's an `N`, `None` if they're malformed, which is logged
+/// and the notification dropped.
+fn cast<N: lsp_types::notification::Notification>(
+    notification: Notification,
+) -> std::result::Result<Option<N::Params>, Notification> {
+    match notification.extract(N::METHOD) {
+        Ok(params) => Ok(Some(params)),
+        Err(ExtractError::MethodMismatch(notification)) => Err(notification),
+        Err(ExtractError::JsonError { method, error }) => {
+            eprintln!("invalid parameters for `{}`: {}", method, error);
+            Ok(None)
+        }
+    }
+}
+
+/// A request [`cast_request`] didn't take the parameters of.
+enum Skipped {
+    /// The request is for another method.
+    Other(Request),
+    /// The request's parameters are malformed, this answers it.
+    Invalid(Response),
+}
+
+fn cast_request<R: lsp_types::request::Request>(
+    request: Request,
+) -> std::result::Result<(RequestId, R::Params), Skipped> {
+    let id = request.id.clone();
+    request.extract(R::METHOD).map_err(|e| match e {
+        ExtractError::MethodMismatch(request) => Skipped::Other(request),
+        ExtractError::JsonError { method, error } => Skipped::Invalid(Response::new_err(
+            id,
+            lsp_server::ErrorCode::InvalidParams as i32,
+            format!("invalid parameters for `{}`: {}", method, error),
+        )),
+    })
+}
+
+/// The file `uri` names, documents that aren't files, like unsaved ones, aren't analysed.
+fn path(uri: &Url) -> Option<PathBuf> {
+    let path = uri.to_file_path().ok();
+    if path.is_none() {
+        eprintln!("ignoring `{}`, it isn't a file", uri);
+    }
+    path
+}
+
+#[allow(deprecated)]
+fn document_symbol(symbol: &Symbol) -> Option<DocumentSymbol> {
+    let location = symbol.location.as_ref()?;
+    let range = range(&location.source, &location.span);
+    Some(DocumentSymbol {
+        name: symbol.name.clone(),
+        detail: Some(symbol.signature.clone()),
+        kind: match symbol.kind {
+            Kind::Builtin | Kind::Product => SymbolKind::STRUCT,
+            Kind::Module => SymbolKind::MODULE,
+            Kind::Alias => SymbolKind::TYPE_PARAMETER,
+            Kind::Sum => SymbolKind::ENUM,
+            Kind::Variant => SymbolKind::ENUM_MEMBER,
+            Kind::Exponent | Kind::Native => SymbolKind::FUNCTION,
+        },
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: Some(symbol.children.iter().filter_map(document_symbol).collect()),
+    })
+}
+
+fn completion_kind(kind: Kind) -> CompletionItemKind {
+    match kind {
+        Kind::Builtin | Kind::Product => CompletionItemKind::STRUCT,
+        Kind::Module => CompletionItemKind::MODULE,
+        Kind::Alias => CompletionItemKind::TYPE_PARAMETER,
+        Kind::Sum => CompletionItemKind::ENUM,
+        Kind::Variant => CompletionItemKind::ENUM_MEMBER,
+        Kind::Exponent | Kind::Native => CompletionItemKind::FUNCTION,
+    }
+}
+
+/// Position of the byte `offset` in `text`, in UTF-16 code units as LSP counts them.
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+fn range(text: &str, span: &Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(position(text, span.start), position(text, span.end))
+}
+
+/// Byte offset of `position` in `text`, clamped to the end of its line.
+fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::{ErrorCode, Notification, Request};
+    use lsp_types::{
+        notification::{DidOpenTextDocument, Notification as _},
+        request::{HoverRequest, Request as _},
+        Position,
+    };
+
+    use super::{cast, cast_request, offset, position, Server, Skipped};
+
+    #[test]
+    fn test_untitled() {
+        let (connection, _client) = lsp_server::Connection::memory();
+        let mut server = Server {
+            connection: &connection,
+            host: dg_script::host::Host::std(),
+            documents: Default::default(),
+            analyses: Default::default(),
+        };
+
+        // Documents that aren't files are ignored rather than stopping the server.
+        let uri = "untitled:Untitled-1";
+        let open = serde_json::json!({
+            "textDocument": { "uri": uri, "languageId": "dg", "version": 1, "text": "^ F;" }
+        });
+        let notification = Notification::new(DidOpenTextDocument::METHOD.to_owned(), open);
+        server.notify(notification).unwrap();
+        assert!(server.documents.is_empty());
+
+        let hover = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 2 }
+        });
+        let request = Request::new(1.into(), HoverRequest::METHOD.to_owned(), hover);
+        let response = server.respond(request).unwrap();
+        assert_eq!(response.result, Some(serde_json::Value::Null));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let params = serde_json::json!({ "textDocument": 1 });
+
+        let notification = Notification::new(DidOpenTextDocument::METHOD.to_owned(), &params);
+        assert!(matches!(
+            cast::<DidOpenTextDocument>(notification.clone()),
+            Ok(None)
+        ));
+        assert!(cast::<lsp_types::notification::Exit>(notification).is_err());
+
+        let request = Request::new(7.into(), HoverRequest::METHOD.to_owned(), &params);
+        let Err(Skipped::Invalid(response)) = cast_request::<HoverRequest>(request) else {
+            panic!("expected an error response");
+        };
+        assert_eq!(response.id, 7.into());
+        assert_eq!(
+            response.error.unwrap().code,
+            ErrorCode::InvalidParams as i32
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let text = "* A x Int;\n# é𝄞 x\n^ B;";
+        assert_eq!(position(text, 0), Position::new(0, 0));
+        assert_eq!(position(text, text.find('x').unwrap()), Position::new(0, 4));
+        let x = text.rfind('x').unwrap();
+        assert_eq!(position(text, x), Position::new(1, 6));
+        assert_eq!(offset(text, Position::new(1, 6)), x);
+        assert_eq!(
+            offset(text, Position::new(1, 99)),
+            text.rfind('\n').unwrap()
+        );
+        assert_eq!(offset(text, Position::new(9, 0)), text.len());
+    }
+}
@@ -0,0 +1,32 @@
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dg_script::{interpreter::Interpreter, value::Value, vm::Vm};
+
+fn benchmark_backends(c: &mut Criterion) {
+    let script = dg_script::parse(include_str!("../src/scripts/example.dg")).unwrap();
+    let program = script.compile().unwrap();
+    let mut interpreter = Interpreter::new(&script);
+    let mut vm = Vm::new(&program);
+
+    let a = Value::product("Vector2", vec![("x", 1.5.into()), ("y", (-2.0).into())]);
+    let b = Value::product("Vector2", vec![("x", 3.0.into()), ("y", 4.25.into())]);
+    let calls = [
+        ("Vector2.Add", vec![a.clone(), b.clone()]),
+        ("Vector2.Dot", vec![a.clone(), b.clone()]),
+        ("Vector2.Len", vec![a.clone()]),
+        ("Vector2.DistSq", vec![a, b]),
+    ];
+
+    let mut group = c.benchmark_group("Backends");
+    for (name, args) in &calls {
+        group.bench_with_input(BenchmarkId::new("Interpreter", name), args, |b, args| {
+            b.iter(|| black_box(interpreter.call(name, black_box(args.clone()))).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("VM", name), args, |b, args| {
+            b.iter(|| black_box(vm.call(name, black_box(args.clone()))).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, benchmark_backends);
+criterion_main!(benches);
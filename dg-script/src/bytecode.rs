@@ -0,0 +1,594 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    checker::Checker,
+    host::Host,
+    interpreter::vector_size,
+    parser::{
+        AssignOp, BinaryOp, ExponentType, Expression, ExpressionKind, Item, Literal, MatchArm,
+        Script, SumType, UnaryOp,
+    },
+    value::Value,
+    Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Pushes a value from the constant pool.
+    Constant(u32),
+    /// Pushes a local slot of the current call.
+    Load(u16),
+    Pop,
+    /// Replaces the top of the stack with its field named by a constant.
+    Field(u32),
+    /// Pushes a field of a local, following a constant list of field names, without copying the
+    /// rest of the local.
+    LoadPath {
+        slot: u16,
+        path: u32,
+    },
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    /// Leaves the left operand and jumps if it decides an `&&` or `||` by itself.
+    ShortCircuit {
+        op: BinaryOp,
+        target: u32,
+    },
+    Jump(u32),
+    /// Pops a value and applies `op` with it to a local, or to a field of it when `path` names a
+    /// constant list of field names, pushing the new value.
+    Assign {
+        slot: u16,
+        path: Option<u32>,
+        op: AssignOp,
+    },
+    /// Calls a function with its arguments on top of the stack.
+    Call(u16),
+    CallNative {
+        native: u16,
+        args: u8,
+    },
+    /// Builds a product or sum variant from its fields on top of the stack.
+    Construct(u16),
+    Vector(u8),
+    List(u32),
+    /// Pops a sum variant and jumps to the arm for it in a match table.
+    Match(u32),
+    Return,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub arity: usize,
+    /// Slots for parameters and the fields bound by match arms.
+    pub locals: usize,
+    pub code: Vec<Instruction>,
+}
+
+/// Fields of a product type or sum variant, in declaration order.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub ty: String,
+    pub variant: Option<String>,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Arm {
+    /// The sum type the arm was compiled for, empty if no sum declares the arm's variants.
+    pub ty: String,
+    pub variant: String,
+    pub target: u32,
+    /// First of the slots the variant's fields are stored in.
+    pub slot: u16,
+    /// Names of the variant's fields, in the order of their slots.
+    pub fields: Vec<String>,
+}
+
+/// What `@name` refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Function(u16),
+    Layout(u16),
+    Vector(u8),
+    Native(u16),
+}
+
+/// A script compiled for the [`Vm`](crate::vm::Vm).
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub constants: Vec<Value>,
+    pub functions: Vec<Function>,
+    pub layouts: Vec<Layout>,
+    pub matches: Vec<Vec<Arm>>,
+    /// Names of the natives called, resolved to implementations by the VM.
+    pub natives: Vec<String>,
+    targets: HashMap<String, Target>,
+}
+
+impl Program {
+    pub fn lookup(&self, name: &str) -> Option<Target> {
+        self.targets.get(name).copied().or_else(|| {
+            let native = self.natives.iter().position(|n| n == name);
+            native.map(|i| Target::Native(i as u16))
+        })
+    }
+
+    fn constant(&mut self, value: Value) -> u32 {
+        // Floats compare by bits so -0.0 isn't folded into 0.0.
+        let same = |c: &Value| match (c, &value) {
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (c, value) => c == value,
+        };
+        let index = match self.constants.iter().position(same) {
+            Some(index) => index,
+            None => {
+                self.constants.push(value);
+                self.constants.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    fn native(&mut self, name: &str) -> u16 {
+        let index = match self.natives.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.natives.push(name.to_owned());
+                self.natives.len() - 1
+            }
+        };
+        index as u16
+    }
+}
+
+impl Script {
+    /// Compiles every exponent to bytecode, calling the [standard library](crate::stdlib).
+    pub fn compile(&self) -> Result<Program, Error> {
+        self.compile_with(&Host::std())
+    }
+
+    /// Compiles every exponent to bytecode, calling the functions of `host`.
+    pub fn compile_with(&self, host: &Host) -> Result<Program, Error> {
+        let mut program = Program::default();
+        let mut exponents = Vec::new();
+        let mut sums = Vec::new();
+        for item in &self.items {
+            match item {
+                Item::Exponent(e) => {
+                    let index = program.functions.len() as u16;
+                    program.functions.push(Function {
+                        name: e.name.name.clone(),
+                        arity: e.parameters.len(),
+                        locals: 0,
+                        code: Vec::new(),
+                    });
+                    program
+                        .targets
+                        .insert(e.name.name.clone(), Target::Function(index));
+                    exponents.push(e);
+                }
+                Item::Product(p) => {
+                    let index = program.layouts.len() as u16;
+                    program.layouts.push(Layout {
+                        ty: p.name.name.clone(),
+                        variant: None,
+                        fields: p.variables.iter().map(|v| v.name.ident.clone()).collect(),
+                    });
+                    program
+                        .targets
+                        .insert(p.name.name.clone(), Target::Layout(index));
+                }
+                Item::Sum(s) => {
+                    for variant in &s.variants {
+                        let index = program.layouts.len() as u16;
+                        program.layouts.push(Layout {
+                            ty: s.name.name.clone(),
+                            variant: Some(variant.name.name.clone()),
+                            fields: variant
+                                .variables
+                                .iter()
+                                .map(|v| v.name.ident.clone())
+                                .collect(),
+                        });
+                        let name = format!("{}.{}", s.name.name, variant.name.name);
+                        program.targets.insert(name, Target::Layout(index));
+                    }
+                    sums.push(s);
+                }
+                Item::Import(_) | Item::Alias(_) => {}
+            }
+        }
+
+        let matches = Checker::new(self, host).matches();
+        for (index, exponent) in exponents.into_iter().enumerate() {
+            let mut compiler = Compiler {
+                program: &mut program,
+                host,
+                sums: &sums,
+                matches: &matches,
+                scope: Vec::new(),
+                locals: 0,
+                code: Vec::new(),
+            };
+            compiler.compile_exponent(exponent)?;
+            let (locals, code) = (compiler.locals, compiler.code);
+            let function = &mut program.functions[index];
+            function.locals = locals;
+            function.code = code;
+        }
+
+        Ok(program)
+    }
+}
+
+struct Compiler<'c, 's> {
+    program: &'c mut Program,
+    host: &'c Host,
+    sums: &'c [&'s SumType],
+    /// The sum type each match expression matches on, as resolved by the [`Checker`].
+    matches: &'c HashMap<Range<usize>, &'s str>,
+    /// Variables in scope and their slots, later bindings shadow earlier ones.
+    scope: Vec<(String, u16)>,
+    locals: usize,
+    code: Vec<Instruction>,
+}
+
+impl Compiler<'_, '_> {
+    fn compile_exponent(&mut self, exponent: &ExponentType) -> Result<(), Error> {
+        for parameter in &exponent.parameters {
+            self.bind(&parameter.name.ident);
+        }
+        let body = exponent
+            .body
+            .as_ref()
+            .ok_or_else(|| Error::MissingBody(exponent.name.name.clone()))?;
+
+        for (i, statement) in body.statements.iter().enumerate() {
+            if i > 0 {
+                self.code.push(Instruction::Pop);
+            }
+            self.expression(statement)?;
+        }
+
+        // Without a return type the body only runs for its effects.
+        if exponent.ret_ty.is_none() && !body.statements.is_empty() {
+            self.code.push(Instruction::Pop);
+        }
+        if exponent.ret_ty.is_none() || body.statements.is_empty() {
+            let void = self.program.constant(Value::Void);
+            self.code.push(Instruction::Constant(void));
+        }
+        self.code.push(Instruction::Return);
+        Ok(())
+    }
+
+    fn bind(&mut self, name: &str) -> u16 {
+        let slot = self.scope.len() as u16;
+        self.scope.push((name.to_owned(), slot));
+        self.locals = self.locals.max(self.scope.len());
+        slot
+    }
+
+    fn slot(&self, name: &str) -> Result<u16, Error> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, slot)| *slot)
+            .ok_or_else(|| Error::UnknownVariable(name.to_owned()))
+    }
+
+    fn here(&self) -> u32 {
+        self.code.len() as u32
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<(), Error> {
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => {
+                let value = match literal {
+                    Literal::Int(i) => Value::Int(*i),
+                    Literal::Float(f) => Value::Float(*f),
+                    Literal::Str(s) => Value::Str(s.clone()),
+                    Literal::Bool(b) => Value::Bool(*b),
+                };
+                let constant = self.program.constant(value);
+                self.code.push(Instruction::Constant(constant));
+            }
+            ExpressionKind::Variable(ident) => {
+                let slot = self.slot(&ident.ident)?;
+                self.code.push(Instruction::Load(slot));
+            }
+            ExpressionKind::Field { on, field } => {
+                let (root, fields) = field_path(expression);
+                if let ExpressionKind::Variable(ident) = &root.kind {
+                    let slot = self.slot(&ident.ident)?;
+                    let path = self.program.constant(Value::List(fields));
+                    self.code.push(Instruction::LoadPath { slot, path });
+                } else {
+                    self.expression(on)?;
+                    let name = self.program.constant(Value::Str(field.ident.clone()));
+                    self.code.push(Instruction::Field(name));
+                }
+            }
+            ExpressionKind::Unary { op, operand } => {
+                self.expression(operand)?;
+                self.code.push(Instruction::Unary(*op));
+            }
+            ExpressionKind::Binary { op, left, right } => {
+                self.expression(left)?;
+                let jump = matches!(op, BinaryOp::And | BinaryOp::Or).then(|| {
+                    self.code
+                        .push(Instruction::ShortCircuit { op: *op, target: 0 });
+                    self.code.len() - 1
+                });
+                self.expression(right)?;
+                self.code.push(Instruction::Binary(*op));
+                if let Some(jump) = jump {
+                    self.code[jump] = Instruction::ShortCircuit {
+                        op: *op,
+                        target: self.here(),
+                    };
+                }
+            }
+            ExpressionKind::Call { ty, arguments } => {
+                for argument in arguments {
+                    self.expression(argument)?;
+                }
+                let target = match self.program.targets.get(&ty.name) {
+                    Some(target) => *target,
+                    None if vector_size(&ty.name).is_some() => {
+                        Target::Vector(arguments.len() as u8)
+                    }
+                    None if self.host.function(&ty.name).is_some() => {
+                        Target::Native(self.program.native(&ty.name))
+                    }
+                    None => return Err(Error::UnknownExponent(ty.name.clone())),
+                };
+                self.code.push(match target {
+                    Target::Function(function) => Instruction::Call(function),
+                    Target::Layout(layout) => Instruction::Construct(layout),
+                    Target::Vector(size) => Instruction::Vector(size),
+                    Target::Native(native) => Instruction::CallNative {
+                        native,
+                        args: arguments.len() as u8,
+                    },
+                });
+            }
+            ExpressionKind::List(items) => {
+                for item in items {
+                    self.expression(item)?;
+                }
+                self.code.push(Instruction::List(items.len() as u32));
+            }
+            ExpressionKind::Match { on, arms } => self.compile_match(on, arms, &expression.span)?,
+            ExpressionKind::Assign { op, target, value } => {
+                let (root, fields) = field_path(target);
+                let ExpressionKind::Variable(ident) = &root.kind else {
+                    return Err(Error::InvalidTarget);
+                };
+
+                let slot = self.slot(&ident.ident)?;
+                let path = (!fields.is_empty()).then(|| self.program.constant(Value::List(fields)));
+                self.expression(value)?;
+                self.code.push(Instruction::Assign {
+                    slot,
+                    path,
+                    op: *op,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_match(
+        &mut self,
+        on: &Expression,
+        arms: &[MatchArm],
+        span: &Range<usize>,
+    ) -> Result<(), Error> {
+        self.expression(on)?;
+        let table = self.program.matches.len() as u32;
+        self.program.matches.push(Vec::new());
+        self.code.push(Instruction::Match(table));
+
+        // Arms only name variants, so take the sum type from the checked type of the value matched,
+        // falling back to the first sum declaring all of them when it couldn't be resolved.
+        let sum = match self.matches.get(span) {
+            Some(name) => self.sums.iter().find(|s| s.name.name == *name),
+            None => self.sums.iter().find(|s| {
+                arms.iter()
+                    .all(|a| s.variants.iter().any(|v| v.name.name == a.variant.name))
+            }),
+        }
+        .copied();
+
+        let mut ends = Vec::new();
+        for arm in arms {
+            let len = self.scope.len();
+            let fields = sum
+                .and_then(|s| s.variants.iter().find(|v| v.name.name == arm.variant.name))
+                .map_or(&[][..], |v| &v.variables[..]);
+            let slot = self.scope.len() as u16;
+            for field in fields {
+                self.bind(&field.name.ident);
+            }
+
+            let target = self.here();
+            self.program.matches[table as usize].push(Arm {
+                ty: sum.map_or_else(String::new, |s| s.name.name.clone()),
+                variant: arm.variant.name.clone(),
+                target,
+                slot,
+                fields: fields.iter().map(|f| f.name.ident.clone()).collect(),
+            });
+            self.expression(&arm.body)?;
+            self.scope.truncate(len);
+            ends.push(self.code.len());
+            self.code.push(Instruction::Jump(0));
+        }
+
+        let end = self.here();
+        for jump in ends {
+            self.code[jump] = Instruction::Jump(end);
+        }
+        Ok(())
+    }
+}
+
+/// Splits `a.b.c` into `a` and the names `["b", "c"]`.
+fn field_path(expression: &Expression) -> (&Expression, Vec<Value>) {
+    let mut fields = Vec::new();
+    let mut root = expression;
+    while let ExpressionKind::Field { on, field } = &root.kind {
+        fields.push(Value::Str(field.ident.clone()));
+        root = on;
+    }
+    fields.reverse();
+    (root, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Instruction, Program};
+    use crate::{
+        parser::{AssignOp, BinaryOp},
+        value::Value,
+        vm::Vm,
+    };
+
+    fn compile(src: &str) -> Program {
+        crate::parse(src).unwrap().compile().unwrap()
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let program = compile("^ Either a Bool b Bool -> Bool => || a b;");
+        assert_eq!(
+            program.functions[0].code,
+            vec![
+                Instruction::Load(0),
+                Instruction::ShortCircuit {
+                    op: BinaryOp::Or,
+                    target: 4,
+                },
+                Instruction::Load(1),
+                Instruction::Binary(BinaryOp::Or),
+                Instruction::Return,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assign() {
+        let program = compile(
+            "* P x Int y Int;
+             ^ Move p P => += p.x 1 = p @P 0 0;",
+        );
+        let function = &program.functions[0];
+        assert_eq!(function.locals, 1);
+        // Without a return type both results are dropped and void returned.
+        assert_eq!(
+            function.code,
+            vec![
+                Instruction::Constant(1),
+                Instruction::Assign {
+                    slot: 0,
+                    path: Some(0),
+                    op: AssignOp::Add,
+                },
+                Instruction::Pop,
+                Instruction::Constant(2),
+                Instruction::Constant(2),
+                Instruction::Construct(0),
+                Instruction::Assign {
+                    slot: 0,
+                    path: None,
+                    op: AssignOp::Set,
+                },
+                Instruction::Pop,
+                Instruction::Constant(3),
+                Instruction::Return,
+            ]
+        );
+        let constants: Vec<_> = program.constants.iter().map(|c| c.to_string()).collect();
+        assert_eq!(constants, vec!["[\"x\"]", "1", "0", "void"]);
+    }
+
+    #[test]
+    fn test_negative_zero() {
+        let program = compile("^ Zeros -> List => [0.0 -0.0 0.0];");
+        assert_eq!(program.constants.len(), 2);
+        let Value::List(zeros) = Vm::new(&program).call("Zeros", vec![]).unwrap() else {
+            panic!("expected a List");
+        };
+        let signs: Vec<_> = zeros
+            .iter()
+            .map(|z| z.as_float().unwrap().is_sign_negative())
+            .collect();
+        assert_eq!(signs, vec![false, true, false]);
+    }
+
+    #[test]
+    fn test_match_shared_variants() {
+        let program = compile(
+            "+ Option Some v Int None;
+             + Maybe Some x Float None;
+             ^ Get m Maybe -> Float => $ m Some x None 0.0;
+             ^ Unwrap o Option -> Int => $ o Some v None 0;",
+        );
+        let mut vm = Vm::new(&program);
+        let some = Value::variant("Maybe", "Some", vec![("x", 1.5.into())]);
+        assert_eq!(vm.call("Get", vec![some]).unwrap(), Value::Float(1.5));
+        let none = Value::variant("Maybe", "None", vec![]);
+        assert_eq!(vm.call("Get", vec![none]).unwrap(), Value::Float(0.0));
+        let some = Value::variant("Option", "Some", vec![("v", 2.into())]);
+        assert_eq!(vm.call("Unwrap", vec![some]).unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_match() {
+        let program = compile(
+            "+ Shape Circle r Float Rect w Float h Float;
+             ^ Area s Shape -> Float => $ s Rect * w h Circle r;",
+        );
+        let function = &program.functions[0];
+        // The parameter and the most fields bound by an arm.
+        assert_eq!(function.locals, 3);
+        assert_eq!(
+            function.code,
+            vec![
+                Instruction::Load(0),
+                Instruction::Match(0),
+                Instruction::Load(1),
+                Instruction::Load(2),
+                Instruction::Binary(BinaryOp::Mul),
+                Instruction::Jump(8),
+                Instruction::Load(1),
+                Instruction::Jump(8),
+                Instruction::Return,
+            ]
+        );
+
+        let arms: Vec<_> = program.matches[0]
+            .iter()
+            .map(|a| {
+                (
+                    a.ty.as_str(),
+                    a.variant.as_str(),
+                    a.target,
+                    a.slot,
+                    a.fields.join(" "),
+                )
+            })
+            .collect();
+        assert_eq!(
+            arms,
+            vec![
+                ("Shape", "Rect", 2, 1, "w h".to_owned()),
+                ("Shape", "Circle", 6, 1, "r".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,755 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    ops::Range,
+};
+
+use thiserror::Error;
+
+use crate::{
+    host::Host,
+    parser::{
+        AliasType, AssignOp, BinaryOp, Body, ExponentType, Expression, ExpressionKind, Item,
+        Literal, MatchArm, ProductType, Script, SumType, TypeName, UnaryOp,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Void,
+    Int,
+    Float,
+    Str,
+    Bool,
+    Vec2,
+    Vec3,
+    Vec4,
+    List,
+    Map,
+    /// Any value, checked when the script runs.
+    Value,
+    /// A product or sum type declared by the script, or an opaque type registered by the host.
+    Named(String),
+}
+
+impl Type {
+    pub fn builtin(name: &str) -> Option<Self> {
+        Some(match name {
+            "Void" => Type::Void,
+            "Int" => Type::Int,
+            "Float" => Type::Float,
+            "Str" => Type::Str,
+            "Bool" => Type::Bool,
+            "Vec2" => Type::Vec2,
+            "Vec3" => Type::Vec3,
+            "Vec4" => Type::Vec4,
+            "List" => Type::List,
+            "Map" => Type::Map,
+            "Value" => Type::Value,
+            _ => return None,
+        })
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+
+    fn vector_size(&self) -> Option<usize> {
+        match self {
+            Type::Vec2 => Some(2),
+            Type::Vec3 => Some(3),
+            Type::Vec4 => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of type `found` can be used where `self` is expected, ints widen to floats.
+    pub fn accepts(&self, found: &Type) -> bool {
+        self == found
+            || *self == Type::Value
+            || *found == Type::Value
+            || (*self == Type::Float && *found == Type::Int)
+    }
+
+    /// Type of both arms of a match, falling back to [`Type::Value`] when they disagree.
+    fn join(self, other: Type) -> Type {
+        if self == other {
+            self
+        } else if self.is_number() && other.is_number() {
+            Type::Float
+        } else {
+            Type::Value
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Type::Void => "Void",
+            Type::Int => "Int",
+            Type::Float => "Float",
+            Type::Str => "Str",
+            Type::Bool => "Bool",
+            Type::Vec2 => "Vec2",
+            Type::Vec3 => "Vec3",
+            Type::Vec4 => "Vec4",
+            Type::List => "List",
+            Type::Map => "Map",
+            Type::Value => "Value",
+            Type::Named(name) => name,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TypeErrorKind {
+    #[error("unknown type `{0}`")]
+    UnknownType(String),
+    #[error("`{0}` is an alias of itself")]
+    AliasCycle(String),
+    #[error("`{0}` is defined more than once")]
+    Duplicate(String),
+    #[error("unknown variable `{0}`")]
+    UnknownVariable(String),
+    #[error("`{ty}` has no field `{field}`")]
+    UnknownField { ty: Type, field: String },
+    #[error("unknown exponent `{0}`")]
+    UnknownExponent(String),
+    #[error("expected {expected} but found {found}")]
+    Mismatch { expected: Type, found: Type },
+    #[error("cannot apply `{op}` to {found}")]
+    InvalidOperand { op: String, found: Type },
+    #[error("cannot apply `{op}` to {left} and {right}")]
+    InvalidOperands { op: String, left: Type, right: Type },
+    #[error("cannot match on {0}, only sum types can be matched")]
+    NotASum(Type),
+    #[error("`{ty}` has no variant `{variant}`")]
+    UnknownVariant { ty: Type, variant: String },
+    #[error("`{0}` is matched more than once")]
+    DuplicateArm(String),
+    #[error("match on `{ty}` does not handle {}", missing.join(", "))]
+    NonExhaustive { ty: Type, missing: Vec<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind}")]
+pub struct TypeError {
+    pub span: Range<usize>,
+    pub kind: TypeErrorKind,
+}
+
+impl Script {
+    /// Type checks every item against the [standard library](crate::stdlib), returning all
+    /// errors found.
+    pub fn check(&self) -> Result<(), Vec<TypeError>> {
+        self.check_with(&Host::std())
+    }
+
+    /// Type checks every item against the functions and types of `host`.
+    pub fn check_with(&self, host: &Host) -> Result<(), Vec<TypeError>> {
+        let errors = Checker::new(self, host).check();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Variables in scope, later bindings shadow earlier ones.
+type Scope = Vec<(String, Type)>;
+
+pub struct Checker<'s> {
+    script: &'s Script,
+    host: &'s Host,
+    /// Declared types with aliases resolved.
+    types: HashMap<&'s str, Type>,
+    products: HashMap<&'s str, &'s ProductType>,
+    sums: HashMap<&'s str, &'s SumType>,
+    /// Keyed by `Sum.Variant`, with the sum type's name.
+    variants: HashMap<String, (&'s str, &'s ProductType)>,
+    exponents: HashMap<&'s str, &'s ExponentType>,
+    /// The sum type each match expression was resolved to, keyed by the match's span.
+    matched: HashMap<Range<usize>, &'s str>,
+    errors: Vec<TypeError>,
+}
+
+impl<'s> Checker<'s> {
+    pub fn new(script: &'s Script, host: &'s Host) -> Self {
+        let mut checker = Self {
+            script,
+            host,
+            types: HashMap::new(),
+            products: HashMap::new(),
+            sums: HashMap::new(),
+            variants: HashMap::new(),
+            exponents: HashMap::new(),
+            matched: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        let mut aliases = HashMap::new();
+        let mut defined = HashSet::new();
+        for item in &script.items {
+            let name = match item {
+                Item::Alias(a) => {
+                    aliases.insert(a.name.name.as_str(), a);
+                    &a.name
+                }
+                Item::Product(p) => {
+                    checker.products.insert(&p.name.name, p);
+                    checker
+                        .types
+                        .insert(&p.name.name, Type::Named(p.name.name.clone()));
+                    &p.name
+                }
+                Item::Sum(s) => {
+                    for variant in &s.variants {
+                        let name = format!("{}.{}", s.name.name, variant.name.name);
+                        checker.variants.insert(name, (&s.name.name, variant));
+                    }
+                    checker.sums.insert(&s.name.name, s);
+                    checker
+                        .types
+                        .insert(&s.name.name, Type::Named(s.name.name.clone()));
+                    &s.name
+                }
+                Item::Exponent(e) => {
+                    if checker.exponents.insert(&e.name.name, e).is_some() {
+                        checker.error(&e.name.span, TypeErrorKind::Duplicate(e.name.name.clone()));
+                    }
+                    continue;
+                }
+                Item::Import(_) => continue,
+            };
+
+            if Type::builtin(&name.name).is_some()
+                || host.has_type(&name.name)
+                || !defined.insert(name.name.as_str())
+            {
+                checker.error(&name.span, TypeErrorKind::Duplicate(name.name.clone()));
+            }
+        }
+
+        for alias in aliases.values() {
+            let ty = checker.resolve_alias(&aliases, alias);
+            checker.types.insert(&alias.name.name, ty);
+        }
+
+        checker
+    }
+
+    /// Follows an alias chain, cycles and unknown targets resolve to [`Type::Value`].
+    ///
+    /// Problems are only reported for the alias they start at so a chain through a broken alias
+    /// doesn't repeat its error.
+    fn resolve_alias(&mut self, aliases: &HashMap<&str, &AliasType>, alias: &AliasType) -> Type {
+        let mut current = alias;
+        let mut visited = vec![alias.name.name.as_str()];
+        loop {
+            let target = current.ty.name.as_str();
+            if let Some(ty) = Type::builtin(target) {
+                return ty;
+            }
+            if self.products.contains_key(target)
+                || self.sums.contains_key(target)
+                || self.host.has_type(target)
+            {
+                return Type::Named(target.to_owned());
+            }
+
+            match aliases.get(target) {
+                None => {
+                    if std::ptr::eq(current, alias) {
+                        let kind = TypeErrorKind::UnknownType(target.to_owned());
+                        self.error(&alias.ty.span, kind);
+                    }
+                    return Type::Value;
+                }
+                Some(_) if target == alias.name.name => {
+                    let kind = TypeErrorKind::AliasCycle(target.to_owned());
+                    self.error(&alias.name.span, kind);
+                    return Type::Value;
+                }
+                Some(_) if visited.contains(&target) => return Type::Value,
+                Some(next) => {
+                    visited.push(target);
+                    current = next;
+                }
+            }
+        }
+    }
+
+    pub fn check(mut self) -> Vec<TypeError> {
+        self.check_items();
+        self.errors.sort_by_key(|e| e.span.start);
+        self.errors
+    }
+
+    /// Checks every item, returning the sum type each match expression matches on by the span of
+    /// the match. Matches on values that aren't sums are left out.
+    pub(crate) fn matches(mut self) -> HashMap<Range<usize>, &'s str> {
+        self.check_items();
+        self.matched
+    }
+
+    fn check_items(&mut self) {
+        for item in &self.script.items {
+            match item {
+                Item::Product(p) => {
+                    for variable in &p.variables {
+                        self.ty(&variable.ty);
+                    }
+                }
+                Item::Sum(s) => {
+                    for variable in s.variants.iter().flat_map(|v| &v.variables) {
+                        self.ty(&variable.ty);
+                    }
+                }
+                Item::Exponent(e) => self.check_exponent(e),
+                Item::Alias(_) | Item::Import(_) => {}
+            }
+        }
+    }
+
+    /// Checks the statements of a body outside of any exponent, returning the type of the last.
+    pub fn infer(mut self, body: &Body) -> Result<Type, Vec<TypeError>> {
+        let mut scope = Scope::new();
+        let mut found = Type::Void;
+        for statement in &body.statements {
+            found = self.expression(&mut scope, statement);
+        }
+
+        if self.errors.is_empty() {
+            Ok(found)
+        } else {
+            self.errors.sort_by_key(|e| e.span.start);
+            Err(self.errors)
+        }
+    }
+
+    fn check_exponent(&mut self, exponent: &ExponentType) {
+        let mut scope: Scope = exponent
+            .parameters
+            .iter()
+            .map(|p| (p.name.ident.clone(), self.ty(&p.ty)))
+            .collect();
+        let ret_ty = exponent.ret_ty.as_ref().map(|t| self.ty(t));
+
+        let Some(body) = &exponent.body else {
+            return;
+        };
+        let mut found = Type::Void;
+        for statement in &body.statements {
+            found = self.expression(&mut scope, statement);
+        }
+
+        if let Some(expected) = ret_ty {
+            if !expected.accepts(&found) {
+                let span = body
+                    .statements
+                    .last()
+                    .map_or(&exponent.name.span, |s| &s.span);
+                self.error(span, TypeErrorKind::Mismatch { expected, found });
+            }
+        }
+    }
+
+    /// Looks up a type by name, reporting it if it doesn't exist.
+    pub fn ty(&mut self, name: &TypeName) -> Type {
+        match self.lookup(&name.name) {
+            Some(ty) => ty,
+            None => {
+                self.error(&name.span, TypeErrorKind::UnknownType(name.name.clone()));
+                Type::Value
+            }
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Type> {
+        Type::builtin(name)
+            .or_else(|| self.types.get(name).cloned())
+            .or_else(|| {
+                let opaque = self.host.has_type(name);
+                opaque.then(|| Type::Named(name.to_owned()))
+            })
+    }
+
+    /// Field types of a product or sum variant, unknown types are already reported.
+    fn fields(&self, ty: &ProductType) -> Vec<(String, Type)> {
+        ty.variables
+            .iter()
+            .map(|v| {
+                let field = self.lookup(&v.ty.name).unwrap_or(Type::Value);
+                (v.name.ident.clone(), field)
+            })
+            .collect()
+    }
+
+    /// Parameter and return types of anything `@name` can call.
+    pub fn signature(&self, name: &str) -> Option<(Vec<Type>, Type)> {
+        let lookup = |t: &TypeName| self.lookup(&t.name).unwrap_or(Type::Value);
+
+        if let Some(exponent) = self.exponents.get(name) {
+            let parameters = exponent.parameters.iter().map(|p| lookup(&p.ty)).collect();
+            let ret_ty = exponent.ret_ty.as_ref().map_or(Type::Void, lookup);
+            return Some((parameters, ret_ty));
+        }
+        if let Some(product) = self.products.get(name) {
+            let fields = self.fields(product).into_iter().map(|(_, t)| t).collect();
+            return Some((fields, Type::Named(name.to_owned())));
+        }
+        if let Some((sum, variant)) = self.variants.get(name) {
+            let fields = self.fields(variant).into_iter().map(|(_, t)| t).collect();
+            return Some((fields, Type::Named((*sum).to_owned())));
+        }
+        if let Some(ty) = Type::builtin(name).filter(|t| t.vector_size().is_some()) {
+            return Some((vec![Type::Float; ty.vector_size().unwrap()], ty));
+        }
+
+        let function = self.host.function(name)?;
+        Some((function.parameters.clone(), function.ret_ty.clone()))
+    }
+
+    fn expression(&mut self, scope: &mut Scope, expression: &Expression) -> Type {
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => match literal {
+                Literal::Int(_) => Type::Int,
+                Literal::Float(_) => Type::Float,
+                Literal::Str(_) => Type::Str,
+                Literal::Bool(_) => Type::Bool,
+            },
+            ExpressionKind::Variable(ident) => {
+                match scope.iter().rev().find(|(n, _)| *n == ident.ident) {
+                    Some((_, ty)) => ty.clone(),
+                    None => {
+                        let kind = TypeErrorKind::UnknownVariable(ident.ident.clone());
+                        self.error(&ident.span, kind);
+                        Type::Value
+                    }
+                }
+            }
+            ExpressionKind::Field { on, field } => {
+                let ty = self.expression(scope, on);
+                let found = match &ty {
+                    Type::Value => Some(Type::Value),
+                    Type::Named(name) => self
+                        .products
+                        .get(name.as_str())
+                        .and_then(|p| self.fields(p).into_iter().find(|(n, _)| *n == field.ident))
+                        .map(|(_, t)| t),
+                    ty => ty.vector_size().and_then(|size| {
+                        ["x", "y", "z", "w"][..size]
+                            .contains(&field.ident.as_str())
+                            .then_some(Type::Float)
+                    }),
+                };
+                found.unwrap_or_else(|| {
+                    let kind = TypeErrorKind::UnknownField {
+                        ty,
+                        field: field.ident.clone(),
+                    };
+                    self.error(&field.span, kind);
+                    Type::Value
+                })
+            }
+            ExpressionKind::Unary { op, operand } => {
+                let found = self.expression(scope, operand);
+                match unary(*op, &found) {
+                    Some(ty) => ty,
+                    None => {
+                        let kind = TypeErrorKind::InvalidOperand {
+                            op: op.to_string(),
+                            found,
+                        };
+                        self.error(&expression.span, kind);
+                        Type::Value
+                    }
+                }
+            }
+            ExpressionKind::Binary { op, left, right } => {
+                let left = self.expression(scope, left);
+                let right = self.expression(scope, right);
+                self.binary(*op, left, right, &expression.span)
+            }
+            ExpressionKind::Call { ty, arguments } => {
+                let Some((parameters, ret_ty)) = self.signature(&ty.name) else {
+                    self.error(&ty.span, TypeErrorKind::UnknownExponent(ty.name.clone()));
+                    return Type::Value;
+                };
+                for (expected, argument) in parameters.into_iter().zip(arguments) {
+                    let found = self.expression(scope, argument);
+                    self.expect(expected, found, &argument.span);
+                }
+                ret_ty
+            }
+            ExpressionKind::List(items) => {
+                for item in items {
+                    self.expression(scope, item);
+                }
+                Type::List
+            }
+            ExpressionKind::Match { on, arms } => {
+                self.check_match(scope, on, arms, &expression.span)
+            }
+            ExpressionKind::Assign { op, target, value } => {
+                let expected = self.expression(scope, target);
+                let found = self.expression(scope, value);
+                let found = match op {
+                    AssignOp::Set => found,
+                    AssignOp::Add => {
+                        self.binary(BinaryOp::Add, expected.clone(), found, &expression.span)
+                    }
+                    AssignOp::Sub => {
+                        self.binary(BinaryOp::Sub, expected.clone(), found, &expression.span)
+                    }
+                    AssignOp::Mul => {
+                        self.binary(BinaryOp::Mul, expected.clone(), found, &expression.span)
+                    }
+                    AssignOp::Div => {
+                        self.binary(BinaryOp::Div, expected.clone(), found, &expression.span)
+                    }
+                };
+                self.expect(expected.clone(), found, &value.span);
+                expected
+            }
+        }
+    }
+
+    fn check_match(
+        &mut self,
+        scope: &mut Scope,
+        on: &Expression,
+        arms: &[MatchArm],
+        span: &Range<usize>,
+    ) -> Type {
+        let ty = self.expression(scope, on);
+        let Some(sum) = self.sum(&ty) else {
+            self.error(&on.span, TypeErrorKind::NotASum(ty));
+            return Type::Value;
+        };
+        self.matched.insert(span.clone(), &sum.name.name);
+
+        let mut result: Option<Type> = None;
+        let mut seen = Vec::new();
+        for arm in arms {
+            let name = &arm.variant.name;
+            if seen.contains(name) {
+                self.error(&arm.variant.span, TypeErrorKind::DuplicateArm(name.clone()));
+            }
+            seen.push(name.clone());
+
+            let Some(variant) = sum.variants.iter().find(|v| v.name.name == *name) else {
+                let kind = TypeErrorKind::UnknownVariant {
+                    ty: ty.clone(),
+                    variant: name.clone(),
+                };
+                self.error(&arm.variant.span, kind);
+                continue;
+            };
+
+            let len = scope.len();
+            scope.extend(self.fields(variant));
+            let arm_ty = self.expression(scope, &arm.body);
+            scope.truncate(len);
+            result = Some(match result {
+                Some(ty) => ty.join(arm_ty),
+                None => arm_ty,
+            });
+        }
+
+        let missing: Vec<_> = sum
+            .variants
+            .iter()
+            .map(|v| v.name.name.clone())
+            .filter(|v| !seen.contains(v))
+            .collect();
+        if !missing.is_empty() {
+            self.error(span, TypeErrorKind::NonExhaustive { ty, missing });
+        }
+
+        result.unwrap_or(Type::Void)
+    }
+
+    fn sum(&self, ty: &Type) -> Option<&'s SumType> {
+        match ty {
+            Type::Named(name) => self.sums.get(name.as_str()).copied(),
+            _ => None,
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: Type, right: Type, span: &Range<usize>) -> Type {
+        match binary(op, &left, &right) {
+            Some(ty) => ty,
+            None => {
+                let kind = TypeErrorKind::InvalidOperands {
+                    op: op.to_string(),
+                    left,
+                    right,
+                };
+                self.error(span, kind);
+                Type::Value
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: Type, found: Type, span: &Range<usize>) {
+        if !expected.accepts(&found) {
+            self.error(span, TypeErrorKind::Mismatch { expected, found });
+        }
+    }
+
+    fn error(&mut self, span: &Range<usize>, kind: TypeErrorKind) {
+        self.errors.push(TypeError {
+            span: span.clone(),
+            kind,
+        });
+    }
+}
+
+fn unary(op: UnaryOp, ty: &Type) -> Option<Type> {
+    match (op, ty) {
+        (_, Type::Value) => Some(Type::Value),
+        (UnaryOp::Neg, ty) if ty.is_number() || ty.vector_size().is_some() => Some(ty.clone()),
+        (UnaryOp::Not, Type::Bool) => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+/// Result of a binary operation, following the interpreter's rules.
+fn binary(op: BinaryOp, left: &Type, right: &Type) -> Option<Type> {
+    match op {
+        BinaryOp::Eq | BinaryOp::Ne => return Some(Type::Bool),
+        BinaryOp::And | BinaryOp::Or => {
+            let bool = |t: &Type| matches!(t, Type::Bool | Type::Value);
+            return (bool(left) && bool(right)).then_some(Type::Bool);
+        }
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordered = |t: &Type| t.is_number() || matches!(t, Type::Str | Type::Value);
+            let comparable = ordered(left)
+                && ordered(right)
+                && (left.is_number() == right.is_number()
+                    || left == &Type::Value
+                    || right == &Type::Value);
+            return comparable.then_some(Type::Bool);
+        }
+        _ => {}
+    }
+
+    match (left, right) {
+        (Type::Value, _) | (_, Type::Value) => Some(Type::Value),
+        (Type::Int, Type::Int) => Some(Type::Int),
+        (l, r) if l.is_number() && r.is_number() => Some(Type::Float),
+        (Type::Str, Type::Str) | (Type::List, Type::List) if op == BinaryOp::Add => {
+            Some(left.clone())
+        }
+        (l, r) if l.vector_size().is_some() && l == r => Some(l.clone()),
+        (l, r)
+            if l.vector_size().is_some()
+                && r.is_number()
+                && matches!(op, BinaryOp::Mul | BinaryOp::Div) =>
+        {
+            Some(l.clone())
+        }
+        (l, r) if l.is_number() && r.vector_size().is_some() && op == BinaryOp::Mul => {
+            Some(r.clone())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Type, TypeErrorKind};
+
+    /// Checks `s`, returning each error with the source it points at.
+    fn check(s: &str) -> Vec<(TypeErrorKind, &str)> {
+        let script = crate::parse(s).unwrap();
+        match script.check() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| (e.kind, &s[e.span])).collect(),
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(check(include_str!("scripts/example.dg")), vec![]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let errors = check(
+            "= A B; = B A; = Name Text;
+             * Point x Float y Float;
+             + Shape Circle r Float Square side Float;
+             ^ Area s Shape -> Float => $ s Circle * r r;
+             ^ Len p Point -> Int => * p.x p.y;
+             ^ Twice p Point -> Point => @Point * 2 p.z true;
+             ^ Name s Shape -> Str => $ s Circle \"circle\" Square \"square\" Square \"again\" Hexagon \"?\";
+             ^ Bad v Vec2 -> Vec2 => += v \"up\" ! v;",
+        );
+
+        let shape = || Type::Named("Shape".into());
+        assert_eq!(
+            errors,
+            vec![
+                (TypeErrorKind::AliasCycle("A".into()), "A"),
+                (TypeErrorKind::AliasCycle("B".into()), "B"),
+                (TypeErrorKind::UnknownType("Text".into()), "Text"),
+                (
+                    TypeErrorKind::NonExhaustive {
+                        ty: shape(),
+                        missing: vec!["Square".into()],
+                    },
+                    "$ s Circle * r r"
+                ),
+                (
+                    TypeErrorKind::Mismatch {
+                        expected: Type::Int,
+                        found: Type::Float,
+                    },
+                    "* p.x p.y"
+                ),
+                (
+                    TypeErrorKind::UnknownField {
+                        ty: Type::Named("Point".into()),
+                        field: "z".into(),
+                    },
+                    "z"
+                ),
+                (
+                    TypeErrorKind::Mismatch {
+                        expected: Type::Float,
+                        found: Type::Bool,
+                    },
+                    "true"
+                ),
+                (TypeErrorKind::DuplicateArm("Square".into()), "Square"),
+                (
+                    TypeErrorKind::UnknownVariant {
+                        ty: shape(),
+                        variant: "Hexagon".into(),
+                    },
+                    "Hexagon"
+                ),
+                (
+                    TypeErrorKind::InvalidOperands {
+                        op: "+".into(),
+                        left: Type::Vec2,
+                        right: Type::Str,
+                    },
+                    "+= v \"up\""
+                ),
+                (
+                    TypeErrorKind::InvalidOperand {
+                        op: "!".into(),
+                        found: Type::Vec2,
+                    },
+                    "! v"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::checker::{TypeError, TypeErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+/// A problem found in a script, pointing at the source it is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub primary: Label,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            primary: Label {
+                span,
+                message: String::new(),
+            },
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self::new(Severity::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self::new(Severity::Warning, message, span)
+    }
+
+    /// Sets the message shown under the primary span.
+    pub fn with_label(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = message.into();
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Range<usize>, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Renders the diagnostic with annotated snippets of `source`, read from `path`.
+    ///
+    /// ```text
+    /// error: expected `;` after the item, found `*`
+    ///  --> example.dg:2:1
+    ///   |
+    /// 1 | * A x Int
+    ///   | - the item starts here
+    /// 2 | * B y Int;
+    ///   | ^ expected `;`
+    /// ```
+    pub fn render(&self, path: &str, source: &str) -> String {
+        let mut labels: Vec<(&Label, char)> = vec![(&self.primary, '^')];
+        labels.extend(self.secondary.iter().map(|l| (l, '-')));
+        labels.sort_by_key(|(l, _)| l.span.start);
+
+        let lines: Vec<&str> = source.split('\n').collect();
+        let (line, column) = line_column(source, self.primary.span.start);
+        let last_line = labels
+            .iter()
+            .map(|(l, _)| line_column(source, l.span.start).0)
+            .max()
+            .unwrap_or(line);
+        let width = (last_line + 1).to_string().len();
+        let gutter = " ".repeat(width);
+
+        let mut out = format!("{}: {}\n", self.severity, self.message);
+        out += &format!("{}--> {}:{}:{}\n", gutter, path, line + 1, column + 1);
+        out += &format!("{} |\n", gutter);
+
+        let mut rendered_line = None;
+        for (label, marker) in labels {
+            let (line, column) = line_column(source, label.span.start);
+            let text = lines
+                .get(line)
+                .copied()
+                .unwrap_or("")
+                .trim_end_matches('\r');
+            if rendered_line != Some(line) {
+                out += &format!("{:>width$} | {}\n", line + 1, text, width = width);
+                rendered_line = Some(line);
+            }
+
+            // Spans running past the end of the line are underlined up to it.
+            let start = column.min(text.chars().count());
+            let end = line_column(source, label.span.end);
+            let end = if end.0 == line {
+                end.1
+            } else {
+                text.chars().count()
+            };
+            let underline = marker.to_string().repeat(end.saturating_sub(start).max(1));
+            let underline = format!("{}{} {}", " ".repeat(start), underline, label.message);
+            out += &format!("{} | {}\n", gutter, underline.trim_end());
+        }
+
+        for note in &self.notes {
+            out += &format!("{} = note: {}\n", gutter, note);
+        }
+        out
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Zero based line and column, in characters, of the byte `offset` into `source`.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count())
+}
+
+impl From<TypeError> for Diagnostic {
+    fn from(error: TypeError) -> Self {
+        let diagnostic = Diagnostic::error(error.kind.to_string(), error.span);
+        match &error.kind {
+            TypeErrorKind::Mismatch { expected, .. } => {
+                diagnostic.with_label(format!("expected {}", expected))
+            }
+            TypeErrorKind::NonExhaustive { missing, .. } => diagnostic.with_note(format!(
+                "add an arm for {}",
+                missing
+                    .iter()
+                    .map(|m| format!("`{}`", m))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            _ => diagnostic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{line_column, Diagnostic};
+
+    #[test]
+    fn test_render() {
+        let source = "* A x Int\n* B y Int;\n";
+        let diagnostic = Diagnostic::error("expected `;` after the item, found `*`", 10..11)
+            .with_label("expected `;`")
+            .with_secondary(0..1, "the item starts here")
+            .with_note("every item ends with `;`");
+
+        assert_eq!(
+            diagnostic.render("example.dg", source),
+            "error: expected `;` after the item, found `*`
+ --> example.dg:2:1
+  |
+1 | * A x Int
+  | - the item starts here
+2 | * B y Int;
+  | ^ expected `;`
+  = note: every item ends with `;`
+"
+        );
+
+        let source = "^ F => + \"é\" 1.0;";
+        let diagnostic = Diagnostic::error("cannot apply `+` to Str and Float", 7..17);
+        assert_eq!(
+            diagnostic.render("f.dg", source),
+            "error: cannot apply `+` to Str and Float
+ --> f.dg:1:8
+  |
+1 | ^ F => + \"é\" 1.0;
+  |        ^^^^^^^^^
+"
+        );
+
+        assert_eq!(line_column("ab\nc", 4), (1, 1));
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    diagnostic::Diagnostic,
+    module::Module,
+    parser::{Expression, ExpressionKind, Item, Script},
+    tokenizer::{Token, TokenKind, Tokenizer},
+};
+
+/// Widest an item's line gets before it is broken over several.
+pub const MAX_WIDTH: usize = 100;
+
+/// Indentation of each level of an item broken over several lines.
+const INDENT: usize = 4;
+
+/// Formats a script that imports nothing it calls, see [`format_module`].
+pub fn format(src: &str) -> Result<String, Vec<Diagnostic>> {
+    let script = crate::parse(src)?;
+    Ok(layout(src, &script, 0))
+}
+
+/// Formats a module loaded by a [`Loader`](crate::module::Loader), which knows what the calls
+/// to the modules it imports are.
+pub fn format_module(module: &Module) -> Result<String, Vec<Diagnostic>> {
+    if module.diagnostics.iter().any(Diagnostic::is_error) {
+        return Err(module.diagnostics.clone());
+    }
+    Ok(layout(&module.source, &module.script, module.base))
+}
+
+/// What a script is made of at the top level.
+enum Element<'a> {
+    Blank,
+    Comment(&'a str),
+    Item {
+        /// Tokens up to and including the `;`, with whether a line break came before them.
+        tokens: Vec<(Token<'a>, bool)>,
+        /// A comment after the `;` on the same line.
+        trailing: Option<&'a str>,
+    },
+}
+
+/// Where an item is broken over several lines.
+#[derive(Default)]
+struct Breaks {
+    /// Tokens starting a new line, with its indentation.
+    lines: HashMap<usize, usize>,
+    /// Where the matches of a broken body start.
+    matches: HashSet<usize>,
+    /// Tokens starting a match arm, with the start of their match.
+    arms: HashMap<usize, usize>,
+}
+
+/// Lays out `src`, whose parsed `script` has spans starting at `base`.
+///
+/// Tokens are separated by single spaces and items by at most one blank line. Items of the same
+/// kind on consecutive lines have their names padded so what follows them lines up. Items wider
+/// than [`MAX_WIDTH`] are broken over several lines, as are bodies with matches, putting each arm
+/// on its own line, and sum types with fields and more than two variants:
+///
+/// ```text
+/// + Message
+///     Increment amount Int
+///     Decrement amount Int
+///     Reset;
+/// ```
+fn layout(src: &str, script: &Script, base: usize) -> String {
+    let items: HashMap<usize, &Item> = script
+        .items
+        .iter()
+        .map(|item| (name_start(item) - base, item))
+        .collect();
+    let elements = elements(src);
+
+    let mut plans = Vec::new();
+    for element in &elements {
+        plans.push(match element {
+            Element::Item { tokens, .. } => {
+                let item = tokens.get(1).and_then(|(t, _)| items.get(&t.span.start));
+                item.map(|item| breaks(item, tokens, base))
+                    .unwrap_or_default()
+            }
+            _ => Breaks::default(),
+        });
+    }
+
+    let mut out = String::new();
+    let mut i = 0;
+    while i < elements.len() {
+        // Items of the same kind on consecutive lines are aligned.
+        let mut group = i;
+        while let (Some(a), Some(b)) = (sigil(&elements[i]), elements.get(group + 1)) {
+            if sigil(b) != Some(a) || a == TokenKind::Percent {
+                break;
+            }
+            group += 1;
+        }
+        let width = (i..=group)
+            .filter_map(|j| aligned_name(&elements[j], &plans[j]))
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+
+        for j in i..=group {
+            match &elements[j] {
+                Element::Blank => out.push('\n'),
+                Element::Comment(comment) => {
+                    out += comment.trim_end();
+                    out.push('\n');
+                }
+                Element::Item { tokens, trailing } => {
+                    let width = aligned_name(&elements[j], &plans[j]).map_or(0, |_| width);
+                    out += &item(tokens, &plans[j], width);
+                    if let Some(comment) = trailing {
+                        out.push(' ');
+                        out += comment.trim_end();
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+        i = group + 1;
+    }
+    out
+}
+
+fn name_start(item: &Item) -> usize {
+    match item {
+        Item::Import(i) => i.script.span.start,
+        Item::Alias(a) => a.name.span.start,
+        Item::Sum(s) => s.name.span.start,
+        Item::Product(p) => p.name.span.start,
+        Item::Exponent(e) => e.name.span.start,
+    }
+}
+
+fn elements(src: &str) -> Vec<Element<'_>> {
+    let mut elements = Vec::new();
+    let mut tokens = Vec::new();
+    let mut newline = true;
+    let mut tokenizer = Tokenizer::new(src);
+    loop {
+        let token = tokenizer.next_token();
+        match token.kind {
+            TokenKind::Eoi => break,
+            TokenKind::Whitespace => {
+                let lines = token.s.matches('\n').count();
+                newline |= lines > 0;
+                let after_item = matches!(
+                    elements.last(),
+                    Some(Element::Item { .. } | Element::Comment(_))
+                );
+                if tokens.is_empty() && lines > 1 && after_item {
+                    elements.push(Element::Blank);
+                }
+            }
+            TokenKind::Comment(_) if tokens.is_empty() => {
+                match elements.last_mut() {
+                    Some(Element::Item { trailing, .. }) if !newline && trailing.is_none() => {
+                        *trailing = Some(token.s)
+                    }
+                    _ => elements.push(Element::Comment(token.s)),
+                }
+                newline = false;
+            }
+            kind => {
+                tokens.push((token, newline));
+                newline = false;
+                if kind == TokenKind::Semicolon {
+                    elements.push(Element::Item {
+                        tokens: std::mem::take(&mut tokens),
+                        trailing: None,
+                    });
+                }
+            }
+        }
+    }
+    if !tokens.is_empty() {
+        elements.push(Element::Item {
+            tokens,
+            trailing: None,
+        });
+    }
+    if let Some(Element::Blank) = elements.last() {
+        elements.pop();
+    }
+    elements
+}
+
+fn sigil(element: &Element) -> Option<TokenKind> {
+    match element {
+        Element::Item { tokens, .. } => tokens.first().map(|(t, _)| t.kind),
+        _ => None,
+    }
+}
+
+/// The item's name if something follows it on its first line, so it can be padded.
+fn aligned_name<'a>(element: &Element<'a>, breaks: &Breaks) -> Option<&'a str> {
+    let Element::Item { tokens, .. } = element else {
+        return None;
+    };
+    match (tokens.get(1), tokens.get(2)) {
+        (Some((name, _)), Some((next, _)))
+            if !matches!(next.kind, TokenKind::Semicolon | TokenKind::Comment(_))
+                && !breaks.lines.contains_key(&next.span.start) =>
+        {
+            Some(name.s)
+        }
+        _ => None,
+    }
+}
+
+/// Decides where `item` is broken, its spans start at `base` and its tokens' at 0.
+fn breaks(item: &Item, tokens: &[(Token, bool)], base: usize) -> Breaks {
+    let flat = flat_width(tokens);
+    let mut breaks = Breaks::default();
+    let mut line = |start: usize| {
+        breaks.lines.insert(start - base, INDENT);
+    };
+    match item {
+        Item::Sum(s) => {
+            let fields = s.variants.iter().any(|v| !v.variables.is_empty());
+            if flat > MAX_WIDTH || (fields && s.variants.len() > 2) {
+                s.variants.iter().for_each(|v| line(v.name.span.start));
+            }
+        }
+        Item::Product(p) if flat > MAX_WIDTH => {
+            p.variables.iter().for_each(|v| line(v.name.span.start));
+        }
+        Item::Exponent(e) => {
+            let Some(body) = &e.body else {
+                return breaks;
+            };
+            let mut matches = Vec::new();
+            body.statements
+                .iter()
+                .for_each(|s| collect_matches(s, &mut matches));
+            if flat > MAX_WIDTH || !matches.is_empty() {
+                body.statements.iter().for_each(|s| line(s.span.start));
+                for (start, arms) in matches {
+                    breaks.matches.insert(start - base);
+                    for arm in arms {
+                        breaks.arms.insert(arm - base, start - base);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    breaks
+}
+
+/// Starts of the matches in `expression` and of their arms.
+fn collect_matches(expression: &Expression, matches: &mut Vec<(usize, Vec<usize>)>) {
+    match &expression.kind {
+        ExpressionKind::Literal(_) | ExpressionKind::Variable(_) => {}
+        ExpressionKind::Field { on, .. } => collect_matches(on, matches),
+        ExpressionKind::Unary { operand, .. } => collect_matches(operand, matches),
+        ExpressionKind::Binary { left, right, .. } => {
+            collect_matches(left, matches);
+            collect_matches(right, matches);
+        }
+        ExpressionKind::Call {
+            arguments: items, ..
+        }
+        | ExpressionKind::List(items) => {
+            items.iter().for_each(|e| collect_matches(e, matches));
+        }
+        ExpressionKind::Match { on, arms } => {
+            let starts = arms.iter().map(|a| a.variant.span.start).collect();
+            matches.push((expression.span.start, starts));
+            collect_matches(on, matches);
+            arms.iter().for_each(|a| collect_matches(&a.body, matches));
+        }
+        ExpressionKind::Assign { target, value, .. } => {
+            collect_matches(target, matches);
+            collect_matches(value, matches);
+        }
+    }
+}
+
+fn spaced(previous: TokenKind, next: TokenKind) -> bool {
+    !matches!(previous, TokenKind::Dot | TokenKind::OpeningBracket)
+        && !matches!(
+            next,
+            TokenKind::Dot | TokenKind::ClosingBracket | TokenKind::Semicolon
+        )
+}
+
+/// Width of the item on one line, without its comments.
+fn flat_width(tokens: &[(Token, bool)]) -> usize {
+    let mut width = 0;
+    let mut previous = None;
+    for (token, _) in tokens {
+        if let TokenKind::Comment(_) = token.kind {
+            continue;
+        }
+        if previous.is_some_and(|p| spaced(p, token.kind)) {
+            width += 1;
+        }
+        width += token.s.chars().count();
+        previous = Some(token.kind);
+    }
+    width
+}
+
+/// Lays out one item, padding its name to `width`.
+fn item(tokens: &[(Token, bool)], breaks: &Breaks, width: usize) -> String {
+    let mut lines: Vec<(usize, String)> = vec![(0, String::new())];
+    let mut match_indents = HashMap::new();
+    let mut previous = None;
+    for (i, (token, newline)) in tokens.iter().enumerate() {
+        let start = token.span.start;
+        let (indent, line) = lines.last().expect("there is always a line");
+        let (indent, empty) = (*indent, line.is_empty());
+
+        if let TokenKind::Comment(_) = token.kind {
+            // Whatever follows a comment is on the next line.
+            let continuation = indent.max(INDENT);
+            if *newline && !empty {
+                lines.push((continuation, String::new()));
+            } else if !empty {
+                lines.last_mut().unwrap().1.push(' ');
+            }
+            lines.last_mut().unwrap().1 += token.s.trim_end();
+            lines.push((continuation, String::new()));
+            previous = None;
+            continue;
+        }
+
+        let break_indent = breaks.lines.get(&start).copied().or_else(|| {
+            let start = breaks.arms.get(&start)?;
+            Some(match_indents[start] + INDENT)
+        });
+        match break_indent {
+            Some(indent) if empty => lines.last_mut().unwrap().0 = indent,
+            Some(indent) => lines.push((indent, String::new())),
+            None if previous.is_some_and(|p| spaced(p, token.kind)) => {
+                let line = &mut lines.last_mut().unwrap().1;
+                if i == 2 {
+                    let name = tokens[1].0.s.len();
+                    line.extend(std::iter::repeat_n(' ', width.saturating_sub(name)));
+                }
+                line.push(' ');
+            }
+            None => {}
+        }
+
+        let (indent, line) = lines.last_mut().unwrap();
+        if breaks.matches.contains(&start) {
+            match_indents.insert(start, *indent);
+        }
+        *line += token.s;
+        previous = Some(token.kind);
+    }
+
+    lines
+        .into_iter()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(indent, line)| format!("{}{}", " ".repeat(indent), line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+
+    fn assert_formats(src: &str, expected: &str) {
+        let formatted = format(src).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(
+            format(&formatted).unwrap(),
+            formatted,
+            "formatting is idempotent"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_formats(
+            "*   Vector2 x  Float\n  y Float ;\n\n\n\n^Vector2.Cross a Vector2 b Vector2->Float=>-*a . x b.y * b.x a.y;",
+            "* Vector2 x Float y Float;\n\n^ Vector2.Cross a Vector2 b Vector2 -> Float => - * a.x b.y * b.x a.y;\n",
+        );
+        assert_formats(
+            "^ L -> List => [ 1 2   [3]];",
+            "^ L -> List => [1 2 [3]];\n",
+        );
+    }
+
+    #[test]
+    fn test_align() {
+        assert_formats(
+            "= String Str;\n= Dictionary Map;\n\n* Empty;\n* Dialog name Str;\n# breaks the group\n* Pair a Int b Int;",
+            "= String     Str;\n= Dictionary Map;\n\n* Empty;\n* Dialog name Str;\n# breaks the group\n* Pair a Int b Int;\n",
+        );
+    }
+
+    #[test]
+    fn test_breaks() {
+        assert_formats(
+            "+ Option Some v Value None;\n+ Message Increment amount Int Decrement amount Int Reset;",
+            "+ Option Some v Value None;\n+ Message\n    Increment amount Int\n    Decrement amount Int\n    Reset;\n",
+        );
+        assert_formats(
+            "+ Message Increment amount Int Decrement amount Int Reset;
+             ^ Update msg Message mdl Int => $ msg Increment += mdl amount Decrement -= mdl amount Reset $ msg Reset 0 Increment 1 Decrement 2;",
+            "+ Message
+    Increment amount Int
+    Decrement amount Int
+    Reset;
+^ Update msg Message mdl Int =>
+    $ msg
+        Increment += mdl amount
+        Decrement -= mdl amount
+        Reset $ msg
+            Reset 0
+            Increment 1
+            Decrement 2;
+",
+        );
+
+        let long = format!("* Long {};", "field Float ".repeat(9).trim_end());
+        let formatted = format(&long).unwrap();
+        assert!(formatted.starts_with("* Long\n    field Float\n"));
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_formats(
+            "# header\n\n## Docs\n* A x Int; # trailing\n\n\n^ F a A -> Int => # why\n  a.x;",
+            "# header\n\n## Docs\n* A x Int; # trailing\n\n^ F a A -> Int => # why\n    a.x;\n",
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let formatted = format(include_str!("scripts/example.dg")).unwrap();
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+}
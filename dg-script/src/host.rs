@@ -0,0 +1,556 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use dg_math::{
+    vector::{Vec2, Vec3},
+    Scalar,
+};
+
+use crate::{
+    checker::Type,
+    interpreter::{check_arity, mismatch},
+    value::Value,
+    Error,
+};
+
+/// Implementation of a native called by scripts.
+pub type Native = Rc<dyn Fn(&[Value]) -> Result<Value, Error>>;
+
+/// A Rust type that converts to and from script values.
+pub trait ScriptType: Sized {
+    fn ty() -> Type;
+    fn from_value(value: &Value) -> Option<Self>;
+    fn into_value(self) -> Value;
+}
+
+impl ScriptType for () {
+    fn ty() -> Type {
+        Type::Void
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        matches!(value, Value::Void).then_some(())
+    }
+
+    fn into_value(self) -> Value {
+        Value::Void
+    }
+}
+
+impl ScriptType for i64 {
+    fn ty() -> Type {
+        Type::Int
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_int()
+    }
+
+    fn into_value(self) -> Value {
+        Value::Int(self)
+    }
+}
+
+impl ScriptType for f64 {
+    fn ty() -> Type {
+        Type::Float
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_float()
+    }
+
+    fn into_value(self) -> Value {
+        Value::Float(self)
+    }
+}
+
+impl ScriptType for bool {
+    fn ty() -> Type {
+        Type::Bool
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl ScriptType for String {
+    fn ty() -> Type {
+        Type::Str
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_str().map(str::to_owned)
+    }
+
+    fn into_value(self) -> Value {
+        Value::Str(self)
+    }
+}
+
+impl ScriptType for Vec<Value> {
+    fn ty() -> Type {
+        Type::List
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(items) => Some(items.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+}
+
+impl ScriptType for BTreeMap<String, Value> {
+    fn ty() -> Type {
+        Type::Map
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(map) => Some(map.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Map(self)
+    }
+}
+
+/// Any value, checked by the function itself.
+impl ScriptType for Value {
+    fn ty() -> Type {
+        Type::Value
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl ScriptType for Vec2 {
+    fn ty() -> Type {
+        Type::Vec2
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match *value {
+            Value::Vec2([x, y]) => Some(Vec2::new(x as Scalar, y as Scalar)),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Vec2([self.x as f64, self.y as f64])
+    }
+}
+
+impl ScriptType for Vec3 {
+    fn ty() -> Type {
+        Type::Vec3
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match *value {
+            Value::Vec3([x, y, z]) => Some(Vec3::new(x as Scalar, y as Scalar, z as Scalar)),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Vec3([self.x as f64, self.y as f64, self.z as f64])
+    }
+}
+
+/// A Rust type scripts can only hold on to and hand back, see [`Host::register_type`].
+pub trait Opaque {
+    /// Name of the type in scripts.
+    const NAME: &'static str;
+}
+
+/// Reference to a host object of type `T` by an id the host hands out.
+pub struct Handle<T> {
+    pub id: u64,
+    ty: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T: Opaque> Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle<{}>({})", T::NAME, self.id)
+    }
+}
+
+impl<T: Opaque> ScriptType for Handle<T> {
+    fn ty() -> Type {
+        Type::Named(T::NAME.to_owned())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Handle { ty, id } if ty == T::NAME => Some(Handle::new(*id)),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Handle {
+            ty: T::NAME.to_owned(),
+            id: self.id,
+        }
+    }
+}
+
+/// What a host function returns, either a value or a value it may fail to produce.
+pub trait Returns {
+    fn ty() -> Type;
+    fn into_result(self) -> Result<Value, Error>;
+}
+
+impl<T: ScriptType> Returns for T {
+    fn ty() -> Type {
+        T::ty()
+    }
+
+    fn into_result(self) -> Result<Value, Error> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: ScriptType> Returns for Result<T, Error> {
+    fn ty() -> Type {
+        T::ty()
+    }
+
+    fn into_result(self) -> Result<Value, Error> {
+        self.map(ScriptType::into_value)
+    }
+}
+
+/// A Rust closure scripts can call, implemented for closures of up to six [`ScriptType`]
+/// parameters returning something that implements [`Returns`].
+pub trait HostFn<Args>: 'static {
+    fn parameters() -> Vec<Type>;
+    fn ret_ty() -> Type;
+    fn call(&self, name: &str, args: &[Value]) -> Result<Value, Error>;
+}
+
+fn argument<T: ScriptType>(name: &str, value: &Value) -> Result<T, Error> {
+    T::from_value(value).ok_or_else(|| mismatch(name, &T::ty().to_string(), value))
+}
+
+macro_rules! host_fn {
+    ($($arg:ident $value:ident),*) => {
+        impl<Func, R, $($arg),*> HostFn<($($arg,)*)> for Func
+        where
+            Func: Fn($($arg),*) -> R + 'static,
+            R: Returns,
+            $($arg: ScriptType,)*
+        {
+            fn parameters() -> Vec<Type> {
+                vec![$($arg::ty()),*]
+            }
+
+            fn ret_ty() -> Type {
+                R::ty()
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn call(&self, name: &str, args: &[Value]) -> Result<Value, Error> {
+                check_arity(name, <[&str]>::len(&[$(stringify!($arg)),*]), args)?;
+                let mut args = args.iter();
+                $(let $value = argument::<$arg>(name, args.next().unwrap())?;)*
+                self($($value),*).into_result()
+            }
+        }
+    };
+}
+
+host_fn!();
+host_fn!(A a);
+host_fn!(A a, B b);
+host_fn!(A a, B b, C c);
+host_fn!(A a, B b, C c, D d);
+host_fn!(A a, B b, C c, D d, E e);
+host_fn!(A a, B b, C c, D d, E e, G g);
+
+/// A function registered with a [`Host`].
+#[derive(Clone)]
+pub struct HostFunction {
+    pub parameters: Vec<Type>,
+    pub ret_ty: Type,
+    pub native: Native,
+}
+
+/// Functions and opaque types the embedding program exposes to scripts, cheap to clone.
+///
+/// ```
+/// use dg_script::{host::Host, value::Value};
+///
+/// let mut host = Host::std();
+/// host.register("Game.Score", |points: i64, combo: i64| points * combo);
+///
+/// let script = dg_script::parse_with("^ Bonus -> Int => @Game.Score 10 3;", &host).unwrap();
+/// assert!(script.check_with(&host).is_ok());
+/// ```
+#[derive(Clone, Default)]
+pub struct Host {
+    functions: Rc<HashMap<String, HostFunction>>,
+    types: Rc<HashSet<String>>,
+    /// Functions removed by [`Host::retain`].
+    denied: Rc<HashSet<String>>,
+}
+
+impl Host {
+    /// A host without any functions, not even the standard library.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A host with the [standard library](crate::stdlib), built once per thread and shared by
+    /// every clone until one of them registers or removes something.
+    pub fn std() -> Self {
+        thread_local! {
+            static STD: Host = {
+                let mut host = Host::new();
+                crate::stdlib::register(&mut host);
+                host
+            };
+        }
+        STD.with(Host::clone)
+    }
+
+    /// Exposes `f` as `@name`, its signature is taken from the closure's types.
+    pub fn register<Args, F: HostFn<Args>>(&mut self, name: &str, f: F) -> &mut Self {
+        let function_name = name.to_owned();
+        Rc::make_mut(&mut self.functions).insert(
+            name.to_owned(),
+            HostFunction {
+                parameters: F::parameters(),
+                ret_ty: F::ret_ty(),
+                native: Rc::new(move |args| f.call(&function_name, args)),
+            },
+        );
+        self
+    }
+
+    /// Exposes `f` as `@name` with an explicit signature, `f` converts its arguments itself.
+    pub fn register_raw<F>(
+        &mut self,
+        name: &str,
+        parameters: Vec<Type>,
+        ret_ty: Type,
+        f: F,
+    ) -> &mut Self
+    where
+        F: Fn(&[Value]) -> Result<Value, Error> + 'static,
+    {
+        let (function_name, arity) = (name.to_owned(), parameters.len());
+        Rc::make_mut(&mut self.functions).insert(
+            name.to_owned(),
+            HostFunction {
+                parameters,
+                ret_ty,
+                native: Rc::new(move |args| {
+                    check_arity(&function_name, arity, args)?;
+                    f(args)
+                }),
+            },
+        );
+        self
+    }
+
+    /// Lets scripts name `T` in signatures and pass around [`Handle`]s to it.
+    pub fn register_type<T: Opaque>(&mut self) -> &mut Self {
+        Rc::make_mut(&mut self.types).insert(T::NAME.to_owned());
+        self
+    }
+
+    /// Removes the functions `allowed` rejects, scripts calling them then fail to load and
+    /// calls to them fail with [`Error::NotAllowed`].
+    pub fn retain(&mut self, mut allowed: impl FnMut(&str) -> bool) -> &mut Self {
+        let denied: Vec<_> = self
+            .functions
+            .keys()
+            .filter(|n| !allowed(n))
+            .cloned()
+            .collect();
+        let functions = Rc::make_mut(&mut self.functions);
+        for name in &denied {
+            functions.remove(name);
+        }
+        Rc::make_mut(&mut self.denied).extend(denied);
+        self
+    }
+
+    /// Whether `name` was removed by [`Host::retain`].
+    pub fn is_denied(&self, name: &str) -> bool {
+        self.denied.contains(name) && !self.functions.contains_key(name)
+    }
+
+    pub fn denied(&self) -> impl Iterator<Item = &str> {
+        self.denied
+            .iter()
+            .map(String::as_str)
+            .filter(|n| self.is_denied(n))
+    }
+
+    pub fn function(&self, name: &str) -> Option<&HostFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &HostFunction)> {
+        self.functions.iter().map(|(n, f)| (n.as_str(), f))
+    }
+
+    pub fn has_type(&self, name: &str) -> bool {
+        self.types.contains(name)
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = &str> {
+        self.types.iter().map(String::as_str)
+    }
+
+    /// Calls the function `name`, `None` if there is no such function.
+    pub fn call(&self, name: &str, args: &[Value]) -> Option<Result<Value, Error>> {
+        match self.functions.get(name) {
+            Some(f) => Some((f.native)(args)),
+            None if self.is_denied(name) => Some(Err(Error::NotAllowed(name.to_owned()))),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Handle, Host, Opaque};
+    use crate::{checker::Type, value::Value, Error};
+
+    struct Texture;
+
+    impl Opaque for Texture {
+        const NAME: &'static str = "Texture";
+    }
+
+    #[test]
+    fn test_register() {
+        let mut host = Host::new();
+        host.register("Add", |a: i64, b: i64| a + b)
+            .register("Half", |a: f64| -> Result<f64, Error> {
+                if a.is_nan() {
+                    Err(Error::Host("NaN".to_owned()))
+                } else {
+                    Ok(a / 2.0)
+                }
+            })
+            .register("Now", || 3.5)
+            .register_raw("Len", vec![Type::Value], Type::Int, |args| {
+                Ok(Value::Int(args[0].to_string().len() as i64))
+            });
+
+        let add = host.function("Add").unwrap();
+        assert_eq!(add.parameters, vec![Type::Int, Type::Int]);
+        assert_eq!(add.ret_ty, Type::Int);
+        assert_eq!(host.function("Half").unwrap().ret_ty, Type::Float);
+
+        let call = |name: &str, args: &[Value]| host.call(name, args).unwrap();
+        assert_eq!(call("Add", &[1.into(), 2.into()]).unwrap(), Value::Int(3));
+        assert_eq!(call("Half", &[3.into()]).unwrap(), Value::Float(1.5));
+        assert_eq!(call("Now", &[]).unwrap(), Value::Float(3.5));
+        assert_eq!(call("Len", &["ab".into()]).unwrap(), Value::Int(4));
+        assert!(matches!(
+            call("Half", &[f64::NAN.into()]),
+            Err(Error::Host(_))
+        ));
+        assert!(matches!(
+            call("Add", &[1.into()]),
+            Err(Error::WrongArity { .. })
+        ));
+        assert!(matches!(
+            call("Add", &[1.into(), 2.5.into()]),
+            Err(Error::TypeMismatch { .. })
+        ));
+        assert!(matches!(call("Len", &[]), Err(Error::WrongArity { .. })));
+        assert!(host.call("Missing", &[]).is_none());
+    }
+
+    #[test]
+    fn test_handles() {
+        let mut host = Host::new();
+        host.register_type::<Texture>()
+            .register("Texture.Load", |path: String| {
+                Handle::<Texture>::new(path.len() as u64)
+            })
+            .register("Texture.Id", |t: Handle<Texture>| t.id as i64);
+
+        let src = "^ Id path Str -> Int => @Texture.Id @Texture.Load path;
+                   ^ Keep t Texture -> Texture => t;";
+        let script = crate::parse_with(src, &host).unwrap();
+        assert_eq!(script.check_with(&host), Ok(()));
+        let texture = Value::Handle {
+            ty: "Texture".to_owned(),
+            id: 7,
+        };
+        assert_eq!(texture.to_string(), "<Texture 7>");
+
+        let mut interpreter = crate::interpreter::Interpreter::with_host(&script, host.clone());
+        assert_eq!(
+            interpreter.call("Id", vec!["a.png".into()]).unwrap(),
+            Value::Int(5)
+        );
+        assert_eq!(
+            interpreter.call("Keep", vec![texture.clone()]).unwrap(),
+            texture
+        );
+        assert!(matches!(
+            interpreter.call("Texture.Id", vec![1.into()]),
+            Err(Error::TypeMismatch { .. })
+        ));
+
+        let errors = crate::load_with("^ Bad t Texture -> Int => t;", &host).unwrap_err();
+        assert_eq!(errors[0].message, "expected Int but found Texture");
+        assert!(crate::load("^ Bad t Texture -> Int => 1;").is_err());
+    }
+}
@@ -0,0 +1,751 @@
+use std::collections::HashMap;
+
+use crate::{
+    host::Host,
+    parser::{
+        AssignOp, BinaryOp, ExponentType, Expression, ExpressionKind, Item, Literal, MatchArm,
+        ProductType, Script, UnaryOp,
+    },
+    value::{Product, Value, Variant},
+    Error,
+};
+
+/// Deepest exponent call chain before evaluation gives up with [`Error::StackOverflow`].
+pub const MAX_CALL_DEPTH: usize = 128;
+
+/// Most expressions being evaluated at once, summed over the whole call chain, before a further
+/// call gives up with [`Error::StackOverflow`]. Each of them takes native stack, so this bounds
+/// chains of calls nested in their own arguments that stay under [`MAX_CALL_DEPTH`]; it's
+/// measured to leave half of a main thread's 8 MiB stack spare in debug builds.
+pub const MAX_EVAL_DEPTH: usize = 512;
+
+impl Script {
+    /// Calls the exponent, type constructor or native `name` with `args`.
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        Interpreter::new(self).call(name, args)
+    }
+}
+
+/// Local variables of one exponent call, later bindings shadow earlier ones.
+#[derive(Default)]
+struct Frame {
+    locals: Vec<(String, Value)>,
+}
+
+impl Frame {
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.locals
+            .iter_mut()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+}
+
+/// Evaluates exponent bodies by walking their expression trees.
+pub struct Interpreter<'s> {
+    exponents: HashMap<&'s str, &'s ExponentType>,
+    products: HashMap<&'s str, &'s ProductType>,
+    /// Keyed by `Sum.Variant`, with the sum type's name.
+    variants: HashMap<String, (&'s str, &'s ProductType)>,
+    host: Host,
+    depth: usize,
+    /// Expressions being evaluated, across every call.
+    nesting: usize,
+}
+
+impl<'s> Interpreter<'s> {
+    /// An interpreter calling the [standard library](crate::stdlib).
+    pub fn new(script: &'s Script) -> Self {
+        Self::with_host(script, Host::std())
+    }
+
+    /// An interpreter calling the functions of `host`.
+    pub fn with_host(script: &'s Script, host: Host) -> Self {
+        let mut exponents = HashMap::new();
+        let mut products = HashMap::new();
+        let mut variants = HashMap::new();
+        for item in &script.items {
+            match item {
+                Item::Exponent(e) => {
+                    exponents.insert(e.name.name.as_str(), e);
+                }
+                Item::Product(p) => {
+                    products.insert(p.name.name.as_str(), p);
+                }
+                Item::Sum(s) => {
+                    for variant in &s.variants {
+                        let name = format!("{}.{}", s.name.name, variant.name.name);
+                        variants.insert(name, (s.name.name.as_str(), variant));
+                    }
+                }
+                Item::Import(_) | Item::Alias(_) => {}
+            }
+        }
+
+        Self {
+            exponents,
+            products,
+            variants,
+            host,
+            depth: 0,
+            nesting: 0,
+        }
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        match self.exponents.get(name).copied() {
+            Some(exponent) => self.call_exponent(exponent, args),
+            None => self.construct(name, args),
+        }
+    }
+
+    /// Builds a product, sum variant or vector, or calls a native.
+    fn construct(&self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        if let Some(product) = self.products.get(name).copied() {
+            check_arity(name, product.variables.len(), &args)?;
+            return Ok(Value::Product(Product {
+                ty: name.to_owned(),
+                fields: zip_fields(product, args),
+            }));
+        }
+
+        if let Some((ty, variant)) = self.variants.get(name) {
+            check_arity(name, variant.variables.len(), &args)?;
+            return Ok(Value::Variant(Variant {
+                ty: (*ty).to_owned(),
+                variant: variant.name.name.clone(),
+                fields: zip_fields(variant, args),
+            }));
+        }
+
+        if let Some(size) = vector_size(name) {
+            check_arity(name, size, &args)?;
+            let components = args
+                .iter()
+                .map(|a| a.as_float().ok_or_else(|| mismatch(name, "Float", a)))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Value::vector(&components).unwrap());
+        }
+
+        self.host
+            .call(name, &args)
+            .unwrap_or_else(|| Err(Error::UnknownExponent(name.to_owned())))
+    }
+
+    /// Calls the exponent `name`, returning the value its `parameter` holds once the body has run
+    /// rather than its result, for exponents that assign to an argument.
+    pub fn call_updating(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        parameter: &str,
+    ) -> Result<Value, Error> {
+        let exponent = self
+            .exponents
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::UnknownExponent(name.to_owned()))?;
+        let (_, frame) = self.run(exponent, args)?;
+        frame
+            .get(parameter)
+            .cloned()
+            .ok_or_else(|| Error::UnknownVariable(parameter.to_owned()))
+    }
+
+    fn call_exponent(
+        &mut self,
+        exponent: &'s ExponentType,
+        args: Vec<Value>,
+    ) -> Result<Value, Error> {
+        let (result, _) = self.run(exponent, args)?;
+        // Without a return type the body only runs for its effects.
+        match exponent.ret_ty {
+            Some(_) => Ok(result),
+            None => Ok(Value::Void),
+        }
+    }
+
+    /// Runs the body of `exponent`, returning its last value and the variables it ended with.
+    fn run(
+        &mut self,
+        exponent: &'s ExponentType,
+        args: Vec<Value>,
+    ) -> Result<(Value, Frame), Error> {
+        let name = &exponent.name.name;
+        check_arity(name, exponent.parameters.len(), &args)?;
+        if self.depth == MAX_CALL_DEPTH || self.nesting >= MAX_EVAL_DEPTH {
+            return Err(Error::StackOverflow {
+                name: name.clone(),
+                depth: self.depth,
+            });
+        }
+        let body = exponent
+            .body
+            .as_ref()
+            .ok_or_else(|| Error::MissingBody(name.clone()))?;
+
+        let mut frame = Frame {
+            locals: exponent
+                .parameters
+                .iter()
+                .map(|p| p.name.ident.clone())
+                .zip(args)
+                .collect(),
+        };
+
+        self.depth += 1;
+        let mut result = Ok(Value::Void);
+        for statement in &body.statements {
+            result = self.evaluate(&mut frame, statement);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.depth -= 1;
+        result.map(|value| (value, frame))
+    }
+
+    fn evaluate(&mut self, frame: &mut Frame, expression: &Expression) -> Result<Value, Error> {
+        self.nesting += 1;
+        let value = self.evaluate_nested(frame, expression);
+        self.nesting -= 1;
+        value
+    }
+
+    fn evaluate_nested(
+        &mut self,
+        frame: &mut Frame,
+        expression: &Expression,
+    ) -> Result<Value, Error> {
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => Ok(match literal {
+                Literal::Int(i) => Value::Int(*i),
+                Literal::Float(f) => Value::Float(*f),
+                Literal::Str(s) => Value::Str(s.clone()),
+                Literal::Bool(b) => Value::Bool(*b),
+            }),
+            ExpressionKind::Variable(ident) => frame
+                .get(&ident.ident)
+                .cloned()
+                .ok_or_else(|| Error::UnknownVariable(ident.ident.clone())),
+            ExpressionKind::Field { on, field } => {
+                let on = self.evaluate(frame, on)?;
+                get_field(&on, &field.ident)
+            }
+            ExpressionKind::Unary { op, operand } => unary(*op, self.evaluate(frame, operand)?),
+            ExpressionKind::Binary { op, left, right } => {
+                self.evaluate_binary(frame, *op, left, right)
+            }
+            ExpressionKind::Call { ty, arguments } => {
+                let args = self.evaluate_all(frame, arguments)?;
+                self.call(&ty.name, args)
+            }
+            ExpressionKind::List(items) => self.evaluate_all(frame, items).map(Value::List),
+            ExpressionKind::Match { on, arms } => self.evaluate_match(frame, on, arms),
+            ExpressionKind::Assign { op, target, value } => {
+                let value = self.evaluate(frame, value)?;
+                assign(frame, target, *op, value)
+            }
+        }
+    }
+
+    fn evaluate_all(
+        &mut self,
+        frame: &mut Frame,
+        expressions: &[Expression],
+    ) -> Result<Vec<Value>, Error> {
+        expressions
+            .iter()
+            .map(|e| self.evaluate(frame, e))
+            .collect()
+    }
+
+    fn evaluate_binary(
+        &mut self,
+        frame: &mut Frame,
+        op: BinaryOp,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<Value, Error> {
+        let left = self.evaluate(frame, left)?;
+        // `&&` and `||` short circuit.
+        match (op, left.as_bool()) {
+            (BinaryOp::And, Some(false)) => return Ok(Value::Bool(false)),
+            (BinaryOp::Or, Some(true)) => return Ok(Value::Bool(true)),
+            _ => {}
+        }
+        let right = self.evaluate(frame, right)?;
+        binary(op, left, right)
+    }
+
+    fn evaluate_match(
+        &mut self,
+        frame: &mut Frame,
+        on: &Expression,
+        arms: &[MatchArm],
+    ) -> Result<Value, Error> {
+        let variant = match self.evaluate(frame, on)? {
+            Value::Variant(variant) => variant,
+            on => return Err(Error::NotASum(on.type_name().to_owned())),
+        };
+        let arm = arms
+            .iter()
+            .find(|a| a.variant.name == variant.variant)
+            .ok_or_else(|| Error::NoMatchingArm {
+                ty: variant.ty.clone(),
+                variant: variant.variant.clone(),
+            })?;
+
+        // Fields of the variant are in scope for the arm only.
+        let len = frame.locals.len();
+        frame.locals.extend(variant.fields);
+        let result = self.evaluate(frame, &arm.body);
+        frame.locals.truncate(len);
+        result
+    }
+}
+
+pub(crate) fn check_arity(name: &str, expected: usize, args: &[Value]) -> Result<(), Error> {
+    if args.len() == expected {
+        Ok(())
+    } else {
+        Err(Error::WrongArity {
+            name: name.to_owned(),
+            expected,
+            found: args.len(),
+        })
+    }
+}
+
+pub(crate) fn mismatch(name: &str, expected: &str, found: &Value) -> Error {
+    Error::TypeMismatch {
+        name: name.to_owned(),
+        expected: expected.to_owned(),
+        found: found.type_name().to_owned(),
+    }
+}
+
+fn zip_fields(ty: &ProductType, args: Vec<Value>) -> Vec<(String, Value)> {
+    ty.variables
+        .iter()
+        .map(|v| v.name.ident.clone())
+        .zip(args)
+        .collect()
+}
+
+pub(crate) fn vector_size(name: &str) -> Option<usize> {
+    match name {
+        "Vec2" => Some(2),
+        "Vec3" => Some(3),
+        "Vec4" => Some(4),
+        _ => None,
+    }
+}
+
+/// Index of a vector component field.
+pub(crate) fn component(field: &str) -> Option<usize> {
+    ["x", "y", "z", "w"].iter().position(|c| *c == field)
+}
+
+pub(crate) fn get_field(on: &Value, field: &str) -> Result<Value, Error> {
+    let unknown = || Error::UnknownField {
+        ty: on.type_name().to_owned(),
+        field: field.to_owned(),
+    };
+    match on.components() {
+        Some(c) => component(field)
+            .and_then(|i| c.get(i))
+            .map(|c| Value::Float(*c))
+            .ok_or_else(unknown),
+        None => on.field(field).cloned().ok_or_else(unknown),
+    }
+}
+
+/// Applies `op` to the variable or field `target`, returning its new value.
+fn assign(
+    frame: &mut Frame,
+    target: &Expression,
+    op: AssignOp,
+    value: Value,
+) -> Result<Value, Error> {
+    let mut fields = Vec::new();
+    let mut root = target;
+    while let ExpressionKind::Field { on, field } = &root.kind {
+        fields.push(field.ident.as_str());
+        root = on;
+    }
+    let ExpressionKind::Variable(ident) = &root.kind else {
+        return Err(Error::InvalidTarget);
+    };
+    fields.reverse();
+
+    let place = frame
+        .get_mut(&ident.ident)
+        .ok_or_else(|| Error::UnknownVariable(ident.ident.clone()))?;
+    assign_path(place, &fields, op, value)
+}
+
+/// Applies `op` to the field reached by following `fields` from `place`.
+pub(crate) fn assign_path(
+    mut place: &mut Value,
+    fields: &[&str],
+    op: AssignOp,
+    value: Value,
+) -> Result<Value, Error> {
+    let mut fields = fields.iter();
+    while let Some(&field) = fields.next() {
+        let unknown = |ty: &str| Error::UnknownField {
+            ty: ty.to_owned(),
+            field: field.to_owned(),
+        };
+
+        // Components are plain floats so they can only be the last field.
+        let ty = place.type_name().to_owned();
+        let vector = match place {
+            Value::Vec2(v) => Some(&mut v[..]),
+            Value::Vec3(v) => Some(&mut v[..]),
+            Value::Vec4(v) => Some(&mut v[..]),
+            _ => None,
+        };
+        if let Some(vector) = vector {
+            let c = component(field)
+                .and_then(|i| vector.get_mut(i))
+                .ok_or_else(|| unknown(&ty))?;
+            if let Some(field) = fields.next() {
+                return Err(Error::UnknownField {
+                    ty: "Float".to_owned(),
+                    field: (*field).to_owned(),
+                });
+            }
+            let new = apply(op, Value::Float(*c), value)?;
+            *c = new
+                .as_float()
+                .ok_or_else(|| mismatch(field, "Float", &new))?;
+            return Ok(new);
+        }
+
+        place = place.field_mut(field).ok_or_else(|| unknown(&ty))?;
+    }
+
+    let new = apply(op, place.clone(), value)?;
+    *place = new.clone();
+    Ok(new)
+}
+
+fn apply(op: AssignOp, old: Value, value: Value) -> Result<Value, Error> {
+    let op = match op {
+        AssignOp::Set => return Ok(value),
+        AssignOp::Add => BinaryOp::Add,
+        AssignOp::Sub => BinaryOp::Sub,
+        AssignOp::Mul => BinaryOp::Mul,
+        AssignOp::Div => BinaryOp::Div,
+    };
+    binary(op, old, value)
+}
+
+pub(crate) fn unary(op: UnaryOp, value: Value) -> Result<Value, Error> {
+    match (op, &value) {
+        (UnaryOp::Neg, Value::Int(i)) => i
+            .checked_neg()
+            .map(Value::Int)
+            .ok_or(Error::IntegerOverflow),
+        (UnaryOp::Neg, Value::Float(f)) => Ok(Value::Float(-f)),
+        (UnaryOp::Neg, Value::Vec2(_) | Value::Vec3(_) | Value::Vec4(_)) => {
+            let c: Vec<_> = value.components().unwrap().iter().map(|c| -c).collect();
+            Ok(Value::vector(&c).unwrap())
+        }
+        (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        _ => Err(Error::InvalidOperand {
+            op: op.to_string(),
+            found: value.type_name().to_owned(),
+        }),
+    }
+}
+
+pub(crate) fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, Error> {
+    let invalid = || Error::InvalidOperands {
+        op: op.to_string(),
+        left: left.type_name().to_owned(),
+        right: right.type_name().to_owned(),
+    };
+
+    match op {
+        BinaryOp::And | BinaryOp::Or => {
+            return match (left.as_bool(), right.as_bool()) {
+                (Some(a), Some(b)) => Ok(Value::Bool(if op == BinaryOp::And {
+                    a && b
+                } else {
+                    a || b
+                })),
+                _ => Err(invalid()),
+            }
+        }
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            return compare(op, &left, &right)
+                .map(Value::Bool)
+                .ok_or_else(invalid)
+        }
+        _ => {}
+    }
+
+    match (&left, &right) {
+        (Value::Int(a), Value::Int(b)) => return integer(op, *a, *b).map(Value::Int),
+        (Value::Str(a), Value::Str(b)) if op == BinaryOp::Add => {
+            return Ok(Value::Str(format!("{}{}", a, b)))
+        }
+        (Value::List(a), Value::List(b)) if op == BinaryOp::Add => {
+            return Ok(Value::List(a.iter().chain(b).cloned().collect()))
+        }
+        _ => {}
+    }
+
+    if let (Some(a), Some(b)) = (left.as_float(), right.as_float()) {
+        return Ok(Value::Float(float(op, a, b)));
+    }
+
+    // Vectors combine componentwise with vectors of the same size, or scale by a number.
+    let c: Vec<f64> = match (left.components(), right.components()) {
+        (Some(a), Some(b)) if a.len() == b.len() => {
+            a.iter().zip(b).map(|(a, b)| float(op, *a, *b)).collect()
+        }
+        (Some(a), None) if matches!(op, BinaryOp::Mul | BinaryOp::Div) => {
+            let b = right.as_float().ok_or_else(invalid)?;
+            a.iter().map(|a| float(op, *a, b)).collect()
+        }
+        (None, Some(b)) if op == BinaryOp::Mul => {
+            let a = left.as_float().ok_or_else(invalid)?;
+            b.iter().map(|b| a * b).collect()
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(Value::vector(&c).unwrap())
+}
+
+fn integer(op: BinaryOp, a: i64, b: i64) -> Result<i64, Error> {
+    let result = match op {
+        BinaryOp::Add => a.checked_add(b),
+        BinaryOp::Sub => a.checked_sub(b),
+        BinaryOp::Mul => a.checked_mul(b),
+        BinaryOp::Div | BinaryOp::Rem if b == 0 => return Err(Error::DivisionByZero),
+        BinaryOp::Div => a.checked_div(b),
+        BinaryOp::Rem => a.checked_rem(b),
+        _ => unreachable!("comparisons and logic are handled separately"),
+    };
+    result.ok_or(Error::IntegerOverflow)
+}
+
+fn float(op: BinaryOp, a: f64, b: f64) -> f64 {
+    match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        BinaryOp::Rem => a % b,
+        _ => unreachable!("comparisons and logic are handled separately"),
+    }
+}
+
+/// Numbers compare by value regardless of kind, ordering is only defined for numbers and strings.
+fn compare(op: BinaryOp, left: &Value, right: &Value) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => a.partial_cmp(b),
+        _ => match (left.as_float(), right.as_float()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => {
+                return match op {
+                    BinaryOp::Eq => Some(left == right),
+                    BinaryOp::Ne => Some(left != right),
+                    _ => None,
+                }
+            }
+        },
+    };
+
+    Some(match op {
+        BinaryOp::Eq => ordering.is_some_and(|o| o.is_eq()),
+        BinaryOp::Ne => !ordering.is_some_and(|o| o.is_eq()),
+        BinaryOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        BinaryOp::Le => ordering.is_some_and(|o| o.is_le()),
+        BinaryOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        BinaryOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+        _ => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parser::{Script, MAX_NESTING},
+        value::Value,
+        Error,
+    };
+
+    fn parse(s: &str) -> Script {
+        crate::parse(s).unwrap()
+    }
+
+    fn vector2(x: f64, y: f64) -> Value {
+        Value::product("Vector2", vec![("x", x.into()), ("y", y.into())])
+    }
+
+    #[test]
+    fn test_example() {
+        let script = parse(include_str!("scripts/example.dg"));
+        let call = |name: &str, args: Vec<Value>| script.call(name, args).unwrap();
+
+        assert_eq!(call("Function", vec![3.into()]), Value::Int(3));
+        assert_eq!(call("DoNothing", vec![]), Value::Void);
+        assert_eq!(call("UseNothing", vec!["anything".into()]), Value::Void);
+        assert_eq!(call("Square", vec![4.into()]), Value::Int(16));
+        assert_eq!(call("Sum", vec![2.into(), 3.into()]), Value::Int(5));
+
+        let (a, b) = (vector2(1.0, 2.0), vector2(3.0, -4.0));
+        assert_eq!(call("Vector2.NegOne", vec![]), vector2(-1.0, -1.0));
+        assert_eq!(
+            call("Vector2.Add", vec![a.clone(), b.clone()]),
+            vector2(4.0, -2.0)
+        );
+        assert_eq!(
+            call("Vector2.Sub", vec![a.clone(), b.clone()]),
+            vector2(-2.0, 6.0)
+        );
+        assert_eq!(
+            call("Vector2.Mul", vec![a.clone(), b.clone()]),
+            vector2(3.0, -8.0)
+        );
+        assert_eq!(
+            call("Vector2.DivF", vec![a.clone(), 2.0.into()]),
+            vector2(0.5, 1.0)
+        );
+        assert_eq!(call("Vector2.Neg", vec![a.clone()]), vector2(-1.0, -2.0));
+        assert_eq!(
+            call("Vector2.Cross", vec![a.clone(), b.clone()]),
+            Value::Float(-10.0)
+        );
+        assert_eq!(
+            call("Vector2.Dot", vec![a.clone(), b.clone()]),
+            Value::Float(-5.0)
+        );
+        // LenSq is written as `* + x x + y y`, the interpreter follows what the script says.
+        assert_eq!(call("Vector2.LenSq", vec![a.clone()]), Value::Float(8.0));
+        assert_eq!(
+            call("Vector2.Len", vec![a.clone()]),
+            Value::Float(8f64.sqrt())
+        );
+        assert_eq!(
+            call("Vector2.DistSq", vec![a.clone(), b.clone()]),
+            Value::Float(40.0)
+        );
+        assert_eq!(
+            call("Vector2.Round", vec![vector2(1.5, -1.4)]),
+            vector2(2.0, -1.0)
+        );
+        assert_eq!(call("Vector2.Abs", vec![b.clone()]), vector2(3.0, 4.0));
+        assert!(matches!(
+            script.call("Vector2.Dist", vec![a.clone(), b.clone()]),
+            Err(Error::StackOverflow { .. })
+        ));
+
+        assert_eq!(
+            call("Option.Some", vec![1.into()]),
+            Value::variant("Option", "Some", vec![("v", 1.into())])
+        );
+        let increment = Value::variant("Message", "Increment", vec![("amount", 2.into())]);
+        assert_eq!(call("Update", vec![increment, 1.into()]), Value::Void);
+        assert_eq!(
+            call("Update", vec![call("Message.Reset", vec![]), 1.into()]),
+            Value::Void
+        );
+        assert_eq!(call("View", vec![5.into()]), Value::Str("5".into()));
+
+        assert!(matches!(
+            script.call("Square", vec![]),
+            Err(Error::WrongArity {
+                expected: 1,
+                found: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            script.call("Missing", vec![]),
+            Err(Error::UnknownExponent(_))
+        ));
+        assert!(matches!(
+            script.call("Update", vec![1.into(), 1.into()]),
+            Err(Error::NotASum(_))
+        ));
+    }
+
+    #[test]
+    fn test_assign() {
+        let script = parse(
+            "* P v Vec2 tags List;
+             + Shape Circle r Float Rect w Float h Float;
+             ^ Area s Shape -> Float => $ s Circle * * r r 3.0 Rect * w h;
+             ^ Move p P -> P => += p.v.x 1.5 *= p.v 2 += p.tags [\"moved\"] p;
+             ^ Both a Bool b Bool -> Bool => && a ! b;
+             ^ Div a Int b Int -> Int => / a b;",
+        );
+
+        let circle = Value::variant("Shape", "Circle", vec![("r", 2.0.into())]);
+        assert_eq!(
+            script.call("Area", vec![circle]).unwrap(),
+            Value::Float(12.0)
+        );
+
+        let p = Value::product(
+            "P",
+            vec![
+                ("v", Value::Vec2([1.0, 1.0])),
+                ("tags", Value::List(vec![])),
+            ],
+        );
+        let moved = script.call("Move", vec![p]).unwrap();
+        assert_eq!(moved.field("v"), Some(&Value::Vec2([5.0, 2.0])));
+        assert_eq!(moved.to_string(), "@P @Vec2 5.0 2.0 [\"moved\"]");
+
+        assert_eq!(
+            script
+                .call("Both", vec![true.into(), false.into()])
+                .unwrap(),
+            Value::Bool(true)
+        );
+        assert!(matches!(
+            script.call("Div", vec![1.into(), 0.into()]),
+            Err(Error::DivisionByZero)
+        ));
+        assert!(matches!(
+            script.call("Area", vec![Value::variant("Shape", "Triangle", vec![])]),
+            Err(Error::NoMatchingArm { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nested_calls() {
+        // Every call nests the next in its own arguments, taking native stack long before the
+        // call depth runs out. The test runs with the stack of a main thread.
+        let thread = std::thread::Builder::new().stack_size(8 << 20);
+        let handle = thread.spawn(|| {
+            for nesting in [30, MAX_NESTING - 1] {
+                let src = format!("^ F n Int -> Int => {}n;", "@F ".repeat(nesting));
+                let result = parse(&src).call("F", vec![1.into()]);
+                assert!(
+                    matches!(result, Err(Error::StackOverflow { .. })),
+                    "{:?}",
+                    result
+                );
+            }
+        });
+        handle.unwrap().join().unwrap();
+    }
+}
@@ -1,2 +1,118 @@
-mod parser;
-mod tokenizer;
\ No newline at end of file
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub mod bytecode;
+pub mod checker;
+pub mod diagnostic;
+pub mod format;
+pub mod host;
+pub mod interpreter;
+pub mod module;
+pub mod parser;
+pub mod program;
+pub mod reload;
+pub mod repl;
+pub mod sandbox;
+pub mod stdlib;
+pub mod tokenizer;
+pub mod value;
+pub mod vm;
+
+use diagnostic::Diagnostic;
+use host::Host;
+use parser::{Parser, Script};
+
+/// Parses `src`, failing with every diagnostic if any of them is an error.
+pub fn parse(src: &str) -> Result<Script, Vec<Diagnostic>> {
+    parse_with(src, &Host::std())
+}
+
+/// Parses `src` calling the functions of `host`.
+pub fn parse_with(src: &str, host: &Host) -> Result<Script, Vec<Diagnostic>> {
+    let mut parser = Parser::with_host(src, host);
+    let script = parser.parse_script();
+    let diagnostics = parser.take_diagnostics();
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        Err(diagnostics)
+    } else {
+        Ok(script)
+    }
+}
+
+/// Parses and type checks `src`.
+pub fn load(src: &str) -> Result<Script, Vec<Diagnostic>> {
+    load_with(src, &Host::std())
+}
+
+/// Parses and type checks `src` against the functions and types of `host`.
+pub fn load_with(src: &str, host: &Host) -> Result<Script, Vec<Diagnostic>> {
+    let script = parse_with(src, host)?;
+    script
+        .check_with(host)
+        .map_err(|errors| errors.into_iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+    Ok(script)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unknown exponent `{0}`")]
+    UnknownExponent(String),
+    #[error("`{0}` has no body")]
+    MissingBody(String),
+    #[error("`{name}` expects {expected} argument(s) but {found} were given")]
+    WrongArity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("`{name}` expects {expected} but found {found}")]
+    TypeMismatch {
+        name: String,
+        expected: String,
+        found: String,
+    },
+    #[error("unknown variable `{0}`")]
+    UnknownVariable(String),
+    #[error("`{ty}` has no field `{field}`")]
+    UnknownField { ty: String, field: String },
+    #[error("cannot apply `{op}` to {found}")]
+    InvalidOperand { op: String, found: String },
+    #[error("cannot apply `{op}` to {left} and {right}")]
+    InvalidOperands {
+        op: String,
+        left: String,
+        right: String,
+    },
+    #[error("cannot match on {0}, only sum types can be matched")]
+    NotASum(String),
+    #[error("no arm matches `{ty}.{variant}`")]
+    NoMatchingArm { ty: String, variant: String },
+    #[error("only variables and their fields can be assigned to")]
+    InvalidTarget,
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("integer overflow")]
+    IntegerOverflow,
+    #[error("`{name}` ran out of stack {depth} calls deep")]
+    StackOverflow { name: String, depth: usize },
+    #[error("ran out of its budget of {0} instructions")]
+    BudgetExceeded(u64),
+    #[error("ran longer than its limit of {0:?}")]
+    Timeout(Duration),
+    #[error("making a {ty} of {len} takes the call past its allocation limit of {limit}")]
+    AllocationLimit {
+        ty: String,
+        len: usize,
+        limit: usize,
+    },
+    #[error("`{0}` isn't allowed to be called here")]
+    NotAllowed(String),
+    #[error("index {index} is out of bounds for a length of {len}")]
+    IndexOutOfBounds { index: i64, len: usize },
+    #[error("no entry for the key {0:?}")]
+    MissingKey(String),
+    /// Raised by a host function.
+    #[error("{0}")]
+    Host(String),
+}
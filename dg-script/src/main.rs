@@ -0,0 +1,36 @@
+use std::io::{self, BufRead, Write};
+
+use dg_script::repl::{Repl, Reply};
+
+/// Reads items and expressions from stdin, `dg-script game/main.dg` loads a file first.
+fn main() -> io::Result<()> {
+    let mut repl = Repl::new();
+    if let Some(path) = std::env::args().nth(1) {
+        println!("{}", output(repl.line(&format!(":load {}", path))));
+    }
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}", repl.prompt());
+        stdout.flush()?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        match repl.line(&line?) {
+            Reply::More => {}
+            Reply::Output(output) if output.is_empty() => {}
+            Reply::Output(output) => println!("{}", output.trim_end()),
+            Reply::Quit => return Ok(()),
+        }
+    }
+}
+
+fn output(reply: Reply) -> String {
+    match reply {
+        Reply::Output(output) => output.trim_end().to_owned(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,748 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    diagnostic::Diagnostic,
+    host::Host,
+    parser::{Expression, ExpressionKind, Ident, Item, Parser, Script, TypeName},
+    tokenizer::{TokenKind, Tokenizer},
+};
+
+/// Where modules are read from, by their path relative to the source's root.
+pub trait Source {
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+/// Reads modules from a directory.
+pub struct FileSource {
+    root: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Source for FileSource {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(self.root.join(path))
+    }
+}
+
+/// Modules kept in memory, for tests and scripts embedded in the executable.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<PathBuf>, src: impl Into<String>) -> &mut Self {
+        self.files.insert(path.into(), src.into());
+        self
+    }
+}
+
+impl Source for MemorySource {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            let message = format!("no module at `{}`", path.display());
+            io::Error::new(io::ErrorKind::NotFound, message)
+        })
+    }
+}
+
+/// A parsed script file, its names are only qualified when it is [linked](Linked).
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub source: Rc<str>,
+    /// Offset of the module's spans, unique to the module within its [`Loader`].
+    pub base: usize,
+    pub script: Script,
+    /// Modules imported, by the name the script refers to them by.
+    pub imports: Vec<(String, PathBuf)>,
+    /// Problems found parsing the module, with spans relative to its source.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Module {
+    fn contains(&self, offset: usize) -> bool {
+        (self.base..=self.base + self.source.len()).contains(&offset)
+    }
+
+    /// Makes the spans of a diagnostic about the module's items relative to its source.
+    pub(crate) fn localize(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+        let local = |span: &Range<usize>| span.start - self.base..span.end - self.base;
+        diagnostic.primary.span = local(&diagnostic.primary.span);
+        diagnostic.secondary.retain(|l| self.contains(l.span.start));
+        for label in &mut diagnostic.secondary {
+            label.span = local(&label.span);
+        }
+        diagnostic
+    }
+}
+
+/// A diagnostic in one of the loaded modules.
+#[derive(Debug, Clone)]
+pub struct ModuleDiagnostic {
+    pub path: PathBuf,
+    pub source: Rc<str>,
+    pub diagnostic: Diagnostic,
+}
+
+impl ModuleDiagnostic {
+    pub fn render(&self) -> String {
+        self.diagnostic
+            .render(&self.path.display().to_string(), &self.source)
+    }
+}
+
+/// Every module reachable from an entry module, linked into one script.
+///
+/// Items of the entry module keep their names, those of the modules it imports are qualified by
+/// their path from the source's root, `ui/button.dg` declares `ui.button.Button`.
+#[derive(Debug, Clone)]
+pub struct Linked {
+    pub script: Script,
+    /// Imported modules before the modules importing them, the entry module last.
+    pub modules: Vec<Rc<Module>>,
+}
+
+impl Linked {
+    pub fn entry(&self) -> &Module {
+        self.modules
+            .last()
+            .expect("the entry module is always loaded")
+    }
+
+    /// Module a span of the linked script is in.
+    pub fn module_at(&self, offset: usize) -> Option<&Module> {
+        self.modules
+            .iter()
+            .map(|m| &**m)
+            .find(|m| m.contains(offset))
+    }
+}
+
+/// Loads scripts and the scripts they import with `%`, caching the parsed modules and the
+/// checked scripts linked from them.
+///
+/// `% ui.button;` in `game/main.dg` imports `game/ui/button.dg`, whose items are then called
+/// and named as `button.Name`.
+pub struct Loader<S> {
+    source: S,
+    host: Host,
+    modules: HashMap<PathBuf, Rc<Module>>,
+    /// Scripts that linked and checked, by their entry module.
+    linked: HashMap<PathBuf, Linked>,
+    next_base: usize,
+}
+
+/// State of one [`Loader::load`].
+#[derive(Default)]
+struct Visit {
+    loaded: HashMap<PathBuf, Rc<Module>>,
+    order: Vec<Rc<Module>>,
+    /// Modules being loaded, each importing the next.
+    stack: Vec<PathBuf>,
+    diagnostics: Vec<ModuleDiagnostic>,
+}
+
+impl<S: Source> Loader<S> {
+    /// A loader for scripts calling the [standard library](crate::stdlib).
+    pub fn new(source: S) -> Self {
+        Self::with_host(source, Host::std())
+    }
+
+    /// A loader for scripts calling the functions of `host`.
+    pub fn with_host(source: S, host: Host) -> Self {
+        Self {
+            source,
+            host,
+            modules: HashMap::new(),
+            linked: HashMap::new(),
+            next_base: 0,
+        }
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// The source, to change modules in it before [invalidating](Loader::invalidate) them.
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// The cached module at `path`, if it has been loaded.
+    pub fn module(&self, path: &Path) -> Option<&Rc<Module>> {
+        self.modules.get(path)
+    }
+
+    /// Forgets the module at `path` and every module importing it, so they are read again.
+    ///
+    /// Linked scripts are forgotten along with their entry module, which imports every module
+    /// they are made of.
+    pub fn invalidate(&mut self, path: &Path) {
+        let mut stale = vec![path.to_owned()];
+        while let Some(path) = stale.pop() {
+            if self.modules.remove(&path).is_none() {
+                continue;
+            }
+            for (importer, module) in &self.modules {
+                if module.imports.iter().any(|(_, p)| *p == path) {
+                    stale.push(importer.clone());
+                }
+            }
+        }
+
+        let modules = &self.modules;
+        self.linked.retain(|entry, _| modules.contains_key(entry));
+    }
+
+    /// Loads, links and type checks the module at `path` with everything it imports.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<Linked, Vec<ModuleDiagnostic>> {
+        let path = path.as_ref();
+        if let Some(linked) = self.linked.get(path) {
+            return Ok(linked.clone());
+        }
+
+        let mut visit = Visit::default();
+        if let Err(e) = self.visit(path, &mut visit) {
+            let message = format!("cannot read `{}`: {}", path.display(), e);
+            visit.diagnostics.push(ModuleDiagnostic {
+                path: path.to_owned(),
+                source: Rc::from(""),
+                diagnostic: Diagnostic::error(message, 0..0),
+            });
+        }
+
+        let mut diagnostics = visit.diagnostics;
+        for module in &visit.order {
+            diagnostics.extend(module.diagnostics.iter().map(|d| ModuleDiagnostic {
+                path: module.path.clone(),
+                source: module.source.clone(),
+                diagnostic: d.clone(),
+            }));
+        }
+        if diagnostics.iter().any(|d| d.diagnostic.is_error()) {
+            return Err(diagnostics);
+        }
+
+        let linked = link(path, visit.order);
+        if let Err(errors) = linked.script.check_with(&self.host) {
+            return Err(errors
+                .into_iter()
+                .map(|e| {
+                    let module = linked
+                        .module_at(e.span.start)
+                        .expect("spans are within a module");
+                    ModuleDiagnostic {
+                        path: module.path.clone(),
+                        source: module.source.clone(),
+                        diagnostic: module.localize(e.into()),
+                    }
+                })
+                .collect());
+        }
+        self.linked.insert(path.to_owned(), linked.clone());
+        Ok(linked)
+    }
+
+    /// Loads the module at `path` after the modules it imports.
+    fn visit(&mut self, path: &Path, visit: &mut Visit) -> io::Result<Rc<Module>> {
+        if let Some(module) = visit.loaded.get(path) {
+            return Ok(module.clone());
+        }
+
+        visit.stack.push(path.to_owned());
+        let module = match self.modules.get(path).cloned() {
+            Some(module) => {
+                for (_, import) in &module.imports {
+                    // Problems with the imports were found when the module was first loaded.
+                    if !visit.stack.contains(import) {
+                        let _ = self.visit(import, visit);
+                    }
+                }
+                module
+            }
+            None => self.parse(path, visit)?,
+        };
+        visit.stack.pop();
+
+        visit.loaded.insert(path.to_owned(), module.clone());
+        visit.order.push(module.clone());
+        Ok(module)
+    }
+
+    fn parse(&mut self, path: &Path, visit: &mut Visit) -> io::Result<Rc<Module>> {
+        let src = self.source.read(path)?;
+        let source: Rc<str> = Rc::from(src.as_str());
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        // Problems with the imports are kept with the module, so they are reported again when it
+        // is loaded from the cache.
+        let mut diagnostics = Vec::new();
+        let mut imports = Vec::new();
+        let mut imported = Vec::new();
+        let mut names: Vec<(String, Range<usize>)> = Vec::new();
+        for import in imports_of(&src) {
+            let import_path = dir
+                .join(import.ident.replace('.', "/"))
+                .with_extension("dg");
+            let name = import.ident.rsplit('.').next().unwrap().to_owned();
+
+            // Imports are referred to by their last segment, so two of them can't share it.
+            if let Some((_, first)) = names.iter().find(|(n, _)| *n == name) {
+                let message = format!("two imports are named `{}`", name);
+                diagnostics.push(
+                    Diagnostic::error(message, import.span)
+                        .with_label("imported again here")
+                        .with_secondary(first.clone(), "first imported here"),
+                );
+                continue;
+            }
+            names.push((name.clone(), import.span.clone()));
+
+            if let Some(i) = visit.stack.iter().position(|p| *p == import_path) {
+                let cycle = visit.stack[i..]
+                    .iter()
+                    .chain([&import_path])
+                    .map(|p| format!("`{}`", p.display()))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                let message = format!("`{}` is imported in a cycle", import.ident);
+                diagnostics.push(Diagnostic::error(message, import.span).with_note(cycle));
+            } else {
+                match self.visit(&import_path, visit) {
+                    Ok(module) => imported.push((name.clone(), module)),
+                    Err(e) => {
+                        let message = format!("cannot find the script `{}`", import.ident);
+                        let label = format!("`{}`: {}", import_path.display(), e);
+                        diagnostics.push(Diagnostic::error(message, import.span).with_label(label));
+                    }
+                }
+            }
+            imports.push((name, import_path));
+        }
+
+        let base = self.next_base;
+        self.next_base += src.len() + 1;
+        let mut parser = Parser::with_offset(&src, &self.host, base);
+        for (name, module) in &imported {
+            for (callable, arity) in callables(&module.script) {
+                parser.declare(&format!("{}.{}", name, callable), arity);
+            }
+        }
+        let script = parser.parse_script();
+
+        let mut module = Module {
+            path: path.to_owned(),
+            source,
+            base,
+            script,
+            imports,
+            diagnostics: Vec::new(),
+        };
+        let parsed = parser.take_diagnostics();
+        diagnostics.extend(parsed.into_iter().map(|d| module.localize(d)));
+        module.diagnostics = diagnostics;
+
+        let module = Rc::new(module);
+        self.modules.insert(path.to_owned(), module.clone());
+        Ok(module)
+    }
+}
+
+/// The scripts imported by `src`, found without parsing it so they can be loaded first.
+pub fn imports_of(src: &str) -> Vec<Ident> {
+    let mut tokens = Tokenizer::new(src)
+        .filter(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Comment(_)))
+        .take_while(|t| t.kind != TokenKind::Eoi);
+
+    let mut imports = Vec::new();
+    let mut item_start = true;
+    while let Some(token) = tokens.next() {
+        match token.kind {
+            TokenKind::Percent if item_start => {
+                let Some(first) = tokens.next().filter(|t| t.kind == TokenKind::Ident) else {
+                    item_start = false;
+                    continue;
+                };
+                let mut import = Ident {
+                    ident: first.s.to_owned(),
+                    span: first.span,
+                };
+                let mut last = None;
+                for token in tokens.by_ref() {
+                    match token.kind {
+                        TokenKind::Dot => continue,
+                        TokenKind::Ident => {
+                            import.ident = format!("{}.{}", import.ident, token.s);
+                            import.span.end = token.span.end;
+                        }
+                        kind => {
+                            last = Some(kind);
+                            break;
+                        }
+                    }
+                }
+                imports.push(import);
+                item_start = last == Some(TokenKind::Semicolon);
+            }
+            kind => item_start = kind == TokenKind::Semicolon,
+        }
+    }
+    imports
+}
+
+/// Names callable with `@` that a script declares, with their arity.
+pub(crate) fn callables(script: &Script) -> Vec<(String, usize)> {
+    let mut callables = Vec::new();
+    for item in &script.items {
+        match item {
+            Item::Exponent(e) => callables.push((e.name.name.clone(), e.parameters.len())),
+            Item::Product(p) => callables.push((p.name.name.clone(), p.variables.len())),
+            Item::Sum(s) => {
+                for variant in &s.variants {
+                    let name = format!("{}.{}", s.name.name, variant.name.name);
+                    callables.push((name, variant.variables.len()));
+                }
+            }
+            Item::Alias(_) | Item::Import(_) => {}
+        }
+    }
+    callables
+}
+
+/// Namespace of the items of the module at `path`, `ui/button.dg` has `ui.button`.
+fn namespace(path: &Path) -> String {
+    path.with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn link(entry: &Path, modules: Vec<Rc<Module>>) -> Linked {
+    let mut items = Vec::new();
+    for module in &modules {
+        let prefix = if module.path == entry {
+            String::new()
+        } else {
+            format!("{}.", namespace(&module.path))
+        };
+        let imports: HashMap<&str, String> = module
+            .imports
+            .iter()
+            .map(|(name, path)| (name.as_str(), namespace(path)))
+            .collect();
+
+        let mut declared: HashSet<String> = callables(&module.script)
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect();
+        for item in &module.script.items {
+            match item {
+                Item::Alias(a) => declared.insert(a.name.name.clone()),
+                Item::Sum(s) => declared.insert(s.name.name.clone()),
+                _ => false,
+            };
+        }
+
+        let resolve = |name: &mut TypeName| {
+            if declared.contains(&name.name) {
+                name.name = format!("{}{}", prefix, name.name);
+            } else if let Some((import, rest)) = name.name.split_once('.') {
+                if let Some(namespace) = imports.get(import) {
+                    name.name = format!("{}.{}", namespace, rest);
+                }
+            }
+        };
+
+        let mut script = module.script.clone();
+        qualify(&mut script, &resolve);
+        items.extend(script.items);
+    }
+
+    Linked {
+        script: Script { items },
+        modules,
+    }
+}
+
+/// Renames every type and callable a script refers to or declares.
+fn qualify(script: &mut Script, resolve: &dyn Fn(&mut TypeName)) {
+    for item in &mut script.items {
+        match item {
+            Item::Import(_) => {}
+            Item::Alias(a) => {
+                resolve(&mut a.name);
+                resolve(&mut a.ty);
+            }
+            Item::Product(p) => {
+                resolve(&mut p.name);
+                p.variables.iter_mut().for_each(|v| resolve(&mut v.ty));
+            }
+            Item::Sum(s) => {
+                resolve(&mut s.name);
+                for variant in &mut s.variants {
+                    variant
+                        .variables
+                        .iter_mut()
+                        .for_each(|v| resolve(&mut v.ty));
+                }
+            }
+            Item::Exponent(e) => {
+                resolve(&mut e.name);
+                e.parameters.iter_mut().for_each(|p| resolve(&mut p.ty));
+                if let Some(ty) = &mut e.ret_ty {
+                    resolve(ty);
+                }
+                if let Some(body) = &mut e.body {
+                    for statement in &mut body.statements {
+                        qualify_expression(statement, resolve);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn qualify_expression(expression: &mut Expression, resolve: &dyn Fn(&mut TypeName)) {
+    match &mut expression.kind {
+        ExpressionKind::Literal(_) | ExpressionKind::Variable(_) => {}
+        ExpressionKind::Field { on, .. } => qualify_expression(on, resolve),
+        ExpressionKind::Unary { operand, .. } => qualify_expression(operand, resolve),
+        ExpressionKind::Binary { left, right, .. } => {
+            qualify_expression(left, resolve);
+            qualify_expression(right, resolve);
+        }
+        ExpressionKind::Call { ty, arguments } => {
+            resolve(ty);
+            for argument in arguments {
+                qualify_expression(argument, resolve);
+            }
+        }
+        ExpressionKind::List(items) => {
+            for item in items {
+                qualify_expression(item, resolve);
+            }
+        }
+        ExpressionKind::Match { on, arms } => {
+            qualify_expression(on, resolve);
+            for arm in arms {
+                qualify_expression(&mut arm.body, resolve);
+            }
+        }
+        ExpressionKind::Assign { target, value, .. } => {
+            qualify_expression(target, resolve);
+            qualify_expression(value, resolve);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        cell::Cell,
+        io,
+        path::{Path, PathBuf},
+    };
+
+    use super::{imports_of, FileSource, Loader, MemorySource, Source};
+    use crate::value::Value;
+
+    /// Counts reads to check modules are cached.
+    struct Counting(MemorySource, Cell<usize>);
+
+    impl Source for Counting {
+        fn read(&self, path: &Path) -> io::Result<String> {
+            self.1.set(self.1.get() + 1);
+            self.0.read(path)
+        }
+    }
+
+    /// A temporary directory where `main.dg` imports `util.dg` and calls its `Two`, removed when
+    /// dropped.
+    pub(crate) struct TempModules(pub PathBuf);
+
+    impl TempModules {
+        pub(crate) fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("dg-script-{}-{}", name, std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("main.dg"), "% util;\n^ Main -> Int => @util.Two;").unwrap();
+            std::fs::write(dir.join("util.dg"), "^ Two -> Int => 2;").unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempModules {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn source() -> MemorySource {
+        let mut source = MemorySource::new();
+        source
+            .insert(
+                "game/main.dg",
+                "% vec;
+                 % ui.button;
+                 * Player pos vec.Vector2 label button.Label;
+                 ^ Spawn x Float -> Player => @Player @vec.Vector2.Scale @vec.Vector2.One x @button.Label.Text \"p1\";
+                 ^ Width p Player -> Int => @button.Width p.label;",
+            )
+            .insert(
+                "game/vec.dg",
+                "* Vector2 x Float y Float;
+                 ^ Vector2.One -> Vector2 => @Vector2 1.0 1.0;
+                 ^ Vector2.Scale v Vector2 by Float -> Vector2 => @Vector2 * v.x by * v.y by;",
+            )
+            .insert(
+                "game/ui/button.dg",
+                "+ Label Text s Str Icon id Int;
+                 ^ Width l Label -> Int => $ l Text @Str.Len s Icon 1;",
+            );
+        source
+    }
+
+    #[test]
+    fn test_load() {
+        let mut loader = Loader::new(Counting(source(), Cell::new(0)));
+        let linked = loader.load("game/main.dg").unwrap();
+        assert_eq!(linked.modules.len(), 3);
+        assert_eq!(linked.entry().path, Path::new("game/main.dg"));
+
+        let player = linked.script.call("Spawn", vec![2.0.into()]).unwrap();
+        assert_eq!(
+            player.to_string(),
+            "@Player @game.vec.Vector2 2.0 2.0 @game.ui.button.Label.Text \"p1\""
+        );
+        assert_eq!(
+            linked.script.call("Width", vec![player]).unwrap(),
+            Value::Int(2)
+        );
+        assert!(linked.script.call("game.vec.Vector2.One", vec![]).is_ok());
+
+        // Modules are only read once.
+        assert_eq!(loader.source().1.get(), 3);
+        loader.load("game/main.dg").unwrap();
+        assert_eq!(loader.source().1.get(), 3);
+
+        // Invalidating a module also drops the modules importing it and what they linked to.
+        let button = "+ Label Text s Str Icon id Int;
+                      ^ Width l Label -> Int => 7;";
+        loader.source_mut().0.insert("game/ui/button.dg", button);
+        let linked = loader.load("game/main.dg").unwrap();
+        let player = linked.script.call("Spawn", vec![2.0.into()]).unwrap();
+        assert_eq!(
+            linked.script.call("Width", vec![player.clone()]).unwrap(),
+            Value::Int(2)
+        );
+        loader.invalidate(Path::new("game/ui/button.dg"));
+        assert!(loader.module(Path::new("game/main.dg")).is_none());
+        assert!(loader.module(Path::new("game/vec.dg")).is_some());
+        let linked = loader.load("game/main.dg").unwrap();
+        assert_eq!(loader.source().1.get(), 5);
+        assert_eq!(
+            linked.script.call("Width", vec![player]).unwrap(),
+            Value::Int(7)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut source = source();
+        source
+            .insert("a.dg", "% b;\n^ A -> Int => 1;")
+            .insert("b.dg", "% a;\n^ B -> Int => 2;")
+            .insert("c.dg", "% missing;")
+            .insert("d.dg", "% vec;\n^ D -> Int => @vec.Len;")
+            .insert("vec.dg", "^ Len -> Float => 1.5;")
+            .insert("e.dg", "% a.util;\n% b.util;")
+            .insert("a/util.dg", "^ A -> Int => 1;")
+            .insert("b/util.dg", "^ B -> Int => 2;");
+
+        let errors = Loader::new(source.clone()).load("a.dg").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].render(),
+            "error: `a` is imported in a cycle
+ --> b.dg:1:3
+  |
+1 | % a;
+  |   ^
+  = note: `a.dg` -> `b.dg` -> `a.dg`
+"
+        );
+
+        let errors = Loader::new(source.clone()).load("c.dg").unwrap_err();
+        assert_eq!(
+            errors[0].diagnostic.message,
+            "cannot find the script `missing`"
+        );
+        let errors = Loader::new(source.clone()).load("nothing.dg").unwrap_err();
+        assert!(errors[0]
+            .diagnostic
+            .message
+            .starts_with("cannot read `nothing.dg`"));
+
+        let errors = Loader::new(source.clone()).load("e.dg").unwrap_err();
+        assert_eq!(
+            errors[0].render(),
+            "error: two imports are named `util`
+ --> e.dg:2:3
+  |
+1 | % a.util;
+  |   ------ first imported here
+2 | % b.util;
+  |   ^^^^^^ imported again here
+"
+        );
+
+        // Type errors point into the module they are in.
+        let errors = Loader::new(source).load("d.dg").unwrap_err();
+        assert_eq!(errors[0].path, Path::new("d.dg"));
+        assert_eq!(errors[0].diagnostic.message, "expected Int but found Float");
+        assert_eq!(errors[0].diagnostic.primary.span, 21..29);
+    }
+
+    #[test]
+    fn test_files() {
+        let modules = TempModules::new("modules");
+        let linked = Loader::new(FileSource::new(&modules.0)).load("main.dg");
+        assert_eq!(
+            linked.unwrap().script.call("Main", vec![]).unwrap(),
+            Value::Int(2)
+        );
+    }
+
+    #[test]
+    fn test_imports_of() {
+        let imports = imports_of("# % no;\n% a;\n^ F => \"% b\";\n% ui.button;");
+        let names: Vec<_> = imports.iter().map(|i| i.ident.as_str()).collect();
+        assert_eq!(names, ["a", "ui.button"]);
+        assert_eq!(imports[1].span, 29..38);
+    }
+}
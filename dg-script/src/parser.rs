@@ -1,72 +1,208 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    ops::Range,
+};
 
-use crate::tokenizer::{Token, TokenKind, Tokenizer};
+use crate::{
+    diagnostic::Diagnostic,
+    host::Host,
+    tokenizer::{LiteralKind, Token, TokenKind, Tokenizer},
+};
+
+/// Built-in vector types, constructed like product types with `@Vec3 x y z`.
+const VECTORS: &[(&str, usize)] = &[("Vec2", 2), ("Vec3", 3), ("Vec4", 4)];
+
+/// Deepest expressions are nested in a body, everything walking the expression tree recurses
+/// into each level.
+pub const MAX_NESTING: usize = 128;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct Ident {
-    ident: String,
+    pub ident: String,
+    pub span: Range<usize>,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct TypeName {
-    name: String,
+    pub name: String,
+    pub span: Range<usize>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Import {
-    script: Ident,
+    /// Path of the script relative to the importing one, like `ui.button` for `ui/button.dg`.
+    pub script: Ident,
 }
 
 #[derive(Debug, Clone)]
 pub struct Variable {
-    name: Ident,
-    ty: TypeName,
+    pub name: Ident,
+    pub ty: TypeName,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `~`
+    Neg,
+    /// `!`
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UnaryOp::Neg => "~",
+            UnaryOp::Not => "!",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignOp {
+    /// `=`
+    Set,
+    /// `+=`
+    Add,
+    /// `-=`
+    Sub,
+    /// `*=`
+    Mul,
+    /// `/=`
+    Div,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    /// Variant of the matched sum type, its fields are in scope in `body`.
+    pub variant: TypeName,
+    pub body: Expression,
+}
+
+#[derive(Debug, Clone)]
 pub enum ExpressionKind {
-    Call { ty: TypeName },
-    Match { on: Vec<Ident> },
+    Literal(Literal),
+    Variable(Ident),
+    /// `a.x`
+    Field {
+        on: Box<Expression>,
+        field: Ident,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expression>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// `@Name args...` calls an exponent or native, or constructs a product, sum variant or vector.
+    Call {
+        ty: TypeName,
+        arguments: Vec<Expression>,
+    },
+    /// `[a b c]`
+    List(Vec<Expression>),
+    /// `$ on Variant body Variant body...`
+    Match {
+        on: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    /// `+= target value`, the target is a variable or a field of one.
+    Assign {
+        op: AssignOp,
+        target: Box<Expression>,
+        value: Box<Expression>,
+    },
 }
 
-#[derive(Debug)]
-pub struct Statement {
-    kind: ExpressionKind,
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Range<usize>,
 }
 
-// TODO: This...
-#[derive(Debug)]
+/// Expressions evaluated in order, the last one is the result.
+#[derive(Debug, Clone)]
 pub struct Body {
-    statements: Vec<Statement>,
+    pub statements: Vec<Expression>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AliasType {
-    name: TypeName,
-    ty: TypeName,
+    pub name: TypeName,
+    pub ty: TypeName,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SumType {
-    name: TypeName,
-    variants: Vec<ProductType>,
+    pub name: TypeName,
+    pub variants: Vec<ProductType>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ProductType {
-    name: TypeName,
-    variables: Vec<Variable>,
+    pub name: TypeName,
+    pub variables: Vec<Variable>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExponentType {
-    name: TypeName,
-    parameters: Vec<Variable>,
-    ret_ty: Option<TypeName>,
-    body: Option<Body>,
+    pub name: TypeName,
+    pub parameters: Vec<Variable>,
+    pub ret_ty: Option<TypeName>,
+    pub body: Option<Body>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Item {
     Import(Import),
     Alias(AliasType),
@@ -75,100 +211,226 @@ pub enum Item {
     Exponent(ExponentType),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Script {
-    items: Vec<Item>,
+    pub items: Vec<Item>,
 }
 
 #[derive(Debug)]
 pub struct ExponentSignature<'a> {
+    /// Span of the exponent's name.
+    name: Range<usize>,
     tokens: Vec<Token<'a>>,
     parameters: Vec<Variable>,
+    /// Span of the `;` ending the body.
+    end: Range<usize>,
+}
+
+/// Describes a token for diagnostics.
+fn describe(token: &Token) -> String {
+    match token.kind {
+        TokenKind::Eoi => "end of input".to_owned(),
+        _ => format!("`{}`", token.s),
+    }
 }
 
 pub struct Parser<'a> {
-    exponent_signatures: HashMap<TypeName, ExponentSignature<'a>>,
+    exponent_signatures: HashMap<String, ExponentSignature<'a>>,
+    /// Fields of product types and sum variants, variants are keyed as `Sum.Variant`.
+    type_fields: HashMap<String, usize>,
+    /// Arity of what the script can call but doesn't declare, from the host or imported scripts.
+    externals: HashMap<String, usize>,
+    /// Natives the host doesn't let the script call.
+    denied: HashSet<String>,
+    diagnostics: Vec<Diagnostic>,
     tokenizer: Tokenizer<'a>,
     peek: Token<'a>,
+    previous: TokenKind,
 }
 
 impl<'a> Parser<'a> {
+    /// A parser for scripts calling the [standard library](crate::stdlib).
     pub fn new(src: &'a str) -> Self {
-        let tokenizer = Tokenizer::new(src);
-        // let cur = Token::default();
+        Self::with_host(src, &Host::std())
+    }
+
+    /// A parser for scripts calling the functions of `host`.
+    pub fn with_host(src: &'a str, host: &Host) -> Self {
+        Self::with_offset(src, host, 0)
+    }
+
+    /// A parser whose spans start at `offset`, see [`Tokenizer::with_offset`].
+    pub fn with_offset(src: &'a str, host: &Host, offset: usize) -> Self {
+        let tokenizer = Tokenizer::with_offset(src, offset);
         let peek = Token::default();
 
         let mut s = Self {
             exponent_signatures: HashMap::new(),
+            type_fields: HashMap::new(),
+            externals: host
+                .functions()
+                .map(|(n, f)| (n.to_owned(), f.parameters.len()))
+                .collect(),
+            denied: host.denied().map(str::to_owned).collect(),
+            diagnostics: Vec::new(),
             tokenizer,
-            // cur,
             peek,
+            previous: TokenKind::Eoi,
         };
         s.next_token();
         s
     }
 
-    pub fn parse_script(&mut self) -> Option<Script> {
+    /// Parses every item it can, problems are collected in [`Parser::diagnostics`].
+    ///
+    /// A broken item is skipped up to the next `;` and exponents whose body doesn't parse are
+    /// left without one.
+    pub fn parse_script(&mut self) -> Script {
         let mut items = Vec::new();
 
         // First pass: to parse everything except for exponent bodies
         // as they require information about other exponent items parameters
         // for when calling them.
-        while let Some(item) = self.parse_item() {
-            items.push(item);
+        while self.peek_token().is_some() {
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(diagnostic) => {
+                    self.diagnostics.push(diagnostic);
+                    self.recover();
+                }
+            }
         }
 
         // Second pass: Parse exponent bodies using the now known exponent parameters.
-        items.iter_mut().filter_map(|i| match i {
-            Item::Exponent(e) => Some(e),
-            _ => None,
-        }).for_each(|e| {
-            e.body = self.parse_body();
-        });
+        for item in &mut items {
+            if let Item::Exponent(e) = item {
+                match self.parse_body(&e.name) {
+                    Ok(body) => e.body = Some(body),
+                    Err(diagnostic) => self.diagnostics.push(diagnostic),
+                }
+            }
+        }
 
-        Some(Script { items })
+        Script { items }
+    }
+
+    /// Lets the script call `name`, declared elsewhere, with `arity` arguments.
+    pub fn declare(&mut self, name: &str, arity: usize) {
+        self.externals.insert(name.to_owned(), arity);
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Skips past the next `;`, unless the item broke at one.
+    fn recover(&mut self) {
+        if self.previous == TokenKind::Semicolon {
+            return;
+        }
+        while let Some(token) = self.next_token() {
+            if token.kind == TokenKind::Semicolon {
+                break;
+            }
+        }
     }
 
-    pub fn parse_item(&mut self) -> Option<Item> {
-        let token = self.next_token()?;
+    pub fn parse_item(&mut self) -> Result<Item, Diagnostic> {
+        let token = self.expect_any("an item")?;
         let item = match token.kind {
             TokenKind::Caret => Item::Exponent(self.parse_exponent_type()?),
-            TokenKind::Asterisk => Item::Product(self.parse_product_type()?),
-            TokenKind::Plus => Item::Sum(self.parse_sum_type()?),
+            TokenKind::Asterisk => {
+                let product = self.parse_product_type()?;
+                self.type_fields
+                    .insert(product.name.name.clone(), product.variables.len());
+                Item::Product(product)
+            }
+            TokenKind::Plus => {
+                let sum = self.parse_sum_type()?;
+                for variant in &sum.variants {
+                    let name = format!("{}.{}", sum.name.name, variant.name.name);
+                    self.type_fields.insert(name, variant.variables.len());
+                }
+                Item::Sum(sum)
+            }
             TokenKind::Equals => Item::Alias(self.parse_alias_type()?),
             TokenKind::Percent => Item::Import(self.parse_import()?),
-            _ => return None,
+            _ => {
+                return Err(Diagnostic::error(
+                    format!("expected an item, found {}", describe(&token)),
+                    token.span,
+                )
+                .with_label("items start with `^`, `*`, `+`, `=` or `%`"))
+            }
         };
-        self.expect_token(TokenKind::Semicolon);
 
-        Some(item)
+        // The item itself is fine, so keep it and carry on from here.
+        if let Err(diagnostic) = self.expect_token(TokenKind::Semicolon, "`;` after the item") {
+            let diagnostic = diagnostic
+                .with_label("expected `;`")
+                .with_secondary(token.span, "the item starts here");
+            self.diagnostics.push(diagnostic);
+        }
+
+        Ok(item)
     }
 
-    pub fn parse_exponent_type(&mut self) -> Option<ExponentType> {
-        let name = self.parse_type_name()?;
+    pub fn parse_exponent_type(&mut self) -> Result<ExponentType, Diagnostic> {
+        let name = self.parse_type_name("the exponent's name")?;
         let mut parameters = Vec::new();
-        while let Some(parameter) = self.parse_variable() {
-            parameters.push(parameter);
+        while self.peek.kind == TokenKind::Ident {
+            parameters.push(self.parse_variable()?);
         }
-        let ret_ty = match self.expect_token(TokenKind::Returns) {
-            Some(_) => self.parse_type_name(),
+        let ret_ty = match self.eat_token(TokenKind::Returns) {
+            Some(_) => Some(self.parse_type_name("the return type")?),
             None => None,
         };
 
-        self.expect_token(TokenKind::WithBody)?;
-        
+        let expected = match ret_ty {
+            Some(_) => "`=>`",
+            None => "a parameter, `->` or `=>`",
+        };
+        self.expect_token(TokenKind::WithBody, expected)?;
+
         // Skip body to process later
         let mut body_tokens = Vec::new();
-        while self.peek_token()?.kind != TokenKind::Semicolon {
+        while self.peek.kind != TokenKind::Semicolon {
+            if self.peek.kind == TokenKind::Eoi {
+                return Err(Diagnostic::error(
+                    format!("the body of `{}` is never closed", name.name),
+                    self.peek.span.clone(),
+                )
+                .with_label("expected `;`")
+                .with_secondary(name.span, "the exponent starts here"));
+            }
             body_tokens.push(self.next_token().unwrap());
         }
 
-        self.exponent_signatures.insert(name.clone(), ExponentSignature{ 
-            tokens: body_tokens,
-            parameters: parameters.clone(),
-        });
+        // Calls are parsed against the signature, so a second one can't stand in for the first.
+        if let Some(first) = self.exponent_signatures.get(&name.name) {
+            return Err(Diagnostic::error(
+                format!("`{}` is defined more than once", name.name),
+                name.span,
+            )
+            .with_label("defined again here")
+            .with_secondary(first.name.clone(), "first defined here"));
+        }
+
+        self.exponent_signatures.insert(
+            name.name.clone(),
+            ExponentSignature {
+                name: name.span.clone(),
+                tokens: body_tokens,
+                parameters: parameters.clone(),
+                end: self.peek.span.clone(),
+            },
+        );
 
-        Some(ExponentType {
+        Ok(ExponentType {
             name,
             parameters,
             ret_ty,
@@ -176,64 +438,126 @@ impl<'a> Parser<'a> {
         })
     }
 
-    pub fn parse_body(&mut self) -> Option<Body> {
-        None
+    /// Parses the body tokens stashed for the exponent `name` by the first pass.
+    pub fn parse_body(&self, name: &TypeName) -> Result<Body, Diagnostic> {
+        let Some(signature) = self.exponent_signatures.get(&name.name) else {
+            let message = format!("`{}` has no body", name.name);
+            return Err(Diagnostic::error(message, name.span.clone()));
+        };
+        let mut parser = BodyParser {
+            parser: self,
+            tokens: &signature.tokens,
+            current: 0,
+            depth: 0,
+            end: signature.end.clone(),
+        };
+
+        let mut statements = Vec::new();
+        while parser.current < parser.tokens.len() {
+            statements.push(parser.parse_expression()?);
+        }
+
+        Ok(Body { statements })
     }
 
-    pub fn parse_product_type(&mut self) -> Option<ProductType> {
-        let name = self.parse_type_name()?;
+    /// How many arguments `@name` takes, `None` if nothing by that name can be called.
+    pub fn arity(&self, name: &str) -> Option<usize> {
+        if let Some(signature) = self.exponent_signatures.get(name) {
+            return Some(signature.parameters.len());
+        }
+
+        self.type_fields
+            .get(name)
+            .or_else(|| self.externals.get(name))
+            .copied()
+            .or_else(|| VECTORS.iter().find(|(n, _)| *n == name).map(|(_, a)| *a))
+    }
+
+    pub fn parse_product_type(&mut self) -> Result<ProductType, Diagnostic> {
+        let name = self.parse_type_name("the type's name")?;
         let mut variables = Vec::new();
-        while let Some(variable) = self.parse_variable() {
-            variables.push(variable);
+        while self.peek.kind == TokenKind::Ident {
+            variables.push(self.parse_variable()?);
         }
 
-        Some(ProductType { name, variables })
+        Ok(ProductType { name, variables })
     }
 
-    pub fn parse_variable(&mut self) -> Option<Variable> {
-        let name = self.parse_ident()?;
-        let ty = self.parse_type_name()?;
+    pub fn parse_variable(&mut self) -> Result<Variable, Diagnostic> {
+        let name = self.parse_ident("a name")?;
+        let ty = self.parse_type_name(&format!("the type of `{}`", name.ident))?;
 
-        Some(Variable { name, ty })
+        Ok(Variable { name, ty })
     }
 
-    pub fn parse_sum_type(&mut self) -> Option<SumType> {
-        let name = self.parse_type_name()?;
+    pub fn parse_sum_type(&mut self) -> Result<SumType, Diagnostic> {
+        let name = self.parse_type_name("the type's name")?;
         let mut variants = Vec::new();
-        while let Some(variant) = self.parse_product_type() {
-            variants.push(variant);
+        while self.peek.kind == TokenKind::Type {
+            variants.push(self.parse_product_type()?);
         }
 
-        Some(SumType { name, variants })
+        Ok(SumType { name, variants })
     }
 
-    pub fn parse_alias_type(&mut self) -> Option<AliasType> {
-        let name = self.parse_type_name()?;
-        let ty = self.parse_type_name()?;
-        Some(AliasType { name, ty })
+    pub fn parse_alias_type(&mut self) -> Result<AliasType, Diagnostic> {
+        let name = self.parse_type_name("the alias' name")?;
+        let ty = self.parse_type_name("the aliased type")?;
+        Ok(AliasType { name, ty })
     }
 
-    pub fn parse_import(&mut self) -> Option<Import> {
-        let script = self.parse_ident()?;
+    pub fn parse_import(&mut self) -> Result<Import, Diagnostic> {
+        let mut script = self.parse_ident("the name of a script")?;
+        while self.eat_token(TokenKind::Dot).is_some() {
+            let next = self.parse_ident("the name of a script")?;
+            script.ident = format!("{}.{}", script.ident, next.ident);
+            script.span.end = next.span.end;
+        }
 
-        Some(Import { script })
+        Ok(Import { script })
     }
 
-    pub fn parse_ident(&mut self) -> Option<Ident> {
-        let ident = self.expect_token(TokenKind::Ident)?;
-        Some(Ident {
+    pub fn parse_ident(&mut self, expected: &str) -> Result<Ident, Diagnostic> {
+        let ident = self.expect_token(TokenKind::Ident, expected)?;
+        Ok(Ident {
             ident: ident.s.to_owned(),
+            span: ident.span,
         })
     }
 
-    pub fn parse_type_name(&mut self) -> Option<TypeName> {
-        let type_name = self.expect_token(TokenKind::Type)?;
-        Some(TypeName {
+    pub fn parse_type_name(&mut self, expected: &str) -> Result<TypeName, Diagnostic> {
+        let type_name = self.expect_token(TokenKind::Type, expected)?;
+        Ok(TypeName {
             name: type_name.s.to_owned(),
+            span: type_name.span,
         })
     }
 
-    pub fn expect_token(&mut self, kind: TokenKind) -> Option<Token> {
+    /// Takes the next token if it is a `kind`, otherwise reports it wasn't `expected`.
+    pub fn expect_token(
+        &mut self,
+        kind: TokenKind,
+        expected: &str,
+    ) -> Result<Token<'a>, Diagnostic> {
+        match self.eat_token(kind) {
+            Some(token) => Ok(token),
+            None => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn expect_any(&mut self, expected: &str) -> Result<Token<'a>, Diagnostic> {
+        self.next_token().ok_or_else(|| self.unexpected(expected))
+    }
+
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        Diagnostic::error(
+            format!("expected {}, found {}", expected, describe(&self.peek)),
+            self.peek.span.clone(),
+        )
+    }
+
+    /// Takes the next token if it is a `kind`.
+    pub fn eat_token(&mut self, kind: TokenKind) -> Option<Token<'a>> {
         if self.peek_token()?.kind == kind {
             self.next_token()
         } else {
@@ -241,7 +565,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    pub fn peek_token(&mut self) -> Option<&Token> {
+    pub fn peek_token(&mut self) -> Option<&Token<'a>> {
         if self.peek.kind == TokenKind::Eoi {
             None
         } else {
@@ -251,16 +575,12 @@ impl<'a> Parser<'a> {
 
     pub fn next_token(&mut self) -> Option<Token<'a>> {
         let mut token = self.tokenizer.next()?;
-        while match token.kind {
-            TokenKind::Comment(_) => true,
-            TokenKind::Whitespace => true,
-            _ => false,
-        } {
+        while matches!(token.kind, TokenKind::Comment(_) | TokenKind::Whitespace) {
             token = self.tokenizer.next()?;
         }
 
         let cur = std::mem::replace(&mut self.peek, token);
-        // self.cur = std::mem::replace(&mut self.peek, token);
+        self.previous = cur.kind;
 
         if cur.kind == TokenKind::Eoi {
             None
@@ -270,17 +590,499 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Parses the prefix notation expressions of one exponent body.
+struct BodyParser<'p, 'a> {
+    parser: &'p Parser<'a>,
+    tokens: &'p [Token<'a>],
+    current: usize,
+    /// How many expressions the one being parsed is nested in.
+    depth: usize,
+    /// Span of the `;` ending the body.
+    end: Range<usize>,
+}
+
+impl<'p, 'a> BodyParser<'p, 'a> {
+    fn parse_expression(&mut self) -> Result<Expression, Diagnostic> {
+        if self.depth == MAX_NESTING {
+            let span = self.peek().map_or(self.end.clone(), |t| t.span.clone());
+            let message = format!("expressions are nested deeper than {} levels", MAX_NESTING);
+            return Err(Diagnostic::error(message, span));
+        }
+        self.depth += 1;
+        let expression = self.parse_nested();
+        self.depth -= 1;
+        expression
+    }
+
+    fn parse_nested(&mut self) -> Result<Expression, Diagnostic> {
+        let token = self.next_expected("an expression")?;
+        let start = token.span.start;
+
+        let kind = match token.kind {
+            TokenKind::Literal(kind) => ExpressionKind::Literal(Self::parse_literal(kind, token)?),
+            TokenKind::Ident => match token.s {
+                "true" => ExpressionKind::Literal(Literal::Bool(true)),
+                "false" => ExpressionKind::Literal(Literal::Bool(false)),
+                _ => return self.parse_fields(Self::variable(token)),
+            },
+            TokenKind::Neg | TokenKind::Not => {
+                let op = match token.kind {
+                    TokenKind::Neg => UnaryOp::Neg,
+                    _ => UnaryOp::Not,
+                };
+                ExpressionKind::Unary {
+                    op,
+                    operand: Box::new(self.parse_operand(token)?),
+                }
+            }
+            TokenKind::Equals
+            | TokenKind::PlusEquals
+            | TokenKind::MinusEquals
+            | TokenKind::TimesEquals
+            | TokenKind::DivideEquals => {
+                let op = match token.kind {
+                    TokenKind::Equals => AssignOp::Set,
+                    TokenKind::PlusEquals => AssignOp::Add,
+                    TokenKind::MinusEquals => AssignOp::Sub,
+                    TokenKind::TimesEquals => AssignOp::Mul,
+                    _ => AssignOp::Div,
+                };
+                let target = self.parse_place()?;
+                ExpressionKind::Assign {
+                    op,
+                    target: Box::new(target),
+                    value: Box::new(self.parse_operand(token)?),
+                }
+            }
+            TokenKind::Call => {
+                let name = &token.s[1..];
+                let arity = self.parser.arity(name).ok_or_else(|| {
+                    if self.parser.denied.contains(name) {
+                        let message = format!("`{}` isn't allowed here", name);
+                        return Diagnostic::error(message, token.span.clone())
+                            .with_label("the host doesn't let this script call it");
+                    }
+                    Diagnostic::error(format!("cannot find `{}`", name), token.span.clone())
+                        .with_label("not an exponent, type or native")
+                })?;
+                let ty = TypeName {
+                    name: name.to_owned(),
+                    span: token.span.clone(),
+                };
+                let arguments = (0..arity)
+                    .map(|_| self.parse_operand(token))
+                    .collect::<Result<_, _>>()?;
+                ExpressionKind::Call { ty, arguments }
+            }
+            TokenKind::OpeningBracket => {
+                let mut items = Vec::new();
+                loop {
+                    match self.peek().map(|t| t.kind) {
+                        Some(TokenKind::ClosingBracket) => break,
+                        Some(_) => items.push(self.parse_expression()?),
+                        None => {
+                            return Err(Diagnostic::error("unclosed `[`", self.end.clone())
+                                .with_label("expected `]`")
+                                .with_secondary(token.span.clone(), "opened here"))
+                        }
+                    }
+                }
+                self.next();
+                ExpressionKind::List(items)
+            }
+            TokenKind::Dollar => {
+                let on = self.parse_operand(token)?;
+                let mut arms = Vec::new();
+                while let Some(variant) = self.peek().filter(|t| t.kind == TokenKind::Type) {
+                    let variant = TypeName {
+                        name: variant.s.to_owned(),
+                        span: variant.span.clone(),
+                    };
+                    self.next();
+                    arms.push(MatchArm {
+                        body: self.parse_expression()?,
+                        variant,
+                    });
+                }
+                ExpressionKind::Match {
+                    on: Box::new(on),
+                    arms,
+                }
+            }
+            kind => {
+                let op = Self::binary_op(kind).ok_or_else(|| {
+                    Diagnostic::error(
+                        format!("expected an expression, found {}", describe(token)),
+                        token.span.clone(),
+                    )
+                })?;
+                ExpressionKind::Binary {
+                    op,
+                    left: Box::new(self.parse_operand(token)?),
+                    right: Box::new(self.parse_operand(token)?),
+                }
+            }
+        };
+
+        Ok(Expression {
+            kind,
+            span: start..self.last_end(),
+        })
+    }
+
+    /// Parses an operand of `operator`, pointing at it if the body ends too soon.
+    fn parse_operand(&mut self, operator: &Token) -> Result<Expression, Diagnostic> {
+        self.parse_expression().map_err(|diagnostic| {
+            if diagnostic.primary.span == self.end && diagnostic.secondary.is_empty() {
+                let message = format!("missing operand for {}", describe(operator));
+                diagnostic.with_secondary(operator.span.clone(), message)
+            } else {
+                diagnostic
+            }
+        })
+    }
+
+    /// Parses an assignment target, a variable with optional field accesses.
+    fn parse_place(&mut self) -> Result<Expression, Diagnostic> {
+        let token = self.next_expected("a variable to assign to")?;
+        if token.kind != TokenKind::Ident {
+            return Err(Diagnostic::error(
+                format!(
+                    "expected a variable to assign to, found {}",
+                    describe(token)
+                ),
+                token.span.clone(),
+            ));
+        }
+        self.parse_fields(Self::variable(token))
+    }
+
+    fn variable(token: &Token) -> Expression {
+        Expression {
+            kind: ExpressionKind::Variable(Ident {
+                ident: token.s.to_owned(),
+                span: token.span.clone(),
+            }),
+            span: token.span.clone(),
+        }
+    }
+
+    /// Parses `.field` accesses following `on`, each nesting `on` one level deeper.
+    fn parse_fields(&mut self, mut on: Expression) -> Result<Expression, Diagnostic> {
+        let mut depth = self.depth;
+        while let Some(dot) = self.peek().filter(|t| t.kind == TokenKind::Dot) {
+            if depth == MAX_NESTING {
+                let message = format!("expressions are nested deeper than {} levels", MAX_NESTING);
+                return Err(Diagnostic::error(message, dot.span.clone()));
+            }
+            depth += 1;
+            self.next();
+            let field = self.next_expected("a field name")?;
+            if field.kind != TokenKind::Ident {
+                return Err(Diagnostic::error(
+                    format!("expected a field name, found {}", describe(field)),
+                    field.span.clone(),
+                ));
+            }
+            let field = Ident {
+                ident: field.s.to_owned(),
+                span: field.span.clone(),
+            };
+            let span = on.span.start..field.span.end;
+            on = Expression {
+                kind: ExpressionKind::Field {
+                    on: Box::new(on),
+                    field,
+                },
+                span,
+            };
+        }
+        Ok(on)
+    }
+
+    fn parse_literal(kind: LiteralKind, token: &Token) -> Result<Literal, Diagnostic> {
+        let s = token.s;
+        let literal = match kind {
+            LiteralKind::Int(base) => {
+                let (sign, digits) = match s.strip_prefix('-') {
+                    Some(digits) => ("-", digits),
+                    None => ("", s),
+                };
+                let digits = format!("{}{}", sign, &digits[base.offset()..]);
+                i64::from_str_radix(&digits, base.radix())
+                    .ok()
+                    .map(Literal::Int)
+            }
+            LiteralKind::Float => s.parse().ok().map(Literal::Float),
+            LiteralKind::String => {
+                return Self::unescape(s).map(Literal::Str).ok_or_else(|| {
+                    Diagnostic::error("unterminated string", token.span.clone())
+                        .with_label("expected a closing `\"`")
+                })
+            }
+        };
+        literal.ok_or_else(|| {
+            Diagnostic::error(format!("invalid number `{}`", s), token.span.clone())
+                .with_note("integers must fit in 64 bits")
+        })
+    }
+
+    /// Decodes a string literal including its quotes, `None` if it is unterminated.
+    fn unescape(s: &str) -> Option<String> {
+        let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+        let mut unescaped = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            unescaped.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '0' => '\0',
+                c => c,
+            });
+        }
+        Some(unescaped)
+    }
+
+    fn binary_op(kind: TokenKind) -> Option<BinaryOp> {
+        Some(match kind {
+            TokenKind::Plus => BinaryOp::Add,
+            TokenKind::Minus => BinaryOp::Sub,
+            TokenKind::Asterisk => BinaryOp::Mul,
+            TokenKind::Slash => BinaryOp::Div,
+            TokenKind::Percent => BinaryOp::Rem,
+            TokenKind::DoubleEquals => BinaryOp::Eq,
+            TokenKind::NotEquals => BinaryOp::Ne,
+            TokenKind::Less => BinaryOp::Lt,
+            TokenKind::LessEquals => BinaryOp::Le,
+            TokenKind::Greater => BinaryOp::Gt,
+            TokenKind::GreaterEquals => BinaryOp::Ge,
+            TokenKind::And => BinaryOp::And,
+            TokenKind::Or => BinaryOp::Or,
+            _ => return None,
+        })
+    }
+
+    fn peek(&self) -> Option<&'p Token<'a>> {
+        self.tokens.get(self.current)
+    }
+
+    fn next(&mut self) -> Option<&'p Token<'a>> {
+        let token = self.tokens.get(self.current)?;
+        self.current += 1;
+        Some(token)
+    }
+
+    /// Takes the next token, reporting the end of the body as not being `expected`.
+    fn next_expected(&mut self, expected: &str) -> Result<&'p Token<'a>, Diagnostic> {
+        self.next().ok_or_else(|| {
+            Diagnostic::error(
+                format!("expected {}, found the end of the body", expected),
+                self.end.clone(),
+            )
+        })
+    }
+
+    /// End of the last consumed token.
+    fn last_end(&self) -> usize {
+        self.tokens[self.current - 1].span.end
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Parser;
+    use super::{BinaryOp, Body, ExpressionKind, Item, Literal, Parser, Script};
+
+    fn parse(s: &str) -> Script {
+        crate::parse(s).unwrap()
+    }
+
+    fn body<'s>(script: &'s Script, name: &str) -> &'s Body {
+        script
+            .items
+            .iter()
+            .find_map(|i| match i {
+                Item::Exponent(e) if e.name.name == name => e.body.as_ref(),
+                _ => None,
+            })
+            .unwrap()
+    }
 
     #[test]
     fn test_parser() {
-        let s = include_str!("scripts/example.dg");
+        for s in [
+            include_str!("scripts/example.dg"),
+            include_str!("scripts/thing.dg"),
+        ] {
+            let script = parse(s);
+            for item in &script.items {
+                if let Item::Exponent(e) = item {
+                    assert!(e.body.is_some(), "{} has no body", e.name.name);
+                }
+            }
+        }
+
+        let script = parse(include_str!("scripts/example.dg"));
+        assert_eq!(script.items.len(), 48);
+        assert!(body(&script, "DoNothing").statements.is_empty());
+
+        // - * a.x b.y * b.x a.y
+        let cross = &body(&script, "Vector2.Cross").statements;
+        assert_eq!(cross.len(), 1);
+        let ExpressionKind::Binary {
+            op: BinaryOp::Sub,
+            left,
+            right,
+        } = &cross[0].kind
+        else {
+            panic!("expected subtraction, got {:?}", cross[0]);
+        };
+        assert!(matches!(
+            left.kind,
+            ExpressionKind::Binary {
+                op: BinaryOp::Mul,
+                ..
+            }
+        ));
+        assert!(matches!(
+            right.kind,
+            ExpressionKind::Binary {
+                op: BinaryOp::Mul,
+                ..
+            }
+        ));
+
+        let one = &body(&script, "Vector2.NegOne").statements[0];
+        let ExpressionKind::Call { ty, arguments } = &one.kind else {
+            panic!("expected call, got {:?}", one);
+        };
+        assert_eq!(ty.name, "Vector2");
+        assert!(matches!(
+            arguments[..],
+            [
+                super::Expression {
+                    kind: ExpressionKind::Literal(Literal::Float(-1.0)),
+                    ..
+                },
+                super::Expression {
+                    kind: ExpressionKind::Literal(Literal::Float(-1.0)),
+                    ..
+                },
+            ]
+        ));
+
+        let update = &body(&script, "Update").statements[0];
+        let ExpressionKind::Match { arms, .. } = &update.kind else {
+            panic!("expected match, got {:?}", update);
+        };
+        let variants: Vec<_> = arms.iter().map(|a| a.variant.name.as_str()).collect();
+        assert_eq!(variants, vec!["Increment", "Decrement", "Reset"]);
+    }
+
+    #[test]
+    fn test_body() {
+        let s = "* P v Int; ^ F p P -> Int => += p.v 2 = p.v [1 \"a\\\"b\" true] @G; ^ G => 0x10;";
+        let script = parse(s);
+        let statements = &body(&script, "F").statements;
+        assert_eq!(statements.len(), 3);
+        assert_eq!(&s[statements[0].span.clone()], "+= p.v 2");
+        assert!(matches!(statements[0].kind, ExpressionKind::Assign { .. }));
+
+        let ExpressionKind::Assign { value, .. } = &statements[1].kind else {
+            panic!("expected assignment, got {:?}", statements[1]);
+        };
+        let ExpressionKind::List(items) = &value.kind else {
+            panic!("expected list, got {:?}", value);
+        };
+        assert!(matches!(&items[1].kind, ExpressionKind::Literal(Literal::Str(s)) if s == "a\"b"));
+        assert!(
+            matches!(statements[2].kind, ExpressionKind::Call { ref arguments, .. } if arguments.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_recovery() {
+        let s = "* A x Int
+* B y Int;
+^ F => + 1;
+& junk;
+= C;;
+^ G -> List => [1 2;
+^ H => @Nope 1;
+^ Ok -> Int => 1;";
         let mut parser = Parser::new(s);
         let script = parser.parse_script();
 
-        // println!("{:?}", script);
-        println!("{:#?}", parser.exponent_signatures);
+        let names: Vec<_> = script
+            .items
+            .iter()
+            .map(|i| match i {
+                Item::Product(p) => (p.name.name.as_str(), true),
+                Item::Exponent(e) => (e.name.name.as_str(), e.body.is_some()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("A", true),
+                ("B", true),
+                ("F", false),
+                ("G", false),
+                ("H", false),
+                ("Ok", true)
+            ]
+        );
+
+        let diagnostics: Vec<_> = parser
+            .diagnostics()
+            .iter()
+            .map(|d| (d.message.as_str(), &s[d.primary.span.clone()]))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                ("expected `;` after the item, found `*`", "*"),
+                ("expected an item, found `&`", "&"),
+                ("expected the aliased type, found `;`", ";"),
+                ("expected an item, found `;`", ";"),
+                ("expected an expression, found the end of the body", ";"),
+                ("unclosed `[`", ";"),
+                ("cannot find `Nope`", "@Nope"),
+            ]
+        );
+
+        let missing = &parser.diagnostics()[4];
+        assert_eq!(
+            missing.render("broken.dg", s),
+            "error: expected an expression, found the end of the body
+ --> broken.dg:3:11
+  |
+3 | ^ F => + 1;
+  |        - missing operand for `+`
+  |           ^
+"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_exponent() {
+        let s = "^ F a Int -> Int => a; ^ F a Int b Int -> Int => + a b; ^ G -> Int => @F 1;";
+        let mut parser = Parser::new(s);
+        let script = parser.parse_script();
+        assert_eq!(script.items.len(), 2);
+
+        // Calls use the first signature rather than the duplicate's.
+        let [diagnostic] = parser.diagnostics() else {
+            panic!("expected one diagnostic, got {:?}", parser.diagnostics());
+        };
+        assert_eq!(diagnostic.message, "`F` is defined more than once");
+        assert_eq!(diagnostic.primary.span, 25..26);
+        assert_eq!(diagnostic.secondary[0].span, 2..3);
+        assert_eq!(diagnostic.secondary[0].message, "first defined here");
     }
 }
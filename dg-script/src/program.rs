@@ -0,0 +1,420 @@
+use thiserror::Error;
+
+use crate::{
+    checker::{Checker, Type},
+    interpreter::Interpreter,
+    parser::{ExponentType, Item, TypeName},
+    reload::{LiveScript, Version},
+    value::Value,
+};
+
+const MODEL: &str = "Model";
+const MESSAGE: &str = "Message";
+const INIT: &str = "Init";
+const UPDATE: &str = "Update";
+const VIEW: &str = "View";
+
+#[derive(Debug, Error)]
+pub enum ProgramError {
+    #[error("the script has no `{0}`")]
+    Missing(&'static str),
+    #[error("`{name}` should be declared like `{expected}`")]
+    Signature {
+        name: &'static str,
+        expected: &'static str,
+    },
+    #[error("expected a `Message` but found {0}")]
+    NotAMessage(String),
+    #[error(transparent)]
+    Script(#[from] crate::Error),
+}
+
+/// How `Update` gives back the model.
+#[derive(Debug)]
+enum Update {
+    /// `^ Update msg Message mdl Model -> Model`, returning the new model.
+    Returns { message: usize },
+    /// `^ Update msg Message mdl Model =>`, assigning to the parameter `mdl`.
+    Assigns { message: usize, model: String },
+}
+
+/// The items of a script making it a program.
+#[derive(Debug)]
+struct Signatures {
+    model: Type,
+    update: Update,
+    /// Whether the script starts the model with `^ Init -> Model`.
+    init: bool,
+}
+
+impl Signatures {
+    fn new(version: &Version, checker: &Checker) -> Result<Self, ProgramError> {
+        let script = &version.linked.script;
+        let exponent = |name: &str| {
+            script.items.iter().find_map(|item| match item {
+                Item::Exponent(e) if e.name.name == name => Some(e),
+                _ => None,
+            })
+        };
+        let ty = |name: &TypeName| checker.lookup(&name.name);
+
+        let model = checker.lookup(MODEL).ok_or(ProgramError::Missing(MODEL))?;
+        match checker.lookup(MESSAGE) {
+            None => return Err(ProgramError::Missing(MESSAGE)),
+            Some(_) if !is_sum(script.items.iter(), MESSAGE) => {
+                return Err(ProgramError::Signature {
+                    name: MESSAGE,
+                    expected: "+ Message Variant field Type ...;",
+                })
+            }
+            Some(_) => {}
+        }
+
+        let update = exponent(UPDATE).ok_or(ProgramError::Missing(UPDATE))?;
+        let update = update_signature(update, &model, ty).ok_or(ProgramError::Signature {
+            name: UPDATE,
+            expected: "^ Update msg Message mdl Model -> Model",
+        })?;
+
+        let view = exponent(VIEW).ok_or(ProgramError::Missing(VIEW))?;
+        let takes_model = matches!(&*view.parameters, [p] if ty(&p.ty).as_ref() == Some(&model));
+        if !takes_model || view.ret_ty.is_none() {
+            return Err(ProgramError::Signature {
+                name: VIEW,
+                expected: "^ View mdl Model -> Type",
+            });
+        }
+
+        let init = exponent(INIT);
+        if let Some(init) = init {
+            let ret_ty = init.ret_ty.as_ref().and_then(ty);
+            if !init.parameters.is_empty() || ret_ty.as_ref() != Some(&model) {
+                return Err(ProgramError::Signature {
+                    name: INIT,
+                    expected: "^ Init -> Model",
+                });
+            }
+        }
+
+        Ok(Self {
+            model,
+            update,
+            init: init.is_some(),
+        })
+    }
+}
+
+fn is_sum<'a>(mut items: impl Iterator<Item = &'a Item>, name: &str) -> bool {
+    items.any(|item| matches!(item, Item::Sum(s) if s.name.name == name))
+}
+
+/// Which of `Update`'s parameters is the message, and how it gives back the model.
+fn update_signature(
+    update: &ExponentType,
+    model: &Type,
+    ty: impl Fn(&TypeName) -> Option<Type>,
+) -> Option<Update> {
+    let [a, b] = &*update.parameters else {
+        return None;
+    };
+    let message = Some(Type::Named(MESSAGE.to_owned()));
+    let (index, model_parameter) = if ty(&a.ty) == message && ty(&b.ty).as_ref() == Some(model) {
+        (0, b)
+    } else if ty(&b.ty) == message && ty(&a.ty).as_ref() == Some(model) {
+        (1, a)
+    } else {
+        return None;
+    };
+    match &update.ret_ty {
+        None => Some(Update::Assigns {
+            message: index,
+            model: model_parameter.name.ident.clone(),
+        }),
+        Some(ret_ty) if ty(ret_ty).as_ref() == Some(model) => {
+            Some(Update::Returns { message: index })
+        }
+        Some(_) => None,
+    }
+}
+
+/// Runs a script written in the Elm architecture, keeping its model and the last view of it.
+///
+/// The script declares the `Model` type, a sum type of the `Message`s it handles, an `Update`
+/// exponent taking a message and the model, and a `View` of the model:
+///
+/// ```text
+/// = Model Int;
+/// + Message Increment amount Int Reset;
+/// ^ Update msg Message mdl Model =>
+///     $ msg
+///         Increment += mdl amount
+///         Reset = mdl 0;
+/// ^ View mdl Model -> Str => @Int.ToStr mdl;
+/// ```
+///
+/// `Update` either returns the new model or assigns to its model parameter. The model starts as
+/// what `^ Init -> Model` returns, or as the default of its type without it. When the script is
+/// [reloaded](crate::reload::Reloader) the model is migrated to the new version.
+pub struct Program {
+    script: LiveScript,
+    generation: u64,
+    signatures: Signatures,
+    model: Value,
+    view: Value,
+}
+
+impl Program {
+    /// Starts the program, running `Init` if the script has one and `View`.
+    pub fn new(script: LiveScript) -> Result<Self, ProgramError> {
+        let version = script.version();
+        let signatures = Signatures::new(
+            &version,
+            &Checker::new(&version.linked.script, script.host()),
+        )?;
+        let model = if signatures.init {
+            script.call(INIT, Vec::new())?
+        } else {
+            version.default(&signatures.model, 0)
+        };
+        let view = script.call(VIEW, vec![model.clone()])?;
+        Ok(Self {
+            generation: version.generation,
+            script,
+            signatures,
+            model,
+            view,
+        })
+    }
+
+    pub fn model(&self) -> &Value {
+        &self.model
+    }
+
+    /// What `View` last returned.
+    pub fn view(&self) -> &Value {
+        &self.view
+    }
+
+    /// Builds the message `Message.variant` from the values of its fields.
+    pub fn message(&self, variant: &str, fields: Vec<Value>) -> Result<Value, ProgramError> {
+        let name = format!("{}.{}", MESSAGE, variant);
+        Ok(self.script.call(&name, fields)?)
+    }
+
+    /// Runs `Update` with `message`, then `View` if the model changed.
+    ///
+    /// Returns whether the view was made again. The model is kept if either fails.
+    pub fn dispatch(&mut self, message: Value) -> Result<bool, ProgramError> {
+        let reloaded = self.refresh()?;
+        if message.type_name() != MESSAGE {
+            return Err(ProgramError::NotAMessage(message.type_name().to_owned()));
+        }
+
+        let version = self.script.version();
+        let mut interpreter =
+            Interpreter::with_host(&version.linked.script, self.script.host().clone());
+        let message = self.script.migrate(message);
+        let model = match &self.signatures.update {
+            Update::Returns { message: index } => {
+                interpreter.call(UPDATE, arguments(*index, message, self.model.clone()))?
+            }
+            Update::Assigns {
+                message: index,
+                model,
+            } => interpreter.call_updating(
+                UPDATE,
+                arguments(*index, message, self.model.clone()),
+                model,
+            )?,
+        };
+        if model == self.model {
+            return Ok(reloaded);
+        }
+
+        self.view = interpreter.call(VIEW, vec![model.clone()])?;
+        self.model = model;
+        Ok(true)
+    }
+
+    /// Dispatches `messages` in order, returning each view made, for driving a program headlessly.
+    pub fn run(
+        &mut self,
+        messages: impl IntoIterator<Item = Value>,
+    ) -> Result<Vec<Value>, ProgramError> {
+        let mut views = Vec::new();
+        for message in messages {
+            if self.dispatch(message)? {
+                views.push(self.view.clone());
+            }
+        }
+        Ok(views)
+    }
+
+    /// Picks up a reload of the script, migrating the model and making the view again.
+    ///
+    /// Returns whether the script was reloaded since the last refresh. [`Program::dispatch`]
+    /// refreshes first, hosts only need to call this to show changes to `View` right away.
+    pub fn refresh(&mut self) -> Result<bool, ProgramError> {
+        let version = self.script.version();
+        if version.generation == self.generation {
+            return Ok(false);
+        }
+
+        let checker = Checker::new(&version.linked.script, self.script.host());
+        let signatures = Signatures::new(&version, &checker)?;
+        let model = self.script.migrate(self.model.clone());
+        let view = self.script.call(VIEW, vec![model.clone()])?;
+        self.generation = version.generation;
+        self.signatures = signatures;
+        self.model = model;
+        self.view = view;
+        Ok(true)
+    }
+}
+
+/// Arguments of `Update`, with the message at `index`.
+fn arguments(index: usize, message: Value, model: Value) -> Vec<Value> {
+    if index == 0 {
+        vec![message, model]
+    } else {
+        vec![model, message]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Program, ProgramError};
+    use crate::{
+        module::{FileSource, Loader},
+        reload::{
+            tests::{change, reloader},
+            Reloader,
+        },
+        value::Value,
+    };
+
+    #[test]
+    fn test_program() {
+        let reloader = reloader(
+            "* Model count Int step Int;
+             + Message Tick Step step Int;
+             ^ Init -> Model => @Model 0 1;
+             ^ Update mdl Model msg Message -> Model =>
+                 $ msg
+                     Tick @Model + mdl.count mdl.step mdl.step
+                     Step @Model mdl.count step;
+             ^ View mdl Model -> Str => @Int.ToStr mdl.count;",
+        );
+        let mut program = Program::new(reloader.script()).unwrap();
+        assert_eq!(program.view(), &Value::Str("0".to_owned()));
+
+        let tick = program.message("Tick", vec![]).unwrap();
+        let step = program.message("Step", vec![Value::Int(5)]).unwrap();
+        let views = program
+            .run([tick.clone(), step.clone(), tick.clone(), step, tick])
+            .unwrap();
+        // Setting the step again changes the model but not the view.
+        assert_eq!(
+            views,
+            ["1", "1", "6", "11"].map(|s| Value::Str(s.to_owned()))
+        );
+        assert_eq!(
+            program.model(),
+            &Value::product(
+                "Model",
+                vec![("count", Value::Int(11)), ("step", Value::Int(5))]
+            )
+        );
+
+        assert!(matches!(
+            program.dispatch(Value::Int(1)),
+            Err(ProgramError::NotAMessage(ty)) if ty == "Int"
+        ));
+        assert!(matches!(
+            program.message("Stop", vec![]),
+            Err(ProgramError::Script(_))
+        ));
+    }
+
+    #[test]
+    fn test_example() {
+        let loader = Loader::new(FileSource::new("src/scripts"));
+        let reloader = Reloader::new(loader, "example.dg").unwrap();
+        let mut program = Program::new(reloader.script()).unwrap();
+        assert_eq!(program.view(), &Value::Str("0".to_owned()));
+
+        let messages = [
+            program.message("Increment", vec![Value::Int(3)]).unwrap(),
+            program.message("Decrement", vec![Value::Int(1)]).unwrap(),
+            program.message("Reset", vec![]).unwrap(),
+            program.message("Reset", vec![]).unwrap(),
+        ];
+        let views = program.run(messages).unwrap();
+        assert_eq!(views, ["3", "2", "0"].map(|s| Value::Str(s.to_owned())));
+    }
+
+    #[test]
+    fn test_signatures() {
+        let error = |main: &str| match Program::new(reloader(main).script()) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("expected an error"),
+        };
+        assert_eq!(error("^ View -> Int => 0;"), "the script has no `Model`");
+        assert_eq!(
+            error("= Model Int; * Message; ^ View mdl Model -> Int => mdl;"),
+            "`Message` should be declared like `+ Message Variant field Type ...;`"
+        );
+        assert_eq!(
+            error(
+                "= Model Int; + Message Reset;
+                 ^ Update msg Message mdl Model -> Str => \"\";
+                 ^ View mdl Model -> Int => mdl;"
+            ),
+            "`Update` should be declared like `^ Update msg Message mdl Model -> Model`"
+        );
+        assert_eq!(
+            error("= Model Int; + Message Reset; ^ Update msg Message mdl Model =>;"),
+            "the script has no `View`"
+        );
+    }
+
+    #[test]
+    fn test_reload() {
+        let mut reloader = reloader(
+            "* Model count Int;
+             + Message Add n Int;
+             ^ Update msg Message mdl Model =>
+                 $ msg Add += mdl.count n;
+             ^ View mdl Model -> Int => mdl.count;",
+        );
+        let mut program = Program::new(reloader.script()).unwrap();
+        let add = program.message("Add", vec![Value::Int(2)]).unwrap();
+        assert!(program.dispatch(add.clone()).unwrap());
+        assert_eq!(program.view(), &Value::Int(2));
+
+        // The model keeps its count and gets the new field.
+        change(
+            &mut reloader,
+            "* Model count Int scale Int;
+             + Message Add n Int Scale by Int;
+             ^ Init -> Model => @Model 0 1;
+             ^ Update msg Message mdl Model =>
+                 $ msg
+                     Add += mdl.count n
+                     Scale = mdl.scale by;
+             ^ View mdl Model -> Int => * mdl.count mdl.scale;",
+        );
+        reloader.reload(&[PathBuf::from("main.dg")]).unwrap();
+        assert!(program.refresh().unwrap());
+        assert!(!program.refresh().unwrap());
+        assert_eq!(program.view(), &Value::Int(0));
+
+        let scale = program.message("Scale", vec![Value::Int(10)]).unwrap();
+        assert_eq!(
+            program.run([scale, add]).unwrap(),
+            [Value::Int(20), Value::Int(40)]
+        );
+    }
+}
@@ -0,0 +1,661 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    checker::{Checker, Type},
+    diagnostic::Diagnostic,
+    host::Host,
+    interpreter::Interpreter,
+    module::{FileSource, Linked, Loader, ModuleDiagnostic, Source},
+    parser::{Item, ProductType},
+    value::{Product, Value, Variant},
+    Error,
+};
+
+/// How deep defaults of products holding products are built before falling back to `Void`.
+const MAX_DEFAULT_DEPTH: usize = 8;
+
+/// A field of a product or sum variant.
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ty: Type,
+    span: Range<usize>,
+}
+
+/// Fields of a product or sum variant.
+#[derive(Debug)]
+struct Layout {
+    span: Range<usize>,
+    /// The sum type declaring the variant, `None` for products.
+    sum: Option<String>,
+    fields: Vec<Field>,
+}
+
+/// One successful load of a script being [reloaded](Reloader).
+#[derive(Debug)]
+pub struct Version {
+    pub linked: Linked,
+    /// Counts the loads, starting at 0.
+    pub generation: u64,
+    /// Keyed by product name or `Sum.Variant`.
+    layouts: HashMap<String, Layout>,
+    /// Spans of the sum types' names.
+    sums: HashMap<String, Range<usize>>,
+    /// A variant without fields of each sum type that has one, its values' default.
+    fieldless: HashMap<String, String>,
+}
+
+impl Version {
+    fn new(linked: Linked, generation: u64, host: &Host) -> Self {
+        let checker = Checker::new(&linked.script, host);
+        let layout = |p: &ProductType, sum: Option<&str>| Layout {
+            span: p.name.span.clone(),
+            sum: sum.map(str::to_owned),
+            fields: p
+                .variables
+                .iter()
+                .map(|v| Field {
+                    name: v.name.ident.clone(),
+                    ty: checker.lookup(&v.ty.name).unwrap_or(Type::Value),
+                    span: v.name.span.clone(),
+                })
+                .collect(),
+        };
+
+        let mut layouts = HashMap::new();
+        let mut sums = HashMap::new();
+        let mut fieldless = HashMap::new();
+        for item in &linked.script.items {
+            match item {
+                Item::Product(p) => {
+                    layouts.insert(p.name.name.clone(), layout(p, None));
+                }
+                Item::Sum(s) => {
+                    for variant in &s.variants {
+                        let name = format!("{}.{}", s.name.name, variant.name.name);
+                        layouts.insert(name, layout(variant, Some(&s.name.name)));
+                    }
+                    sums.insert(s.name.name.clone(), s.name.span.clone());
+                    if let Some(v) = s.variants.iter().find(|v| v.variables.is_empty()) {
+                        fieldless.insert(s.name.name.clone(), v.name.name.clone());
+                    }
+                }
+                Item::Import(_) | Item::Alias(_) | Item::Exponent(_) => {}
+            }
+        }
+
+        Self {
+            linked,
+            generation,
+            layouts,
+            sums,
+            fieldless,
+        }
+    }
+
+    /// Value new fields of type `ty` start with, `Void` for sum types without a fieldless variant.
+    pub(crate) fn default(&self, ty: &Type, depth: usize) -> Value {
+        match ty {
+            Type::Void | Type::Value => Value::Void,
+            Type::Int => Value::Int(0),
+            Type::Float => Value::Float(0.0),
+            Type::Str => Value::Str(String::new()),
+            Type::Bool => Value::Bool(false),
+            Type::Vec2 => Value::Vec2([0.0; 2]),
+            Type::Vec3 => Value::Vec3([0.0; 3]),
+            Type::Vec4 => Value::Vec4([0.0; 4]),
+            Type::List => Value::List(Vec::new()),
+            Type::Map => Value::Map(Default::default()),
+            Type::Named(_) if depth >= MAX_DEFAULT_DEPTH => Value::Void,
+            Type::Named(name) => {
+                if let Some(variant) = self.fieldless.get(name) {
+                    return Value::Variant(Variant {
+                        ty: name.clone(),
+                        variant: variant.clone(),
+                        fields: Vec::new(),
+                    });
+                }
+                match self.layouts.get(name) {
+                    Some(layout) => Value::Product(Product {
+                        ty: name.clone(),
+                        fields: layout
+                            .fields
+                            .iter()
+                            .map(|f| (f.name.clone(), self.default(&f.ty, depth + 1)))
+                            .collect(),
+                    }),
+                    None => Value::Void,
+                }
+            }
+        }
+    }
+
+    /// Fits the fields of a value made by an earlier version to `layout`.
+    fn migrate_fields(&self, layout: &Layout, old: Vec<(String, Value)>) -> Vec<(String, Value)> {
+        let mut old: HashMap<_, _> = old.into_iter().collect();
+        layout
+            .fields
+            .iter()
+            .map(|field| {
+                let value = match old.remove(&field.name).map(|v| self.migrate(v)) {
+                    Some(Value::Int(i)) if field.ty == Type::Float => Value::Float(i as f64),
+                    Some(value) if fits(&field.ty, &value) => value,
+                    _ => self.default(&field.ty, 0),
+                };
+                (field.name.clone(), value)
+            })
+            .collect()
+    }
+
+    fn migrate(&self, value: Value) -> Value {
+        match value {
+            Value::Product(p) => match self.layouts.get(&p.ty) {
+                Some(layout) => Value::Product(Product {
+                    fields: self.migrate_fields(layout, p.fields),
+                    ty: p.ty,
+                }),
+                // The product was removed.
+                None => self.default(&Type::Named(p.ty), 0),
+            },
+            Value::Variant(v) => match self.layouts.get(&format!("{}.{}", v.ty, v.variant)) {
+                Some(layout) => Value::Variant(Variant {
+                    fields: self.migrate_fields(layout, v.fields),
+                    ..v
+                }),
+                // The variant or its whole sum type was removed.
+                None => self.default(&Type::Named(v.ty), 0),
+            },
+            Value::List(items) => Value::List(items.into_iter().map(|v| self.migrate(v)).collect()),
+            Value::Map(map) => {
+                Value::Map(map.into_iter().map(|(k, v)| (k, self.migrate(v))).collect())
+            }
+            value => value,
+        }
+    }
+
+    /// Warnings about how values made by `old` change when migrated to this version.
+    fn changes(&self, old: &Version) -> Vec<ModuleDiagnostic> {
+        let mut names: Vec<_> = self.layouts.keys().collect();
+        names.sort();
+
+        // Warnings about removed types point into the old version, where they were declared.
+        let mut warnings = Vec::new();
+        for name in names {
+            let (new, Some(old)) = (&self.layouts[name], old.layouts.get(name)) else {
+                continue;
+            };
+            for field in &new.fields {
+                let message = match old.fields.iter().find(|f| f.name == field.name) {
+                    None => format!("`{}.{}` was added", name, field.name),
+                    Some(f) if !field.ty.accepts(&f.ty) => format!(
+                        "`{}.{}` changed from {} to {}",
+                        name, field.name, f.ty, field.ty
+                    ),
+                    Some(_) => continue,
+                };
+                let label = format!("existing values get `{}`", self.default(&field.ty, 0));
+                let warning = Diagnostic::warning(message, field.span.clone()).with_label(label);
+                warnings.push((warning, self));
+            }
+            for field in &old.fields {
+                if new.fields.iter().all(|f| f.name != field.name) {
+                    let message = format!("`{}.{}` was removed", name, field.name);
+                    let warning = Diagnostic::warning(message, new.span.clone())
+                        .with_label("existing values lose it");
+                    warnings.push((warning, self));
+                }
+            }
+        }
+
+        let mut removed: Vec<_> = old
+            .layouts
+            .iter()
+            .filter(|(name, _)| !self.layouts.contains_key(*name))
+            .collect();
+        removed.sort_by_key(|(name, _)| *name);
+        for (name, layout) in removed {
+            let (ty, span, version) = match &layout.sum {
+                Some(sum) => match self.sums.get(sum) {
+                    Some(span) => (sum, span, self),
+                    // Reported once for the whole sum type.
+                    None => continue,
+                },
+                None => (name, &layout.span, old),
+            };
+            let default = self.default(&Type::Named(ty.clone()), 0);
+            let warning = Diagnostic::warning(format!("`{}` was removed", name), span.clone())
+                .with_label(format!("existing values become `{}`", default));
+            warnings.push((warning, version));
+        }
+
+        let mut removed: Vec<_> = old
+            .sums
+            .iter()
+            .filter(|(name, _)| !self.sums.contains_key(*name))
+            .collect();
+        removed.sort_by_key(|(name, _)| *name);
+        for (name, span) in removed {
+            let default = self.default(&Type::Named(name.clone()), 0);
+            let warning = Diagnostic::warning(format!("`{}` was removed", name), span.clone())
+                .with_label(format!("existing values become `{}`", default));
+            warnings.push((warning, old));
+        }
+
+        warnings
+            .into_iter()
+            .map(|(warning, version)| {
+                let module = version
+                    .linked
+                    .module_at(warning.primary.span.start)
+                    .expect("spans are within a module");
+                ModuleDiagnostic {
+                    path: module.path.clone(),
+                    source: module.source.clone(),
+                    diagnostic: module.localize(warning),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether `value` is of type `ty`.
+fn fits(ty: &Type, value: &Value) -> bool {
+    match ty {
+        Type::Value => true,
+        Type::Named(name) => value.type_name() == name,
+        ty => value.type_name() == ty.to_string(),
+    }
+}
+
+/// A handle to whichever version of a [reloaded](Reloader) script is current, cheap to clone.
+#[derive(Clone)]
+pub struct LiveScript {
+    version: Rc<RefCell<Rc<Version>>>,
+    host: Host,
+}
+
+impl LiveScript {
+    /// The current version, calls already running keep the version they started with.
+    pub fn version(&self) -> Rc<Version> {
+        self.version.borrow().clone()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.version.borrow().generation
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    /// Calls `name` as defined by the current version.
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        let version = self.version();
+        Interpreter::with_host(&version.linked.script, self.host.clone()).call(name, args)
+    }
+
+    /// Fits products and variants made by an earlier version, and those nested in them, to the
+    /// fields of the current version.
+    ///
+    /// Fields that were removed are dropped, fields that were added or whose value no longer fits
+    /// get a default: zero, empty, `false`, a variant without fields or `Void`. Values of removed
+    /// products, variants and sum types are replaced by their type's default the same way.
+    pub fn migrate(&self, value: Value) -> Value {
+        self.version().migrate(value)
+    }
+}
+
+/// Events of the files under a [`FileSource`]'s root.
+struct Watch {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    root: PathBuf,
+}
+
+/// Loads a script again when its modules change, swapping the [`LiveScript`] over to it.
+///
+/// A reload that fails to parse or type check is rejected, the previous version keeps running.
+pub struct Reloader<S> {
+    loader: Loader<S>,
+    entry: PathBuf,
+    live: LiveScript,
+    watch: Option<Watch>,
+}
+
+impl<S: Source> Reloader<S> {
+    /// Loads the module at `entry` with everything it imports.
+    pub fn new(
+        mut loader: Loader<S>,
+        entry: impl Into<PathBuf>,
+    ) -> Result<Self, Vec<ModuleDiagnostic>> {
+        let entry = entry.into();
+        let linked = loader.load(&entry)?;
+        let host = loader.host().clone();
+        let version = Version::new(linked, 0, &host);
+        Ok(Self {
+            loader,
+            entry,
+            live: LiveScript {
+                version: Rc::new(RefCell::new(Rc::new(version))),
+                host,
+            },
+            watch: None,
+        })
+    }
+
+    /// The handle to give running instances.
+    pub fn script(&self) -> LiveScript {
+        self.live.clone()
+    }
+
+    pub fn loader(&self) -> &Loader<S> {
+        &self.loader
+    }
+
+    pub fn loader_mut(&mut self) -> &mut Loader<S> {
+        &mut self.loader
+    }
+
+    /// Loads the script again after the modules at `changed` changed.
+    ///
+    /// Returns warnings about the products, variants and sum types that were removed or whose
+    /// fields changed, or the diagnostics the reload was rejected with.
+    pub fn reload(
+        &mut self,
+        changed: &[PathBuf],
+    ) -> Result<Vec<ModuleDiagnostic>, Vec<ModuleDiagnostic>> {
+        for path in changed {
+            self.loader.invalidate(path);
+        }
+        let linked = self.loader.load(&self.entry)?;
+
+        let old = self.live.version();
+        let version = Version::new(linked, old.generation + 1, &self.live.host);
+        let warnings = version.changes(&old);
+        *self.live.version.borrow_mut() = Rc::new(version);
+        Ok(warnings)
+    }
+}
+
+impl Reloader<FileSource> {
+    /// Starts watching the `.dg` files under the source's root, see [`Reloader::poll`].
+    pub fn watch(&mut self) -> notify::Result<()> {
+        let root = self.loader.source().root().canonicalize()?;
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        self.watch = Some(Watch {
+            _watcher: watcher,
+            events,
+            root,
+        });
+        Ok(())
+    }
+
+    /// Reloads if a watched file changed since the last poll, without blocking.
+    ///
+    /// Returns `None` if nothing changed, otherwise the result of [`Reloader::reload`].
+    pub fn poll(&mut self) -> Option<Result<Vec<ModuleDiagnostic>, Vec<ModuleDiagnostic>>> {
+        let watch = self.watch.as_ref()?;
+        // Errors watching only mean a change may be missed.
+        let changed = changed(&watch.root, watch.events.try_iter().flatten());
+        (!changed.is_empty()).then(|| self.reload(&changed))
+    }
+}
+
+/// The `.dg` files under `root` that `events` changed, relative to it.
+fn changed(root: &Path, events: impl IntoIterator<Item = Event>) -> Vec<PathBuf> {
+    let mut changed = Vec::new();
+    for event in events {
+        if event.kind.is_access() {
+            continue;
+        }
+        for path in event.paths {
+            if path.extension() != Some("dg".as_ref()) {
+                continue;
+            }
+            if let Ok(path) = path.strip_prefix(root) {
+                if !changed.iter().any(|p: &PathBuf| p == path) {
+                    changed.push(path.to_owned());
+                }
+            }
+        }
+    }
+    changed
+}
+
+impl<S> Reloader<S> {
+    /// Whether `path` is a module the current version was loaded from.
+    pub fn is_loaded(&self, path: &Path) -> bool {
+        self.live
+            .version()
+            .linked
+            .modules
+            .iter()
+            .any(|m| m.path == path)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    };
+
+    use notify::{
+        event::{AccessKind, ModifyKind},
+        Event, EventKind,
+    };
+
+    use super::{changed, Reloader};
+    use crate::{
+        module::{tests::TempModules, FileSource, Loader, MemorySource},
+        value::Value,
+    };
+
+    /// Reloads `main.dg`, which starts out as `main`.
+    pub(crate) fn reloader(main: &str) -> Reloader<MemorySource> {
+        let mut source = MemorySource::new();
+        source.insert("main.dg", main);
+        Reloader::new(Loader::new(source), "main.dg").unwrap()
+    }
+
+    /// Replaces `main.dg` ahead of a reload.
+    pub(crate) fn change(reloader: &mut Reloader<MemorySource>, main: &str) {
+        reloader.loader_mut().source_mut().insert("main.dg", main);
+    }
+
+    #[test]
+    fn test_reload() {
+        let mut reloader = reloader("^ Speed -> Int => 1;");
+        let script = reloader.script();
+        assert_eq!(script.call("Speed", vec![]).unwrap(), Value::Int(1));
+
+        change(&mut reloader, "^ Speed -> Int => 2;");
+        assert!(reloader
+            .reload(&[PathBuf::from("main.dg")])
+            .unwrap()
+            .is_empty());
+        assert_eq!(script.call("Speed", vec![]).unwrap(), Value::Int(2));
+        assert_eq!(script.generation(), 1);
+
+        // A reload that doesn't type check leaves the previous version running.
+        change(&mut reloader, "^ Speed -> Int => 2.5;");
+        let errors = reloader.reload(&[PathBuf::from("main.dg")]).unwrap_err();
+        assert_eq!(errors[0].diagnostic.message, "expected Int but found Float");
+        assert_eq!(script.call("Speed", vec![]).unwrap(), Value::Int(2));
+        assert_eq!(script.generation(), 1);
+
+        change(&mut reloader, "^ Speed -> Int => 3;");
+        reloader.reload(&[PathBuf::from("main.dg")]).unwrap();
+        assert_eq!(script.call("Speed", vec![]).unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut reloader = reloader(
+            "+ Mood Happy Angry level Int;
+             * Player hp Int name Str mood Mood;
+             ^ New -> Player => @Player 10 \"p1\" @Mood.Angry 3;",
+        );
+        let script = reloader.script();
+        let player = script.call("New", vec![]).unwrap();
+        let players = Value::List(vec![player.clone()]);
+
+        change(
+            &mut reloader,
+            "+ Mood Calm Happy Angry level Float;
+             * Player hp Float name Bool mood Mood speed Vec2 friend Mood;
+             ^ New -> Player => @Player 10 false @Mood.Calm @Vec2 1.0 1.0 @Mood.Calm;",
+        );
+        let warnings = reloader.reload(&[PathBuf::from("main.dg")]).unwrap();
+        let messages: Vec<_> = warnings
+            .iter()
+            .map(|w| w.diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "`Player.name` changed from Str to Bool",
+                "`Player.speed` was added",
+                "`Player.friend` was added",
+            ]
+        );
+        assert_eq!(
+            warnings[2].render(),
+            "warning: `Player.friend` was added
+ --> main.dg:2:63
+  |
+2 |              * Player hp Float name Bool mood Mood speed Vec2 friend Mood;
+  |                                                               ^^^^^^ existing values get `@Mood.Calm`
+"
+        );
+
+        assert_eq!(
+            script.migrate(player).to_string(),
+            "@Player 10.0 false @Mood.Angry 3.0 @Vec2 0.0 0.0 @Mood.Calm"
+        );
+        assert_eq!(
+            script.migrate(players).to_string(),
+            "[@Player 10.0 false @Mood.Angry 3.0 @Vec2 0.0 0.0 @Mood.Calm]"
+        );
+
+        let angry = Value::variant("Mood", "Angry", vec![("level", 2.0.into())]);
+        change(
+            &mut reloader,
+            "+ Mood Calm Happy;
+             * Player hp Float name Bool mood Mood speed Vec2 friend Mood;",
+        );
+        let warnings = reloader.reload(&[PathBuf::from("main.dg")]).unwrap();
+        assert_eq!(
+            warnings[0].render(),
+            "warning: `Mood.Angry` was removed
+ --> main.dg:1:3
+  |
+1 | + Mood Calm Happy;
+  |   ^^^^ existing values become `@Mood.Calm`
+"
+        );
+        assert_eq!(script.migrate(angry.clone()).to_string(), "@Mood.Calm");
+
+        change(&mut reloader, "* Player hp Float;");
+        let warnings = reloader.reload(&[PathBuf::from("main.dg")]).unwrap();
+        let messages: Vec<_> = warnings
+            .iter()
+            .map(|w| w.diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "`Player.name` was removed",
+                "`Player.mood` was removed",
+                "`Player.speed` was removed",
+                "`Player.friend` was removed",
+                "`Mood` was removed",
+            ]
+        );
+        // Removed types point at where they were declared.
+        assert_eq!(
+            warnings[4].render(),
+            "warning: `Mood` was removed
+ --> main.dg:1:3
+  |
+1 | + Mood Calm Happy;
+  |   ^^^^ existing values become `void`
+"
+        );
+        assert_eq!(script.migrate(angry).to_string(), "void");
+        assert!(script.call("New", vec![]).is_err());
+
+        change(&mut reloader, "^ Score -> Int => 1;");
+        let warnings = reloader.reload(&[PathBuf::from("main.dg")]).unwrap();
+        assert_eq!(warnings[0].diagnostic.message, "`Player` was removed");
+        let player = Value::product("Player", vec![("hp", 1.0.into())]);
+        assert_eq!(script.migrate(player).to_string(), "void");
+    }
+
+    #[test]
+    fn test_changed() {
+        let root = Path::new("/game");
+        let modify =
+            |path: &str| Event::new(EventKind::Modify(ModifyKind::Any)).add_path(path.into());
+        let events = [
+            modify("/game/main.dg"),
+            Event::new(EventKind::Access(AccessKind::Any)).add_path("/game/util.dg".into()),
+            modify("/game/notes.txt"),
+            modify("/elsewhere/main.dg"),
+            modify("/game/ui/button.dg"),
+            modify("/game/main.dg"),
+        ];
+        assert_eq!(
+            changed(root, events),
+            [PathBuf::from("main.dg"), PathBuf::from("ui/button.dg")]
+        );
+    }
+
+    #[test]
+    fn test_reload_files() {
+        let modules = TempModules::new("reload");
+        let mut reloader =
+            Reloader::new(Loader::new(FileSource::new(&modules.0)), "main.dg").unwrap();
+        assert!(reloader.is_loaded(Path::new("util.dg")));
+
+        std::fs::write(modules.0.join("util.dg"), "^ Two -> Int => 22;").unwrap();
+        let script = reloader.script();
+        assert_eq!(script.call("Main", vec![]).unwrap(), Value::Int(2));
+        reloader.reload(&[PathBuf::from("util.dg")]).unwrap();
+        assert_eq!(script.call("Main", vec![]).unwrap(), Value::Int(22));
+    }
+
+    #[test]
+    #[ignore = "waits on the platform's file system events"]
+    fn test_watch() {
+        let modules = TempModules::new("watch");
+        let mut reloader =
+            Reloader::new(Loader::new(FileSource::new(&modules.0)), "main.dg").unwrap();
+        reloader.watch().unwrap();
+        assert!(reloader.poll().is_none());
+
+        std::fs::write(modules.0.join("util.dg"), "^ Two -> Int => 22;").unwrap();
+        // The write may be seen in several events, the first ones before it is complete.
+        let script = reloader.script();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while script.call("Main", vec![]).ok() != Some(Value::Int(22)) && Instant::now() < deadline
+        {
+            reloader.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(script.call("Main", vec![]).unwrap(), Value::Int(22));
+        assert!(script.generation() > 0);
+    }
+}
@@ -0,0 +1,574 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    checker::{Checker, Type},
+    diagnostic::Diagnostic,
+    host::Host,
+    interpreter::Interpreter,
+    module::{callables, FileSource, Linked, Loader},
+    parser::{Item, Parser, ProductType, Script, TypeName, Variable},
+    tokenizer::{TokenKind, Tokenizer},
+};
+
+/// Name of the exponent expressions entered are evaluated as.
+const INPUT: &str = "Repl.Input";
+
+const HELP: &str = "\
+Enter items, ending with `;`, or expressions to evaluate, like `+ 1 2`.
+Items replace the ones of the same name entered before. Expressions continue on
+the next line until they are complete or end with `;`.
+
+:type <expression>  shows the type of an expression
+:load <file.dg>     loads the items of a file and the files it imports
+:reload             loads the file again
+:items              lists the items declared
+:cancel             discards the input continuing over lines
+:help               shows this
+:quit               exits";
+
+/// What the REPL does with a line of input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// The input continues on the next line.
+    More,
+    Output(String),
+    Quit,
+}
+
+/// An item entered in the REPL.
+#[derive(Clone)]
+struct Entry {
+    name: String,
+    text: String,
+}
+
+/// Why the session's items and an expression couldn't be put together.
+enum Failure {
+    /// The expression ends too soon, more of it may be on the next line.
+    Incomplete,
+    Errors(String),
+}
+
+/// A session of entering items and evaluating expressions.
+pub struct Repl {
+    host: Host,
+    /// The file loaded, with everything it imports.
+    file: Option<(PathBuf, Linked)>,
+    entries: Vec<Entry>,
+    /// Lines of an item or expression not yet complete.
+    buffer: String,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    /// A session calling the [standard library](crate::stdlib).
+    pub fn new() -> Self {
+        Self::with_host(Host::std())
+    }
+
+    pub fn with_host(host: Host) -> Self {
+        Self {
+            host,
+            file: None,
+            entries: Vec::new(),
+            buffer: String::new(),
+        }
+    }
+
+    /// What to show before the next line, which is different while an input continues.
+    pub fn prompt(&self) -> &'static str {
+        if self.buffer.is_empty() {
+            "> "
+        } else {
+            ". "
+        }
+    }
+
+    /// Handles a line of input, commands are run even while an input continues.
+    pub fn line(&mut self, line: &str) -> Reply {
+        if let Some(command) = line.trim().strip_prefix(':') {
+            return self.command(command);
+        }
+        if self.buffer.is_empty() && line.trim().is_empty() {
+            return Reply::Output(String::new());
+        }
+        self.buffer += line;
+        self.buffer.push('\n');
+
+        let input = self.buffer.clone();
+        let reply = if starts_item(&input) {
+            if !ends_item(&input) {
+                return Reply::More;
+            }
+            self.declare(&input)
+        } else {
+            match self.evaluate(&input) {
+                Err(Failure::Incomplete) => return Reply::More,
+                Err(Failure::Errors(errors)) => errors,
+                Ok(output) => output,
+            }
+        };
+        self.buffer.clear();
+        Reply::Output(reply)
+    }
+
+    fn command(&mut self, command: &str) -> Reply {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(n, a)| (n, a.trim()));
+        let output = match name {
+            "type" | "t" => match self.build(&self.entries, Some(argument)) {
+                Ok(script) => match self.infer(&script) {
+                    Ok(ty) => ty.to_string(),
+                    Err(errors) => errors,
+                },
+                Err(Failure::Incomplete) => "the expression is incomplete".to_owned(),
+                Err(Failure::Errors(errors)) => errors,
+            },
+            "load" | "l" => self.load(Path::new(argument)),
+            "reload" | "r" => match self.file.as_ref().map(|(path, _)| path.clone()) {
+                Some(path) => self.load(&path),
+                None => "no file is loaded, use `:load <file.dg>`".to_owned(),
+            },
+            "items" | "i" => self.items(),
+            "cancel" | "c" if self.buffer.is_empty() => "nothing to cancel".to_owned(),
+            "cancel" | "c" => {
+                self.buffer.clear();
+                "cancelled".to_owned()
+            }
+            "help" | "h" | "?" => HELP.to_owned(),
+            "quit" | "q" => return Reply::Quit,
+            _ => format!("unknown command `:{}`, see `:help`", name),
+        };
+        Reply::Output(output)
+    }
+
+    /// Adds the items in `input`, replacing those of the same names.
+    fn declare(&mut self, input: &str) -> String {
+        let added = split_items(input);
+        if added.iter().any(|e| e.text.trim_start().starts_with('%')) {
+            return "imports aren't supported here, `:load` a file that imports it".to_owned();
+        }
+
+        let mut entries: Vec<Entry> = self
+            .entries
+            .iter()
+            .filter(|e| added.iter().all(|a| a.name != e.name))
+            .cloned()
+            .collect();
+        let names: Vec<_> = added.iter().map(|e| e.name.clone()).collect();
+        entries.extend(added);
+
+        match self.build(&entries, None) {
+            Ok(_) => {
+                self.entries = entries;
+                format!("defined {}", names.join(", "))
+            }
+            Err(Failure::Errors(errors)) => errors,
+            Err(Failure::Incomplete) => unreachable!("only expressions are incomplete"),
+        }
+    }
+
+    fn evaluate(&self, input: &str) -> Result<String, Failure> {
+        let mut script = self.build(&self.entries, Some(input))?;
+        let ty = self.infer(&script).map_err(Failure::Errors)?;
+        // Exponents only return their result when they declare a type.
+        for item in &mut script.items {
+            if let Item::Exponent(e) = item {
+                if e.name.name == INPUT {
+                    e.ret_ty = Some(TypeName {
+                        name: ty.to_string(),
+                        span: e.name.span.clone(),
+                    });
+                }
+            }
+        }
+        let value = Interpreter::with_host(&script, self.host.clone())
+            .call(INPUT, Vec::new())
+            .map_err(|e| Failure::Errors(format!("error: {}", e)))?;
+        Ok(format!("{} : {}", value, ty))
+    }
+
+    /// Type of the expression [built](Repl::build) into `script`.
+    fn infer(&self, script: &Script) -> Result<Type, String> {
+        let body = script.items.iter().find_map(|item| match item {
+            Item::Exponent(e) if e.name.name == INPUT => e.body.as_ref(),
+            _ => None,
+        });
+        let body = body.expect("the expression is built into the script");
+        // The script has already been checked.
+        Checker::new(script, &self.host)
+            .infer(body)
+            .map_err(|_| "the expression doesn't type check".to_owned())
+    }
+
+    fn load(&mut self, path: &Path) -> String {
+        if path.as_os_str().is_empty() {
+            return "expected a file, like `:load game/main.dg`".to_owned();
+        }
+        let (root, name) = match (path.parent(), path.file_name()) {
+            (Some(root), Some(name)) => (root, Path::new(name)),
+            _ => (Path::new(""), path),
+        };
+        let mut loader = Loader::with_host(FileSource::new(root), self.host.clone());
+        let linked = match loader.load(name) {
+            Ok(linked) => linked,
+            Err(diagnostics) => {
+                return diagnostics
+                    .iter()
+                    .map(|d| d.render())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        };
+
+        let previous = self.file.replace((path.to_owned(), linked));
+        match self.build(&self.entries, None) {
+            Ok(_) => format!("loaded `{}`", path.display()),
+            Err(failure) => {
+                self.file = previous;
+                let errors = match failure {
+                    Failure::Errors(errors) => errors,
+                    Failure::Incomplete => unreachable!("only expressions are incomplete"),
+                };
+                format!(
+                    "{}\n`{}` wasn't loaded, the items entered don't work with it",
+                    errors.trim_end(),
+                    path.display()
+                )
+            }
+        }
+    }
+
+    fn items(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some((path, linked)) = &self.file {
+            lines.push(format!("# {}", path.display()));
+            lines.extend(linked.entry().script.items.iter().filter_map(signature));
+        }
+        if !self.entries.is_empty() {
+            lines.push("# entered".to_owned());
+            lines.extend(self.entries.iter().map(|e| e.text.trim().to_owned()));
+        }
+        if lines.is_empty() {
+            return "no items are declared".to_owned();
+        }
+        lines.join("\n")
+    }
+
+    /// Parses and checks the loaded file's items with `entries` and the exponent `expression` is
+    /// evaluated as.
+    fn build(&self, entries: &[Entry], expression: Option<&str>) -> Result<Script, Failure> {
+        let mut items = Vec::new();
+        let base = match &self.file {
+            Some((_, linked)) => {
+                items.extend(linked.script.items.iter().cloned());
+                let last = linked.modules.iter().map(|m| m.base + m.source.len() + 1);
+                last.max().unwrap_or(0)
+            }
+            None => 0,
+        };
+
+        let mut source: String = entries.iter().map(|e| format!("{}\n", e.text)).collect();
+        // Diagnostics about the expression are shown against it alone.
+        let mut parts = vec![(base, source.clone())];
+        let mut end = None;
+        if let Some(expression) = expression {
+            let expression = expression.trim_end();
+            source += &format!("^ {} =>\n", INPUT);
+            parts.push((base + source.len(), expression.to_owned()));
+            // Whatever is missing from an expression ended with `;` is an error.
+            if ends_item(expression) {
+                source += expression;
+            } else {
+                source += &format!("{}\n;", expression);
+                end = Some(base + source.len() - 1);
+            }
+        }
+
+        let mut parser = Parser::with_offset(&source, &self.host, base);
+        if let Some((_, linked)) = &self.file {
+            for (name, arity) in callables(&linked.script) {
+                parser.declare(&name, arity);
+            }
+        }
+        let script = parser.parse_script();
+        let diagnostics = parser.take_diagnostics();
+        if diagnostics
+            .iter()
+            .any(|d| Some(d.primary.span.start) == end)
+        {
+            return Err(Failure::Incomplete);
+        }
+        if diagnostics.iter().any(Diagnostic::is_error) {
+            return Err(Failure::Errors(self.render(diagnostics, &parts)));
+        }
+
+        items.extend(script.items);
+        let script = Script { items };
+        if let Err(errors) = script.check_with(&self.host) {
+            let diagnostics = errors.into_iter().map(Diagnostic::from).collect();
+            return Err(Failure::Errors(self.render(diagnostics, &parts)));
+        }
+        Ok(script)
+    }
+
+    /// Renders diagnostics about the loaded file or the `parts` of the input, each starting at
+    /// an offset.
+    fn render(&self, diagnostics: Vec<Diagnostic>, parts: &[(usize, String)]) -> String {
+        let mut rendered = String::new();
+        for mut diagnostic in diagnostics {
+            let start = diagnostic.primary.span.start;
+            if let Some((base, source)) = parts.iter().rev().find(|(base, _)| start >= *base) {
+                let contains =
+                    |span: &Range<usize>| (*base..=base + source.len()).contains(&span.start);
+                let local = |span: &Range<usize>| span.start - base..span.end - base;
+                diagnostic.primary.span = local(&diagnostic.primary.span);
+                diagnostic.secondary.retain(|l| contains(&l.span));
+                for label in &mut diagnostic.secondary {
+                    label.span = local(&label.span);
+                }
+                rendered += &diagnostic.render("<repl>", source);
+            } else if let Some(module) = self
+                .file
+                .as_ref()
+                .and_then(|(_, linked)| linked.module_at(start))
+            {
+                let path = module.path.display().to_string();
+                rendered += &module.localize(diagnostic).render(&path, &module.source);
+            }
+        }
+        rendered
+    }
+}
+
+/// Whether `input` declares items rather than being an expression, `+ Name ...` is a sum type
+/// while `+ 1 2` is an addition.
+fn starts_item(input: &str) -> bool {
+    let mut tokens = significant(input);
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenKind::Percent), Some(TokenKind::Ident)) => true,
+        (Some(kind), Some(TokenKind::Type)) => matches!(
+            kind,
+            TokenKind::Equals | TokenKind::Plus | TokenKind::Asterisk | TokenKind::Caret
+        ),
+        _ => false,
+    }
+}
+
+fn ends_item(input: &str) -> bool {
+    significant(input).last() == Some(TokenKind::Semicolon)
+}
+
+/// Kinds of the tokens of `input` that aren't whitespace or comments.
+fn significant(input: &str) -> impl Iterator<Item = TokenKind> + '_ {
+    let mut tokenizer = Tokenizer::new(input);
+    std::iter::from_fn(move || Some(tokenizer.next_token().kind))
+        .take_while(|kind| *kind != TokenKind::Eoi)
+        .filter(|kind| !matches!(kind, TokenKind::Whitespace | TokenKind::Comment(_)))
+}
+
+/// Splits `input` into its items, named by the token after their sigil.
+fn split_items(input: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut tokenizer = Tokenizer::new(input);
+    let (mut start, mut name) = (0, None);
+    loop {
+        let token = tokenizer.next_token();
+        match token.kind {
+            TokenKind::Eoi => break,
+            TokenKind::Whitespace | TokenKind::Comment(_) => {}
+            TokenKind::Semicolon => {
+                entries.push(Entry {
+                    name: name.take().unwrap_or_default(),
+                    text: input[start..token.span.end].trim().to_owned(),
+                });
+                start = token.span.end;
+            }
+            TokenKind::Type | TokenKind::Ident if name.is_none() => {
+                name = Some(token.s.to_owned());
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// The declaration of an item without its body.
+fn signature(item: &Item) -> Option<String> {
+    let variables = |variables: &[Variable]| -> String {
+        variables
+            .iter()
+            .map(|v| format!(" {} {}", v.name.ident, v.ty.name))
+            .collect()
+    };
+    let fields = |ty: &ProductType| format!("{}{}", ty.name.name, variables(&ty.variables));
+    Some(match item {
+        Item::Import(_) => return None,
+        Item::Alias(a) => format!("= {} {};", a.name.name, a.ty.name),
+        Item::Product(p) => format!("* {};", fields(p)),
+        Item::Sum(s) => {
+            let variants: Vec<_> = s.variants.iter().map(fields).collect();
+            format!("+ {} {};", s.name.name, variants.join(" "))
+        }
+        Item::Exponent(e) => {
+            let mut signature = format!("^ {}{}", e.name.name, variables(&e.parameters));
+            if let Some(ret_ty) = &e.ret_ty {
+                signature += &format!(" -> {}", ret_ty.name);
+            }
+            signature + ";"
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Repl, Reply};
+    use crate::module::tests::TempModules;
+
+    fn output(repl: &mut Repl, lines: &str) -> String {
+        let mut reply = Reply::More;
+        for line in lines.lines() {
+            reply = repl.line(line);
+        }
+        match reply {
+            Reply::Output(output) => output,
+            reply => panic!("expected output, got {:?}", reply),
+        }
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut repl = Repl::new();
+        assert_eq!(output(&mut repl, "+ 1 2"), "3 : Int");
+        assert_eq!(output(&mut repl, "* 1.5 2;"), "3.0 : Float");
+
+        // Items and expressions continue until they are complete.
+        assert_eq!(repl.line("* Vector2"), Reply::More);
+        assert_eq!(repl.prompt(), ". ");
+        assert_eq!(
+            output(&mut repl, "    x Float\n    y Float;"),
+            "defined Vector2"
+        );
+        assert_eq!(repl.prompt(), "> ");
+        assert_eq!(repl.line("@Vector2 1.0"), Reply::More);
+        assert_eq!(output(&mut repl, "2.0"), "@Vector2 1.0 2.0 : Vector2");
+
+        assert_eq!(
+            output(
+                &mut repl,
+                "^ Len v Vector2 -> Float => @Float.Sqrt + * v.x v.x * v.y v.y;"
+            ),
+            "defined Len"
+        );
+        assert_eq!(output(&mut repl, ":type @Len @Vector2 3.0 4.0"), "Float");
+        assert_eq!(output(&mut repl, "@Len @Vector2 3.0 4.0"), "5.0 : Float");
+
+        // Declaring an item again replaces it, unless the new one doesn't check.
+        assert_eq!(
+            output(&mut repl, "^ Len v Vector2 -> Float => + v.x v.y;"),
+            "defined Len"
+        );
+        assert_eq!(output(&mut repl, "@Len @Vector2 3.0 4.0"), "7.0 : Float");
+        assert!(output(&mut repl, "^ Len v Vector2 -> Int => v.x;")
+            .starts_with("error: expected Int but found Float"));
+        assert_eq!(output(&mut repl, "@Len @Vector2 3.0 4.0"), "7.0 : Float");
+
+        assert_eq!(
+            output(&mut repl, ":items"),
+            "# entered\n* Vector2\n    x Float\n    y Float;\n^ Len v Vector2 -> Float => + v.x v.y;"
+        );
+        assert_eq!(
+            output(&mut repl, "+ 1 true"),
+            "error: cannot apply `+` to Int and Bool
+ --> <repl>:1:1
+  |
+1 | + 1 true
+  | ^^^^^^^^
+"
+        );
+        assert_eq!(output(&mut repl, "/ 1 0"), "error: division by zero");
+        assert_eq!(repl.line(":quit"), Reply::Quit);
+    }
+
+    #[test]
+    fn test_continuation() {
+        let mut repl = Repl::new();
+
+        // A `;` ends an expression, even one missing operands.
+        assert_eq!(repl.line("@Float.Sqrt"), Reply::More);
+        assert_eq!(
+            output(&mut repl, ";"),
+            "error: expected an expression, found the end of the body
+ --> <repl>:2:1
+  |
+1 | @Float.Sqrt
+  | ----------- missing operand for `@Float.Sqrt`
+2 | ;
+  | ^
+"
+        );
+        assert_eq!(repl.prompt(), "> ");
+        assert!(output(&mut repl, "+ 1;").starts_with("error: expected an expression"));
+
+        // Commands are run while an input continues, `:cancel` discards it.
+        assert_eq!(repl.line("+ 1"), Reply::More);
+        assert_eq!(output(&mut repl, ":type 1.0"), "Float");
+        assert_eq!(repl.prompt(), ". ");
+        assert_eq!(output(&mut repl, ":cancel"), "cancelled");
+        assert_eq!(repl.prompt(), "> ");
+        assert_eq!(output(&mut repl, ":cancel"), "nothing to cancel");
+        assert_eq!(output(&mut repl, "+ 2 2"), "4 : Int");
+
+        assert_eq!(repl.line("^ F"), Reply::More);
+        assert_eq!(repl.line(":quit"), Reply::Quit);
+    }
+
+    #[test]
+    fn test_load() {
+        let modules = TempModules::new("repl");
+        let dir = &modules.0;
+        let main = dir.join("main.dg");
+
+        let mut repl = Repl::new();
+        assert_eq!(
+            output(&mut repl, ":reload"),
+            "no file is loaded, use `:load <file.dg>`"
+        );
+        assert_eq!(
+            output(&mut repl, &format!(":load {}", main.display())),
+            format!("loaded `{}`", main.display())
+        );
+        assert_eq!(output(&mut repl, "@Main"), "2 : Int");
+        assert_eq!(
+            output(&mut repl, "^ Four -> Int => * @Main @util.Two;"),
+            "defined Four"
+        );
+        assert_eq!(output(&mut repl, "@Four"), "4 : Int");
+
+        std::fs::write(dir.join("util.dg"), "^ Two -> Int => 3;").unwrap();
+        output(&mut repl, ":reload");
+        assert_eq!(output(&mut repl, "@Four"), "9 : Int");
+        assert_eq!(
+            output(&mut repl, ":items"),
+            format!(
+                "# {}\n^ Main -> Int;\n# entered\n^ Four -> Int => * @Main @util.Two;",
+                main.display()
+            )
+        );
+
+        // A file that breaks the items entered isn't loaded.
+        std::fs::write(dir.join("main.dg"), "^ Main -> Float => 1.0;").unwrap();
+        let reload = output(&mut repl, ":reload");
+        assert!(reload.contains("wasn't loaded"), "{}", reload);
+        assert_eq!(output(&mut repl, "@Four"), "9 : Int");
+    }
+}
@@ -0,0 +1,404 @@
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::{
+    bytecode::Program,
+    diagnostic::Diagnostic,
+    host::Host,
+    interpreter::MAX_CALL_DEPTH,
+    value::{Product, Value, Variant},
+    vm::Vm,
+};
+
+/// Instructions run between checks of the time limit.
+const TIME_CHECK_INTERVAL: u64 = 256;
+
+/// What a single call into a script may do before it fails.
+///
+/// By default only the call depth is limited, to [`MAX_CALL_DEPTH`] like the interpreter's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Instructions run, fails with [`Error::BudgetExceeded`](crate::Error::BudgetExceeded).
+    pub steps: Option<u64>,
+    /// Deepest chain of exponent calls, fails with
+    /// [`Error::StackOverflow`](crate::Error::StackOverflow).
+    pub call_depth: usize,
+    /// Most items of Lists and Maps, nested ones included, and bytes of Strs the call may make
+    /// in total, fails with [`Error::AllocationLimit`](crate::Error::AllocationLimit).
+    pub allocation: Option<usize>,
+    /// Time the call may run for, fails with [`Error::Timeout`](crate::Error::Timeout).
+    pub time: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            steps: None,
+            call_depth: MAX_CALL_DEPTH,
+            allocation: None,
+            time: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits for scripts that aren't trusted, like mods running on a server.
+    pub fn untrusted() -> Self {
+        Self {
+            steps: Some(1_000_000),
+            call_depth: 64,
+            allocation: Some(1 << 16),
+            time: Some(Duration::from_millis(100)),
+        }
+    }
+}
+
+/// Keeps track of a call against its [`Limits`].
+pub(crate) struct Meter {
+    limits: Limits,
+    steps: u64,
+    /// Size of the values made so far, see [`size`].
+    allocated: usize,
+    start: Instant,
+}
+
+impl Meter {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: 0,
+            allocated: 0,
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Counts an instruction.
+    pub(crate) fn step(&mut self) -> Result<(), crate::Error> {
+        if self.limits.steps == Some(self.steps) {
+            return Err(crate::Error::BudgetExceeded(self.steps));
+        }
+        self.steps += 1;
+        if let Some(time) = self.limits.time {
+            if self.steps.is_multiple_of(TIME_CHECK_INTERVAL) && self.start.elapsed() > time {
+                return Err(crate::Error::Timeout(time));
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts `value`, just made by the call, towards the allocation limit.
+    pub(crate) fn made(&mut self, value: &Value) -> Result<(), crate::Error> {
+        if self.limits.allocation.is_none() {
+            return Ok(());
+        }
+        let len = size(value);
+        self.check(value.type_name(), len)?;
+        self.allocated += len;
+        Ok(())
+    }
+
+    /// Fails if the native `name` would make something larger than the allocation limit allows,
+    /// before it does.
+    ///
+    /// Only needed for the natives whose results can be far larger than their arguments, the
+    /// results of the others are counted with [`Meter::made`].
+    pub(crate) fn precheck(&self, name: &str, args: &[Value]) -> Result<(), crate::Error> {
+        if self.limits.allocation.is_none() {
+            return Ok(());
+        }
+        match (name, args) {
+            ("List.Range", [Value::Int(start), Value::Int(end)]) => {
+                let len = (*end as i128 - *start as i128).max(0);
+                self.check("List", len.try_into().unwrap_or(usize::MAX))
+            }
+            ("Str.Join", [Value::List(items), Value::Str(by)]) => {
+                let len = items
+                    .iter()
+                    .map(|i| i.as_str().map_or(0, str::len))
+                    .fold(by.len() * items.len(), usize::saturating_add);
+                self.check("Str", len)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if making something of size `len` would go over the allocation limit.
+    fn check(&self, ty: &str, len: usize) -> Result<(), crate::Error> {
+        match self.limits.allocation {
+            Some(limit) if self.allocated.saturating_add(len) > limit => {
+                Err(crate::Error::AllocationLimit {
+                    ty: ty.to_owned(),
+                    len,
+                    limit,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What `value` counts towards the allocation limit, the items of its Lists and Maps and the
+/// bytes of its Strs, all the way down.
+fn size(value: &Value) -> usize {
+    match value {
+        Value::Str(s) => s.len(),
+        Value::List(items) => items
+            .iter()
+            .map(size)
+            .fold(items.len(), usize::saturating_add),
+        Value::Map(map) => map
+            .iter()
+            .map(|(key, value)| key.len().saturating_add(size(value)))
+            .fold(map.len(), usize::saturating_add),
+        Value::Product(Product { fields, .. }) | Value::Variant(Variant { fields, .. }) => fields
+            .iter()
+            .map(|(_, value)| size(value))
+            .fold(0, usize::saturating_add),
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("the script has errors")]
+    Diagnostics(Vec<Diagnostic>),
+    #[error(transparent)]
+    Compile(#[from] crate::Error),
+}
+
+/// Runs scripts that aren't trusted, with [`Limits`] on every call.
+///
+/// Scripts only call the natives of the sandbox's host, [`Host::retain`] takes away those they
+/// shouldn't:
+///
+/// ```
+/// use dg_script::{host::Host, sandbox::{Limits, Sandbox}, value::Value, Error};
+///
+/// let mut host = Host::std();
+/// host.retain(|name| name.starts_with("Int."));
+/// let sandbox = Sandbox::new(host, Limits::untrusted());
+///
+/// assert!(sandbox.load("^ Root -> Float => @Float.Sqrt 2.0;").is_err());
+/// let program = sandbox.load("^ Loop n Int -> Int => @Loop + n 1;").unwrap();
+/// assert!(matches!(
+///     sandbox.call(&program, "Loop", vec![Value::Int(0)]),
+///     Err(Error::StackOverflow { .. })
+/// ));
+/// ```
+///
+/// Scripts run on the [`Vm`], whose calls don't recurse on the host's stack, and the parser
+/// rejects expressions nested deeper than [`MAX_NESTING`](crate::parser::MAX_NESTING).
+#[derive(Clone)]
+pub struct Sandbox {
+    host: Host,
+    limits: Limits,
+}
+
+impl Sandbox {
+    pub fn new(host: Host, limits: Limits) -> Self {
+        Self { host, limits }
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Parses, type checks and compiles `src`.
+    pub fn load(&self, src: &str) -> Result<Program, LoadError> {
+        let script = crate::load_with(src, &self.host).map_err(LoadError::Diagnostics)?;
+        Ok(script.compile_with(&self.host)?)
+    }
+
+    /// A VM for `program` with the sandbox's limits, for making several calls.
+    pub fn vm<'p>(&self, program: &'p Program) -> Vm<'p> {
+        let mut vm = Vm::with_host(program, self.host.clone());
+        vm.set_limits(self.limits);
+        vm
+    }
+
+    /// Calls the exponent, type constructor or native `name` of `program` with `args`.
+    pub fn call(
+        &self,
+        program: &Program,
+        name: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, crate::Error> {
+        self.vm(program).call(name, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Limits, LoadError, Sandbox};
+    use crate::{host::Host, parser::MAX_NESTING, value::Value, Error};
+
+    fn sandbox(limits: Limits) -> Sandbox {
+        Sandbox::new(Host::std(), limits)
+    }
+
+    #[test]
+    fn test_limits() {
+        let sandbox = sandbox(Limits {
+            steps: Some(10_000),
+            call_depth: 16,
+            allocation: Some(100),
+            time: None,
+        });
+        let program = sandbox
+            .load(
+                "^ Down n Int -> Int => @Down - n 1;
+                 ^ Grow s Str -> Str => @Grow + s s;
+                 ^ Range n Int -> List => @List.Range 0 n;
+                 ^ Double l List -> List => @Double @List.Concat l l;
+                 ^ Join l List -> Str => @Str.Join l \"-\";
+                 ^ Wrap l List -> List => [l l l l l l l l l l];
+                 ^ Nest n Int -> List => @Wrap @List.Range 0 n;",
+            )
+            .unwrap();
+        let call = |name: &str, args: Vec<Value>| sandbox.call(&program, name, args);
+
+        assert!(matches!(
+            call("Down", vec![Value::Int(0)]),
+            Err(Error::StackOverflow { depth: 16, .. })
+        ));
+        assert_eq!(
+            call("Grow", vec![Value::Str("ab".to_owned())])
+                .unwrap_err()
+                .to_string(),
+            "making a Str of 64 takes the call past its allocation limit of 100"
+        );
+        assert!(matches!(
+            call("Range", vec![Value::Int(100)]),
+            Ok(Value::List(l)) if l.len() == 100
+        ));
+        assert!(matches!(
+            call("Range", vec![Value::Int(i64::MAX)]),
+            Err(Error::AllocationLimit { len, .. }) if len as i64 == i64::MAX
+        ));
+        assert!(matches!(
+            call("Double", vec![Value::List(vec![Value::Void])]),
+            Err(Error::AllocationLimit { len: 64, .. })
+        ));
+        let items = vec![Value::Str("x".repeat(50)); 2];
+        assert!(matches!(
+            call("Join", vec![Value::List(items)]),
+            Err(Error::AllocationLimit { len: 102, .. })
+        ));
+
+        // Values nested in a List count towards the limit, each of the 10 copies of the range
+        // of 9 makes 9 more.
+        assert!(matches!(
+            call("Nest", vec![Value::Int(5)]),
+            Ok(Value::List(l)) if l.len() == 10
+        ));
+        assert!(matches!(
+            call("Nest", vec![Value::Int(9)]),
+            Err(Error::AllocationLimit { len: 100, .. })
+        ));
+
+        let sandbox = self::sandbox(Limits {
+            steps: Some(1000),
+            call_depth: 1000,
+            ..Limits::default()
+        });
+        assert!(matches!(
+            sandbox.call(&program, "Down", vec![Value::Int(0)]),
+            Err(Error::BudgetExceeded(1000))
+        ));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut host = Host::std();
+        host.register("Wait", || std::thread::sleep(Duration::from_millis(1)));
+        let sandbox = Sandbox::new(
+            host,
+            Limits {
+                time: Some(Duration::from_millis(20)),
+                call_depth: usize::MAX,
+                ..Limits::default()
+            },
+        );
+        let program = sandbox
+            .load("^ Spin n Int -> Int => @Wait @Spin + n 1;")
+            .unwrap();
+        assert!(matches!(
+            sandbox.call(&program, "Spin", vec![Value::Int(0)]),
+            Err(Error::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn test_natives() {
+        let mut host = Host::std();
+        host.retain(|name| name != "Float.Sqrt");
+        let sandbox = Sandbox::new(host, Limits::untrusted());
+
+        let LoadError::Diagnostics(diagnostics) = sandbox
+            .load("^ Root -> Float => @Float.Sqrt 2.0;")
+            .unwrap_err()
+        else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(diagnostics[0].message, "`Float.Sqrt` isn't allowed here");
+
+        // Programs compiled against the full standard library can't call it either.
+        let program = crate::load("^ Root -> Float => @Float.Sqrt 4.0;")
+            .unwrap()
+            .compile()
+            .unwrap();
+        assert!(matches!(
+            sandbox.call(&program, "Root", vec![]),
+            Err(Error::NotAllowed(name)) if name == "Float.Sqrt"
+        ));
+        assert!(matches!(
+            sandbox.call(&program, "Float.Sqrt", vec![Value::Float(4.0)]),
+            Err(Error::NotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_nesting() {
+        let sandbox = sandbox(Limits::untrusted());
+        let nested = |depth: usize| format!("^ Deep -> Int => {}1;", "- 0 ".repeat(depth - 1));
+        assert!(sandbox.load(&nested(MAX_NESTING)).is_ok());
+
+        let LoadError::Diagnostics(diagnostics) = sandbox.load(&nested(100_000)).unwrap_err()
+        else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(
+            diagnostics[0].message,
+            "expressions are nested deeper than 128 levels"
+        );
+
+        let fields = |depth: usize| {
+            let chain = ".next".repeat(depth - 2);
+            format!(
+                "* Node next Node n Int; ^ Deep p Node -> Int => p{}.n;",
+                chain
+            )
+        };
+        assert!(sandbox.load(&fields(MAX_NESTING)).is_ok());
+
+        let LoadError::Diagnostics(diagnostics) = sandbox.load(&fields(100_000)).unwrap_err()
+        else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(
+            diagnostics[0].message,
+            "expressions are nested deeper than 128 levels"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+
+use dg_math::{
+    interp::LinearInterp,
+    vector::{Vec2, Vec3},
+    Scalar,
+};
+
+use crate::{host::Host, interpreter::mismatch, value::Value, Error};
+
+type List = Vec<Value>;
+type Map = BTreeMap<String, Value>;
+
+/// Registers the natives every script may call, see [`Host::std`].
+pub(crate) fn register(host: &mut Host) {
+    float(host);
+    int(host);
+    string(host);
+    list(host);
+    map(host);
+    vector(host);
+}
+
+fn float(host: &mut Host) {
+    host.register("Float.Pi", || std::f64::consts::PI)
+        .register("Float.Sqrt", f64::sqrt)
+        .register("Float.PowI", |f: f64, n: i64| {
+            f.powi(n.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+        })
+        .register("Float.Pow", f64::powf)
+        .register("Float.Round", f64::round)
+        .register("Float.Ceil", f64::ceil)
+        .register("Float.Floor", f64::floor)
+        .register("Float.Abs", f64::abs)
+        .register("Float.Min", f64::min)
+        .register("Float.Max", f64::max)
+        // `f64::clamp` panics when `min > max`.
+        .register("Float.Clamp", |f: f64, min: f64, max: f64| {
+            f.max(min).min(max)
+        })
+        .register("Float.Lerp", |a: f64, b: f64, t: f64| a + (b - a) * t)
+        .register("Float.Sin", f64::sin)
+        .register("Float.Cos", f64::cos)
+        .register("Float.Tan", f64::tan)
+        .register("Float.Atan2", f64::atan2)
+        // Saturates, NaN becomes 0.
+        .register("Float.ToInt", |f: f64| f as i64)
+        .register("Float.ToStr", |f: f64| format!("{:?}", f));
+}
+
+fn int(host: &mut Host) {
+    host.register("Int.Abs", |i: i64| {
+        i.checked_abs().ok_or(Error::IntegerOverflow)
+    })
+    .register("Int.Min", |a: i64, b: i64| a.min(b))
+    .register("Int.Max", |a: i64, b: i64| a.max(b))
+    .register("Int.Clamp", |i: i64, min: i64, max: i64| {
+        i.max(min).min(max)
+    })
+    .register("Int.Sign", i64::signum)
+    .register("Int.ToFloat", |i: i64| i as f64)
+    .register("Int.ToStr", |i: i64| i.to_string());
+}
+
+fn string(host: &mut Host) {
+    host.register("Str.Len", |s: String| s.chars().count() as i64)
+        .register("Str.Concat", |a: String, b: String| a + &b)
+        .register("Str.Contains", |s: String, p: String| s.contains(&p))
+        .register("Str.StartsWith", |s: String, p: String| s.starts_with(&p))
+        .register("Str.EndsWith", |s: String, p: String| s.ends_with(&p))
+        .register("Str.Upper", |s: String| s.to_uppercase())
+        .register("Str.Lower", |s: String| s.to_lowercase())
+        .register("Str.Trim", |s: String| s.trim().to_owned())
+        .register("Str.Slice", |s: String, start: i64, end: i64| {
+            let len = s.chars().count();
+            let start = index(start, len + 1)?;
+            let end = index(end, len + 1)?.max(start);
+            Ok(s.chars().skip(start).take(end - start).collect::<String>())
+        })
+        .register("Str.Split", |s: String, by: String| {
+            let parts: List = if by.is_empty() {
+                s.chars().map(|c| Value::Str(c.to_string())).collect()
+            } else {
+                s.split(by.as_str()).map(Value::from).collect()
+            };
+            parts
+        })
+        .register("Str.Join", |items: List, by: String| {
+            let parts = items
+                .iter()
+                .map(|i| i.as_str().ok_or_else(|| mismatch("Str.Join", "Str", i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(parts.join(&by))
+        });
+}
+
+fn list(host: &mut Host) {
+    host.register("List.Len", |l: List| l.len() as i64)
+        .register("List.Get", |l: List, i: i64| {
+            let i = index(i, l.len())?;
+            Ok(l[i].clone())
+        })
+        .register("List.Set", |mut l: List, i: i64, value: Value| {
+            let i = index(i, l.len())?;
+            l[i] = value;
+            Ok(l)
+        })
+        .register("List.Push", |mut l: List, value: Value| {
+            l.push(value);
+            l
+        })
+        .register("List.Pop", |mut l: List| {
+            l.pop();
+            l
+        })
+        .register("List.Concat", |mut a: List, b: List| {
+            a.extend(b);
+            a
+        })
+        .register("List.Reverse", |mut l: List| {
+            l.reverse();
+            l
+        })
+        .register("List.Contains", |l: List, value: Value| l.contains(&value))
+        .register("List.Range", |start: i64, end: i64| {
+            (start..end).map(Value::Int).collect::<List>()
+        });
+}
+
+fn map(host: &mut Host) {
+    host.register("Map.New", Map::new)
+        .register("Map.Len", |m: Map| m.len() as i64)
+        .register("Map.Get", |m: Map, key: String| {
+            m.get(&key).cloned().ok_or(Error::MissingKey(key))
+        })
+        .register("Map.Has", |m: Map, key: String| m.contains_key(&key))
+        .register("Map.Insert", |mut m: Map, key: String, value: Value| {
+            m.insert(key, value);
+            m
+        })
+        .register("Map.Remove", |mut m: Map, key: String| {
+            m.remove(&key);
+            m
+        })
+        .register("Map.Keys", |m: Map| {
+            m.into_keys().map(Value::Str).collect::<List>()
+        })
+        .register("Map.Values", |m: Map| m.into_values().collect::<List>());
+}
+
+/// `Vec2` and `Vec3` operations backed by `dg-math`.
+fn vector(host: &mut Host) {
+    host.register("Vec2.Dot", |a: Vec2, b: Vec2| a.dot(b) as f64)
+        .register("Vec2.Cross", |a: Vec2, b: Vec2| a.cross(b) as f64)
+        .register("Vec2.Len", |v: Vec2| v.length() as f64)
+        .register("Vec2.LenSq", |v: Vec2| v.length_squared() as f64)
+        .register("Vec2.Dist", |a: Vec2, b: Vec2| a.distance_to(b) as f64)
+        .register("Vec2.Normalized", |v: Vec2| v.normalized())
+        .register("Vec2.Angle", |v: Vec2| v.angle() as f64)
+        .register("Vec2.Rotate", |v: Vec2, by: f64| v.rotate(by as Scalar))
+        .register("Vec2.Reflect", |v: Vec2, normal: Vec2| v.reflect(normal))
+        .register("Vec2.Lerp", |a: Vec2, b: Vec2, t: f64| {
+            Vec2::lerp(a, b, t as Scalar)
+        })
+        .register("Vec2.Extend", |v: Vec2, z: f64| v.extend(z as Scalar));
+
+    host.register("Vec3.Dot", |a: Vec3, b: Vec3| a.dot(b) as f64)
+        .register("Vec3.Cross", |a: Vec3, b: Vec3| a.cross(b))
+        .register("Vec3.Len", |v: Vec3| v.length() as f64)
+        .register("Vec3.LenSq", |v: Vec3| v.length_squared() as f64)
+        .register("Vec3.Dist", |a: Vec3, b: Vec3| a.distance_to(b) as f64)
+        .register("Vec3.Normalized", |v: Vec3| v.normalized())
+        .register("Vec3.Lerp", |a: Vec3, b: Vec3, t: f64| {
+            Vec3::lerp(a, b, t as Scalar)
+        })
+        .register("Vec3.Truncate", |v: Vec3| v.truncate());
+}
+
+/// Checks `i` is an index into something `len` long.
+fn index(i: i64, len: usize) -> Result<usize, Error> {
+    usize::try_from(i)
+        .ok()
+        .filter(|i| *i < len)
+        .ok_or(Error::IndexOutOfBounds { index: i, len })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{host::Host, value::Value, Error};
+
+    #[test]
+    fn test_std() {
+        let host = Host::std();
+        let call = |name: &str, args: Vec<Value>| host.call(name, &args).unwrap();
+        let list = Value::List(vec![1.into(), 2.into(), 3.into()]);
+
+        assert_eq!(
+            call("Float.Sqrt", vec![9.into()]).unwrap(),
+            Value::Float(3.0)
+        );
+        assert_eq!(
+            call("Float.Clamp", vec![5.0.into(), 1.0.into(), 0.0.into()]).unwrap(),
+            Value::Float(0.0)
+        );
+        assert!(matches!(
+            call("Int.Abs", vec![i64::MIN.into()]),
+            Err(Error::IntegerOverflow)
+        ));
+        assert_eq!(
+            call("Str.Slice", vec!["héllo".into(), 1.into(), 3.into()]).unwrap(),
+            Value::Str("él".to_owned())
+        );
+        assert_eq!(
+            call("Str.Split", vec!["a,b".into(), ",".into()]).unwrap(),
+            Value::List(vec!["a".into(), "b".into()])
+        );
+        assert_eq!(
+            call("List.Get", vec![list.clone(), 2.into()]).unwrap(),
+            Value::Int(3)
+        );
+        assert!(matches!(
+            call("List.Get", vec![list.clone(), (-1).into()]),
+            Err(Error::IndexOutOfBounds { index: -1, len: 3 })
+        ));
+        assert_eq!(
+            call("List.Reverse", vec![list]).unwrap().to_string(),
+            "[3 2 1]"
+        );
+
+        let map = call("Map.New", vec![]).unwrap();
+        let map = call("Map.Insert", vec![map, "hp".into(), 10.into()]).unwrap();
+        assert_eq!(
+            call("Map.Get", vec![map.clone(), "hp".into()]).unwrap(),
+            Value::Int(10)
+        );
+        assert!(matches!(
+            call("Map.Get", vec![map, "mp".into()]),
+            Err(Error::MissingKey(_))
+        ));
+
+        assert_eq!(
+            call(
+                "Vec2.Dot",
+                vec![Value::Vec2([1.0, 2.0]), Value::Vec2([3.0, 4.0])]
+            )
+            .unwrap(),
+            Value::Float(11.0)
+        );
+        assert_eq!(
+            call(
+                "Vec3.Cross",
+                vec![Value::Vec3([1.0, 0.0, 0.0]), Value::Vec3([0.0, 1.0, 0.0])]
+            )
+            .unwrap(),
+            Value::Vec3([0.0, 0.0, 1.0])
+        );
+        assert!(matches!(
+            call("Vec2.Len", vec![Value::Vec3([0.0; 3])]),
+            Err(Error::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_scripts() {
+        let script = crate::load(
+            "^ Greet names List -> Str => @Str.Concat \"hi \" @Str.Join names \", \";
+             ^ Speed v Vec2 -> Float => @Float.Round @Vec2.Len v;
+             ^ Score m Map -> Int => @Map.Get m \"score\";",
+        )
+        .unwrap();
+        let names = Value::List(vec!["a".into(), "b".into()]);
+        assert_eq!(
+            script.call("Greet", vec![names]).unwrap(),
+            Value::Str("hi a, b".to_owned())
+        );
+        assert_eq!(
+            script.call("Speed", vec![Value::Vec2([3.0, 4.0])]).unwrap(),
+            Value::Float(5.0)
+        );
+
+        let errors = crate::load("^ F -> Int => @Str.Len 1;").unwrap_err();
+        assert_eq!(errors[0].message, "expected Str but found Int");
+    }
+}
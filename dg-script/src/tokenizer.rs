@@ -8,7 +8,7 @@ pub enum Base {
 }
 
 impl Base {
-    fn radix(&self) -> u32 {
+    pub fn radix(&self) -> u32 {
         match self {
             Base::Decimal => 10,
             Base::Hexadecimal => 16,
@@ -16,7 +16,7 @@ impl Base {
         }
     }
 
-    fn offset(&self) -> usize {
+    pub fn offset(&self) -> usize {
         match self {
             Base::Decimal => 0,
             Base::Hexadecimal | Base::Binary => 2,
@@ -105,14 +105,22 @@ pub struct Tokenizer<'a> {
     s: &'a str,
     chars: Peekable<Chars<'a>>,
     current: usize,
+    /// Added to every span, so tokens of several sources can be told apart.
+    offset: usize,
 }
 
 impl<'a> Tokenizer<'a> {
     pub fn new(s: &'a str) -> Self {
+        Self::with_offset(s, 0)
+    }
+
+    /// A tokenizer whose spans start at `offset` instead of 0.
+    pub fn with_offset(s: &'a str, offset: usize) -> Self {
         Self {
             s,
             chars: s.chars().peekable(),
             current: 0,
+            offset,
         }
     }
 
@@ -124,7 +132,10 @@ impl<'a> Tokenizer<'a> {
                 return Token {
                     kind: TokenKind::Eoi,
                     s: "",
-                    span: Range::default(),
+                    span: Range {
+                        start: start + self.offset,
+                        end: start + self.offset,
+                    },
                 }
             }
         };
@@ -133,54 +144,56 @@ impl<'a> Tokenizer<'a> {
             ('#', _) => self.consume_comment(),
             c if Self::is_whitespace(c.0) => self.consume_whitespace(),
             c if Self::is_type_start(c.0) => self.consume_type(),
-            c if Self::is_ident(c.0) => self.consume_ident(),
-            c if c.0.is_numeric() => self.consume_number(c.0),
+            c if Self::is_ident_start(c.0) => self.consume_ident(),
+            c if c.0.is_ascii_digit() => self.consume_number(c.0),
+            // Prefix operators are always followed by whitespace, so `-1.0` is a negative literal.
+            ('-', Some(c)) if c.is_ascii_digit() => self.consume_number('-'),
             ('=', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::DoubleEquals
             }
             ('!', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::NotEquals
             }
             ('>', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::GreaterEquals
             }
             ('<', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::LessEquals
             }
             ('+', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::PlusEquals
             }
             ('-', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::MinusEquals
             }
             ('*', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::TimesEquals
             }
             ('/', Some('=')) => {
-                self.next();
+                self.next_char();
                 TokenKind::DivideEquals
             }
             ('-', Some('>')) => {
-                self.next();
+                self.next_char();
                 TokenKind::Returns
             }
             ('=', Some('>')) => {
-                self.next();
+                self.next_char();
                 TokenKind::WithBody
             }
             ('|', Some('|')) => {
-                self.next();
+                self.next_char();
                 TokenKind::Or
             }
             ('&', Some('&')) => {
-                self.next();
+                self.next_char();
                 TokenKind::And
             }
             ('@', _) => self.consume_call(),
@@ -208,15 +221,18 @@ impl<'a> Tokenizer<'a> {
 
         Token {
             kind,
-            span: Range { start, end },
+            span: Range {
+                start: start + self.offset,
+                end: end + self.offset,
+            },
             s: &self.s[start..end],
         }
     }
 
     fn next_char(&mut self) -> Option<char> {
         let c = self.chars.next();
-        if c.is_some() {
-            self.current += 1;
+        if let Some(c) = c {
+            self.current += c.len_utf8();
         }
         c
     }
@@ -235,27 +251,19 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn is_type_start(c: char) -> bool {
-        match c {
-            'A'..='Z' => true,
-            _ => false,
-        }
+        c.is_ascii_uppercase()
     }
 
     fn is_type(c: char) -> bool {
-        match c {
-            'a'..='z' => true,
-            'A'..='Z' => true,
-            '0'..='9' => true,
-            '.' => true,
-            _ => false,
-        }
+        matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '.')
+    }
+
+    fn is_ident_start(c: char) -> bool {
+        c.is_ascii_lowercase()
     }
 
     fn is_ident(c: char) -> bool {
-        match c {
-            'a'..='z' => true,
-            _ => false,
-        }
+        matches!(c, 'a'..='z' | '0'..='9' | '_')
     }
 
     fn consume_while<P>(&mut self, p: P)
@@ -272,17 +280,8 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn consume_comment(&mut self) -> TokenKind {
-        let doc = match self.look_ahead() {
-            Some('#') => true,
-            _ => false,
-        };
-
-        self.consume_while(|c: char| match c {
-            '\n' => false,
-            _ => true,
-        });
-        self.next();
-
+        let doc = self.look_ahead() == Some('#');
+        self.consume_while(|c| c != '\n');
         TokenKind::Comment(doc)
     }
 
@@ -291,12 +290,23 @@ impl<'a> Tokenizer<'a> {
         TokenKind::Type
     }
 
+    /// An identifier, or a type qualified by the module it is imported as, like `thing.Vector2`.
     fn consume_ident(&mut self) -> TokenKind {
         self.consume_while(Self::is_ident);
+        let mut rest = self.chars.clone();
+        if rest.next() == Some('.') && rest.next().is_some_and(Self::is_type_start) {
+            self.consume_while(Self::is_type);
+            return TokenKind::Type;
+        }
         TokenKind::Ident
     }
 
     fn consume_number(&mut self, start: char) -> TokenKind {
+        let start = match start {
+            '-' => self.next_char().expect("sign is followed by a digit"),
+            c => c,
+        };
+
         let base = if start == '0' {
             match self.look_ahead() {
                 Some('x') => {
@@ -314,33 +324,19 @@ impl<'a> Tokenizer<'a> {
         };
 
         match base {
-            Base::Decimal => self.consume_while(|c| match c {
-                '0'..='9' => true,
-                _ => false,
-            }),
-            Base::Hexadecimal => self.consume_while(|c| match c {
-                '0'..='9' => true,
-                'a'..='f' => true,
-                'A'..='F' => true,
-                _ => false,
-            }),
-            Base::Binary => self.consume_while(|c| match c {
-                '0'..='1' => true,
-                _ => false,
-            }),
+            Base::Decimal => self.consume_while(|c| c.is_ascii_digit()),
+            Base::Hexadecimal => self.consume_while(|c| c.is_ascii_hexdigit()),
+            Base::Binary => self.consume_while(|c| matches!(c, '0' | '1')),
         }
 
-        if self.look_ahead() == Some('.')
-            && match base {
-                Base::Decimal => true,
-                _ => false,
-            }
-        {
-            self.next_token();
-            self.consume_while(|c| match c {
-                '0'..='9' => true,
-                _ => false,
-            });
+        let fraction = {
+            let mut chars = self.chars.clone();
+            chars.next() == Some('.') && chars.next().is_some_and(|c| c.is_ascii_digit())
+        };
+
+        if fraction && base == Base::Decimal {
+            self.next_char();
+            self.consume_while(|c| c.is_ascii_digit());
             TokenKind::Literal(LiteralKind::Float)
         } else {
             TokenKind::Literal(LiteralKind::Int(base))
@@ -348,30 +344,18 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn consume_call(&mut self) -> TokenKind {
-        self.consume_while(|c: char| match c {
-            'A'..='Z' => true,
-            'a'..='z' => true,
-            '.' => true,
-            _ => false,
-        });
+        self.consume_while(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_');
         TokenKind::Call
     }
 
     fn consume_string(&mut self) -> TokenKind {
-        while let Some(c) = self.look_ahead() {
-            if match c {
-                '\\' if self.look_ahead() == Some('"') || self.look_ahead() == Some('\\') => {
+        while let Some(c) = self.next_char() {
+            match c {
+                '\\' => {
                     self.next_char();
-                    true
                 }
-                '"' => false,
-                _ => true,
-            } {
-                self.next_char();
-            } else {
-                // To include the last doublequote
-                self.next_char();
-                break;
+                '"' => break,
+                _ => {}
             }
         }
         TokenKind::Literal(LiteralKind::String)
@@ -388,7 +372,57 @@ impl<'a> Iterator for Tokenizer<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{TokenKind, Tokenizer};
+    use super::{Base, LiteralKind, TokenKind, Tokenizer};
+
+    fn kinds(s: &str) -> Vec<(TokenKind, &str)> {
+        Tokenizer::new(s)
+            .take_while(|t| t.kind != TokenKind::Eoi)
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .map(|t| (t.kind, t.s))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            kinds("@Vector2 -1.0 - a.x 0x1F => -> += # done"),
+            vec![
+                (TokenKind::Call, "@Vector2"),
+                (TokenKind::Literal(LiteralKind::Float), "-1.0"),
+                (TokenKind::Minus, "-"),
+                (TokenKind::Ident, "a"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Ident, "x"),
+                (
+                    TokenKind::Literal(LiteralKind::Int(Base::Hexadecimal)),
+                    "0x1F"
+                ),
+                (TokenKind::WithBody, "=>"),
+                (TokenKind::Returns, "->"),
+                (TokenKind::PlusEquals, "+="),
+                (TokenKind::Comment(false), "# done"),
+            ]
+        );
+
+        // Spans are byte offsets.
+        let tokens: Vec<_> = Tokenizer::new("\"é\" x").take(3).collect();
+        assert_eq!(tokens[0].span, 0..4);
+        assert_eq!(tokens[2].span, 5..6);
+        let tokens: Vec<_> = Tokenizer::with_offset("a", 10).take(2).collect();
+        assert_eq!(tokens[0].span, 10..11);
+        assert_eq!(tokens[1].span, 11..11);
+
+        assert_eq!(
+            kinds("thing.Vector2 a.b @ui_kit.Button.New"),
+            vec![
+                (TokenKind::Type, "thing.Vector2"),
+                (TokenKind::Ident, "a"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Ident, "b"),
+                (TokenKind::Call, "@ui_kit.Button.New"),
+            ]
+        );
+    }
 
     #[test]
     fn test_example() {
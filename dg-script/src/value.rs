@@ -0,0 +1,294 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+/// An instance of a product type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Product {
+    pub ty: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+/// An instance of one variant of a sum type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub ty: String,
+    pub variant: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Value {
+    #[default]
+    Void,
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Vec2([f64; 2]),
+    Vec3([f64; 3]),
+    Vec4([f64; 4]),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+    Product(Product),
+    Variant(Variant),
+    /// Reference to an object of an opaque type registered by the host.
+    Handle {
+        ty: String,
+        id: u64,
+    },
+}
+
+impl Value {
+    pub fn product(ty: &str, fields: Vec<(&str, Value)>) -> Self {
+        Value::Product(Product {
+            ty: ty.to_owned(),
+            fields: fields.into_iter().map(|(n, v)| (n.to_owned(), v)).collect(),
+        })
+    }
+
+    pub fn variant(ty: &str, variant: &str, fields: Vec<(&str, Value)>) -> Self {
+        Value::Variant(Variant {
+            ty: ty.to_owned(),
+            variant: variant.to_owned(),
+            fields: fields.into_iter().map(|(n, v)| (n.to_owned(), v)).collect(),
+        })
+    }
+
+    /// Name of the value's type as written in scripts.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Value::Void => "Void",
+            Value::Int(_) => "Int",
+            Value::Float(_) => "Float",
+            Value::Str(_) => "Str",
+            Value::Bool(_) => "Bool",
+            Value::Vec2(_) => "Vec2",
+            Value::Vec3(_) => "Vec3",
+            Value::Vec4(_) => "Vec4",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Product(p) => &p.ty,
+            Value::Variant(v) => &v.ty,
+            Value::Handle { ty, .. } => ty,
+        }
+    }
+
+    /// Ints convert to floats.
+    pub fn as_float(&self) -> Option<f64> {
+        match *self {
+            Value::Int(i) => Some(i as f64),
+            Value::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            Value::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Components of a vector.
+    pub fn components(&self) -> Option<&[f64]> {
+        match self {
+            Value::Vec2(v) => Some(v),
+            Value::Vec3(v) => Some(v),
+            Value::Vec4(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Builds a vector from 2 to 4 components.
+    pub fn vector(components: &[f64]) -> Option<Self> {
+        match *components {
+            [x, y] => Some(Value::Vec2([x, y])),
+            [x, y, z] => Some(Value::Vec3([x, y, z])),
+            [x, y, z, w] => Some(Value::Vec4([x, y, z, w])),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Product(Product { fields, .. }) | Value::Variant(Variant { fields, .. }) => {
+                fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Value> {
+        match self {
+            Value::Product(Product { fields, .. }) | Value::Variant(Variant { fields, .. }) => {
+                fields.iter_mut().find(|(n, _)| n == name).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+fn write_fields(f: &mut std::fmt::Formatter<'_>, fields: &[(String, Value)]) -> std::fmt::Result {
+    for (_, value) in fields {
+        write!(f, " {}", value)?;
+    }
+    Ok(())
+}
+
+/// Formats values the way they would be written in a script, e.g. `@Vector2 1.0 -1.0`.
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Void => write!(f, "void"),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Vec2(_) | Value::Vec3(_) | Value::Vec4(_) => {
+                write!(f, "@{}", self.type_name())?;
+                for c in self.components().unwrap() {
+                    write!(f, " {:?}", c)?;
+                }
+                Ok(())
+            }
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Product(p) => {
+                write!(f, "@{}", p.ty)?;
+                write_fields(f, &p.fields)
+            }
+            Value::Variant(v) => {
+                write!(f, "@{}.{}", v.ty, v.variant)?;
+                write_fields(f, &v.fields)
+            }
+            Value::Handle { ty, id } => write!(f, "<{} {}>", ty, id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Value;
+
+    #[test]
+    fn test_display() {
+        let point = Value::product("Point", vec![("x", 1.0.into()), ("y", (-1.0).into())]);
+        let some = Value::variant("Option", "Some", vec![("v", point.clone())]);
+        let map = BTreeMap::from([("a".to_owned(), 1.into()), ("b".to_owned(), "x".into())]);
+        let values = [
+            (Value::Void, "void"),
+            (Value::Int(-3), "-3"),
+            (Value::Float(2.0), "2.0"),
+            (Value::Str("say \"hi\"".to_owned()), "\"say \\\"hi\\\"\""),
+            (Value::Bool(true), "true"),
+            (Value::Vec3([1.0, 0.5, 0.0]), "@Vec3 1.0 0.5 0.0"),
+            (Value::List(vec![1.into(), Value::List(vec![])]), "[1 []]"),
+            (Value::Map(map), "{\"a\": 1, \"b\": \"x\"}"),
+            (point, "@Point 1.0 -1.0"),
+            (some, "@Option.Some @Point 1.0 -1.0"),
+            (Value::variant("Option", "None", vec![]), "@Option.None"),
+            (
+                Value::Handle {
+                    ty: "Entity".to_owned(),
+                    id: 7,
+                },
+                "<Entity 7>",
+            ),
+        ];
+        for (value, text) in values {
+            assert_eq!(value.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_vector() {
+        assert_eq!(Value::vector(&[1.0, 2.0]), Some(Value::Vec2([1.0, 2.0])));
+        assert_eq!(
+            Value::vector(&[1.0, 2.0, 3.0, 4.0]),
+            Some(Value::Vec4([1.0, 2.0, 3.0, 4.0]))
+        );
+        assert_eq!(Value::vector(&[1.0]), None);
+        assert_eq!(Value::vector(&[0.0; 5]), None);
+
+        let v = Value::vector(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(v.components(), Some(&[1.0, 2.0, 3.0][..]));
+        assert_eq!(Value::Int(1).components(), None);
+    }
+
+    #[test]
+    fn test_field_mut() {
+        let mut point = Value::product("Point", vec![("x", 1.into()), ("y", 2.into())]);
+        *point.field_mut("y").unwrap() = 5.into();
+        assert_eq!(point.field("y"), Some(&Value::Int(5)));
+        assert_eq!(point.field("x"), Some(&Value::Int(1)));
+        assert_eq!(point.field_mut("z"), None);
+        assert_eq!(Value::Int(1).field_mut("x"), None);
+
+        let mut some = Value::variant("Option", "Some", vec![("v", 1.into())]);
+        *some.field_mut("v").unwrap() = "one".into();
+        assert_eq!(some.to_string(), "@Option.Some \"one\"");
+    }
+}
@@ -0,0 +1,492 @@
+use std::{borrow::Cow, rc::Rc};
+
+use crate::{
+    bytecode::{Instruction, Program, Target},
+    host::{Host, Native},
+    interpreter::{assign_path, binary, check_arity, get_field, mismatch, unary},
+    parser::BinaryOp,
+    sandbox::{Limits, Meter},
+    value::{Product, Value, Variant},
+    Error,
+};
+
+struct Frame {
+    function: u16,
+    ip: usize,
+    /// Stack index of the first local.
+    base: usize,
+}
+
+/// Runs compiled [`Program`]s on a value stack.
+pub struct Vm<'p> {
+    program: &'p Program,
+    natives: Vec<Native>,
+    host: Host,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    limits: Limits,
+}
+
+impl<'p> Vm<'p> {
+    /// A VM calling the [standard library](crate::stdlib).
+    pub fn new(program: &'p Program) -> Self {
+        Self::with_host(program, Host::std())
+    }
+
+    /// A VM calling the functions of `host`, which should be the one `program` was compiled with.
+    pub fn with_host(program: &'p Program, host: Host) -> Self {
+        let natives = program
+            .natives
+            .iter()
+            .map(|name| match host.function(name) {
+                Some(function) => function.native.clone(),
+                None => {
+                    let name = name.clone();
+                    let error = if host.is_denied(&name) {
+                        Error::NotAllowed
+                    } else {
+                        Error::UnknownExponent
+                    };
+                    Rc::new(move |_: &[Value]| Err(error(name.clone()))) as Native
+                }
+            })
+            .collect();
+
+        Self {
+            program,
+            natives,
+            host,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Replaces the implementation of the native `name`, if the program calls it.
+    pub fn hook_native<F>(&mut self, name: &str, f: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, Error> + 'static,
+    {
+        if let Some(index) = self.program.natives.iter().position(|n| n == name) {
+            self.natives[index] = Rc::new(f);
+        }
+    }
+
+    /// Limits how many instructions a single [`Vm::call`] may run.
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.limits.steps = budget;
+    }
+
+    /// Limits what a single [`Vm::call`] may do, see [`Sandbox`](crate::sandbox::Sandbox).
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Calls the exponent, type constructor or native `name` with `args`.
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        let mut meter = Meter::new(self.limits);
+        let target = match self.program.lookup(name) {
+            Some(target) => target,
+            None => match crate::interpreter::vector_size(name) {
+                Some(size) => Target::Vector(size as u8),
+                None => {
+                    meter.precheck(name, &args)?;
+                    let result = self
+                        .host
+                        .call(name, &args)
+                        .unwrap_or_else(|| Err(Error::UnknownExponent(name.to_owned())))?;
+                    meter.made(&result)?;
+                    return Ok(result);
+                }
+            },
+        };
+
+        let function = match target {
+            Target::Function(function) => function,
+            Target::Layout(layout) => {
+                check_arity(
+                    name,
+                    self.program.layouts[layout as usize].fields.len(),
+                    &args,
+                )?;
+                self.stack = args;
+                return self.construct(layout);
+            }
+            Target::Vector(size) => {
+                check_arity(name, size as usize, &args)?;
+                self.stack = args;
+                return self.vector(size);
+            }
+            Target::Native(native) => {
+                meter.precheck(name, &args)?;
+                let result = (self.natives[native as usize])(&args)?;
+                meter.made(&result)?;
+                return Ok(result);
+            }
+        };
+
+        let arity = self.program.functions[function as usize].arity;
+        check_arity(name, arity, &args)?;
+        self.stack = args;
+        self.frames.clear();
+        let result = self.run(function, meter);
+        self.stack.clear();
+        result
+    }
+
+    fn enter(&mut self, function: u16, meter: &Meter) -> Result<(), Error> {
+        let f = &self.program.functions[function as usize];
+        let depth = meter.limits().call_depth;
+        if self.frames.len() >= depth {
+            return Err(Error::StackOverflow {
+                name: f.name.clone(),
+                depth,
+            });
+        }
+        let base = self.stack.len() - f.arity;
+        self.stack.resize(base + f.locals, Value::Void);
+        self.frames.push(Frame {
+            function,
+            ip: 0,
+            base,
+        });
+        Ok(())
+    }
+
+    fn run(&mut self, function: u16, mut meter: Meter) -> Result<Value, Error> {
+        self.enter(function, &meter)?;
+
+        loop {
+            meter.step()?;
+
+            let frame = self.frames.last_mut().unwrap();
+            let instruction = self.program.functions[frame.function as usize].code[frame.ip];
+            frame.ip += 1;
+            let base = frame.base;
+
+            match instruction {
+                Instruction::Constant(constant) => self
+                    .stack
+                    .push(self.program.constants[constant as usize].clone()),
+                Instruction::Load(slot) => {
+                    self.stack.push(self.stack[base + slot as usize].clone())
+                }
+                Instruction::Pop => {
+                    self.stack.pop();
+                }
+                Instruction::Field(name) => {
+                    let on = self.pop();
+                    let name = self.program.constants[name as usize].as_str().unwrap();
+                    self.stack.push(get_field(&on, name)?);
+                }
+                Instruction::LoadPath { slot, path } => {
+                    let Value::List(path) = &self.program.constants[path as usize] else {
+                        unreachable!("paths are lists of field names")
+                    };
+                    let mut on = Cow::Borrowed(&self.stack[base + slot as usize]);
+                    for name in path.iter().filter_map(Value::as_str) {
+                        on = match on {
+                            Cow::Borrowed(v) => match v.field(name) {
+                                Some(field) => Cow::Borrowed(field),
+                                None => Cow::Owned(get_field(v, name)?),
+                            },
+                            Cow::Owned(v) => Cow::Owned(get_field(&v, name)?),
+                        };
+                    }
+                    let value = on.into_owned();
+                    self.stack.push(value);
+                }
+                Instruction::Unary(op) => {
+                    let value = self.pop();
+                    self.stack.push(unary(op, value)?);
+                }
+                Instruction::Binary(op) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = binary(op, left, right)?;
+                    meter.made(&value)?;
+                    self.stack.push(value);
+                }
+                Instruction::ShortCircuit { op, target } => {
+                    let decided = matches!(
+                        (op, self.stack.last().and_then(Value::as_bool)),
+                        (BinaryOp::And, Some(false)) | (BinaryOp::Or, Some(true))
+                    );
+                    if decided {
+                        self.jump(target);
+                    }
+                }
+                Instruction::Jump(target) => self.jump(target),
+                Instruction::Assign { slot, path, op } => {
+                    let value = self.pop();
+                    let fields: Vec<&str> = match path {
+                        Some(path) => match &self.program.constants[path as usize] {
+                            Value::List(fields) => {
+                                fields.iter().filter_map(Value::as_str).collect()
+                            }
+                            _ => unreachable!("paths are lists of field names"),
+                        },
+                        None => Vec::new(),
+                    };
+                    let place = &mut self.stack[base + slot as usize];
+                    let new = assign_path(place, &fields, op, value)?;
+                    meter.made(&new)?;
+                    self.stack.push(new);
+                }
+                Instruction::Call(function) => self.enter(function, &meter)?,
+                Instruction::CallNative { native, args } => {
+                    let args = self.stack.split_off(self.stack.len() - args as usize);
+                    meter.precheck(&self.program.natives[native as usize], &args)?;
+                    let result = (self.natives[native as usize])(&args)?;
+                    meter.made(&result)?;
+                    self.stack.push(result);
+                }
+                Instruction::Construct(layout) => {
+                    let value = self.construct(layout)?;
+                    self.stack.push(value);
+                }
+                Instruction::Vector(size) => {
+                    let value = self.vector(size)?;
+                    self.stack.push(value);
+                }
+                Instruction::List(len) => {
+                    let items = Value::List(self.stack.split_off(self.stack.len() - len as usize));
+                    meter.made(&items)?;
+                    self.stack.push(items);
+                }
+                Instruction::Match(table) => {
+                    let variant = match self.pop() {
+                        Value::Variant(variant) => variant,
+                        on => return Err(Error::NotASum(on.type_name().to_owned())),
+                    };
+                    let arm = self.program.matches[table as usize]
+                        .iter()
+                        .find(|a| {
+                            (a.ty.is_empty() || a.ty == variant.ty) && a.variant == variant.variant
+                        })
+                        .ok_or_else(|| Error::NoMatchingArm {
+                            ty: variant.ty.clone(),
+                            variant: variant.variant.clone(),
+                        })?;
+                    // Values made by the host needn't have the declared fields, or in order.
+                    let declared = variant.fields.len() == arm.fields.len()
+                        && arm
+                            .fields
+                            .iter()
+                            .all(|f| variant.fields.iter().any(|(name, _)| name == f));
+                    if !declared {
+                        return Err(Error::TypeMismatch {
+                            name: format!("{}.{}", variant.ty, variant.variant),
+                            expected: describe_fields(arm.fields.iter()),
+                            found: describe_fields(variant.fields.iter().map(|(name, _)| name)),
+                        });
+                    }
+                    let slot = base + arm.slot as usize;
+                    for (name, value) in variant.fields {
+                        let i = arm.fields.iter().position(|f| *f == name).unwrap();
+                        self.stack[slot + i] = value;
+                    }
+                    self.jump(arm.target);
+                }
+                Instruction::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().unwrap();
+                    self.stack.truncate(frame.base);
+                    if self.frames.is_empty() {
+                        return Ok(result);
+                    }
+                    self.stack.push(result);
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("the compiler keeps the stack balanced")
+    }
+
+    fn jump(&mut self, target: u32) {
+        self.frames.last_mut().unwrap().ip = target as usize;
+    }
+
+    /// Builds a product or sum variant from the fields on top of the stack.
+    fn construct(&mut self, layout: u16) -> Result<Value, Error> {
+        let layout = &self.program.layouts[layout as usize];
+        let values = self.stack.split_off(self.stack.len() - layout.fields.len());
+        let fields = layout.fields.iter().cloned().zip(values).collect();
+        Ok(match &layout.variant {
+            Some(variant) => Value::Variant(Variant {
+                ty: layout.ty.clone(),
+                variant: variant.clone(),
+                fields,
+            }),
+            None => Value::Product(Product {
+                ty: layout.ty.clone(),
+                fields,
+            }),
+        })
+    }
+
+    fn vector(&mut self, size: u8) -> Result<Value, Error> {
+        let values = self.stack.split_off(self.stack.len() - size as usize);
+        let name = format!("Vec{}", size);
+        let components = values
+            .iter()
+            .map(|v| v.as_float().ok_or_else(|| mismatch(&name, "Float", v)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::vector(&components).unwrap())
+    }
+}
+
+/// Names the fields of a variant for errors, like ``fields `a`, `b` ``.
+fn describe_fields<'a>(fields: impl Iterator<Item = &'a String>) -> String {
+    let fields: Vec<_> = fields.map(|f| format!("`{}`", f)).collect();
+    match fields.len() {
+        0 => "no fields".to_owned(),
+        _ => format!("fields {}", fields.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vm;
+    use crate::{interpreter::Interpreter, value::Value, Error};
+
+    #[test]
+    fn test_matches_interpreter() {
+        let script = crate::parse(include_str!("scripts/example.dg")).unwrap();
+        let program = script.compile().unwrap();
+        let mut vm = Vm::new(&program);
+        let mut interpreter = Interpreter::new(&script);
+
+        let a = Value::product("Vector2", vec![("x", 1.0.into()), ("y", 2.0.into())]);
+        let b = Value::product("Vector2", vec![("x", 3.0.into()), ("y", (-4.0).into())]);
+        let increment = Value::variant("Message", "Increment", vec![("amount", 2.into())]);
+        let calls: Vec<(&str, Vec<Value>)> = vec![
+            ("Function", vec![3.into()]),
+            ("DoNothing", vec![]),
+            ("Square", vec![4.into()]),
+            ("Sum", vec![2.into(), 3.into()]),
+            ("Vector2.NegOne", vec![]),
+            ("Vector2.Add", vec![a.clone(), b.clone()]),
+            ("Vector2.Cross", vec![a.clone(), b.clone()]),
+            ("Vector2.Neg", vec![a.clone()]),
+            ("Vector2.MulF", vec![a.clone(), 2.0.into()]),
+            ("Vector2.Len", vec![a.clone()]),
+            ("Vector2.DistSq", vec![a.clone(), b.clone()]),
+            ("Vector2.Floor", vec![b.clone()]),
+            ("Option.Some", vec![1.into()]),
+            ("Update", vec![increment, 1.into()]),
+            ("View", vec![5.into()]),
+            ("Vec2", vec![1.into(), 2.into()]),
+            ("Float.Sqrt", vec![4.0.into()]),
+        ];
+        for (name, args) in calls {
+            assert_eq!(
+                vm.call(name, args.clone()).unwrap(),
+                interpreter.call(name, args).unwrap(),
+                "{}",
+                name
+            );
+        }
+
+        assert!(matches!(
+            vm.call("Vector2.Dist", vec![a.clone(), b]),
+            Err(Error::StackOverflow { .. })
+        ));
+        assert!(matches!(
+            vm.call("Square", vec![]),
+            Err(Error::WrongArity { .. })
+        ));
+    }
+
+    #[test]
+    fn test_locals() {
+        let script = crate::parse(
+            "* P v Vec2 tags List;
+             + Shape Circle r Float Rect w Float h Float;
+             ^ Area s Shape -> Float => $ s Circle * * r r 3.0 Rect * w h;
+             ^ Sum a Shape b Shape -> Float => + @Area a @Area b;
+             ^ Aspect s Shape -> Float => $ s Circle 1.0 Rect / w h;
+             ^ Move p P -> P => += p.v.x 1.5 *= p.v 2 += p.tags [\"moved\"] p;
+             ^ Either a Bool b Int -> Bool => || a == / 10 b 2;",
+        )
+        .unwrap();
+        let program = script.compile().unwrap();
+        let mut vm = Vm::new(&program);
+
+        let circle = Value::variant("Shape", "Circle", vec![("r", 2.0.into())]);
+        let rect = Value::variant("Shape", "Rect", vec![("w", 2.0.into()), ("h", 3.0.into())]);
+        assert_eq!(
+            vm.call("Sum", vec![circle, rect]).unwrap(),
+            Value::Float(18.0)
+        );
+
+        // Fields from the host are bound by name, whatever their order.
+        let mut interpreter = Interpreter::new(&script);
+        let rect = Value::variant("Shape", "Rect", vec![("h", 3.0.into()), ("w", 2.0.into())]);
+        assert_eq!(
+            vm.call("Aspect", vec![rect.clone()]).unwrap(),
+            interpreter.call("Aspect", vec![rect]).unwrap()
+        );
+        let circle = |fields: Vec<(&str, Value)>| Value::variant("Shape", "Circle", fields);
+        let extra = circle(vec![
+            ("r", 2.0.into()),
+            ("x", 0.0.into()),
+            ("y", 0.0.into()),
+        ]);
+        assert_eq!(
+            vm.call("Area", vec![extra]).unwrap_err().to_string(),
+            "`Shape.Circle` expects fields `r` but found fields `r`, `x`, `y`"
+        );
+        assert!(matches!(
+            vm.call("Area", vec![circle(vec![])]),
+            Err(Error::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            vm.call("Area", vec![circle(vec![("radius", 2.0.into())])]),
+            Err(Error::TypeMismatch { .. })
+        ));
+
+        let p = Value::product(
+            "P",
+            vec![
+                ("v", Value::Vec2([1.0, 1.0])),
+                ("tags", Value::List(vec![])),
+            ],
+        );
+        let moved = vm.call("Move", vec![p]).unwrap();
+        assert_eq!(moved.to_string(), "@P @Vec2 5.0 2.0 [\"moved\"]");
+
+        // `||` skips the division by zero.
+        assert_eq!(
+            vm.call("Either", vec![true.into(), 0.into()]).unwrap(),
+            Value::Bool(true)
+        );
+        assert!(matches!(
+            vm.call("Either", vec![false.into(), 0.into()]),
+            Err(Error::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_budget_and_hooks() {
+        let script = crate::parse(include_str!("scripts/example.dg")).unwrap();
+        let program = script.compile().unwrap();
+        let mut vm = Vm::new(&program);
+        let v = Value::product("Vector2", vec![("x", 1.0.into()), ("y", 2.0.into())]);
+
+        vm.set_budget(Some(3));
+        assert!(matches!(
+            vm.call("Vector2.Add", vec![v.clone(), v.clone()]),
+            Err(Error::BudgetExceeded(3))
+        ));
+        vm.set_budget(Some(100));
+        assert!(vm.call("Vector2.Add", vec![v.clone(), v.clone()]).is_ok());
+
+        vm.hook_native("Float.Sqrt", |_| Ok(Value::Float(42.0)));
+        assert_eq!(vm.call("Vector2.Len", vec![v]).unwrap(), Value::Float(42.0));
+    }
+}